@@ -0,0 +1,434 @@
+//! Settings of the server. Each layer overrides the one before it: built-in defaults, a TOML
+//! file, environment variables and command line flags.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use log::LevelFilter;
+use serde::Deserialize;
+use sqlx::pool::PoolOptions;
+
+/// Read when neither `--config` nor `KEIKO_CONFIG` names a file, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "keiko.toml";
+/// Shortest wait between purges of the trash, so they never run back to back.
+const MIN_PURGE_INTERVAL_SECS: u64 = 1;
+
+/// Flags and environment variables, which take precedence over the config file.
+#[derive(Args, Clone, Default)]
+pub struct ConfigArgs {
+    /// TOML file to read settings from [default: keiko.toml, if present]
+    #[arg(long, env = "KEIKO_CONFIG")]
+    config: Option<PathBuf>,
+    /// Database to use, `postgres://...` or `sqlite://path`
+    #[arg(long, env = "DATABASE_URL")]
+    database: Option<String>,
+    /// Address to listen on
+    #[arg(long, env = "KEIKO_HOST")]
+    host: Option<String>,
+    /// Port to listen on
+    #[arg(long, env = "KEIKO_PORT")]
+    port: Option<u16>,
+    /// Most connections the database pool opens
+    #[arg(long, env = "KEIKO_MAX_CONNECTIONS")]
+    max_connections: Option<u32>,
+    /// Connections the database pool keeps open when idle
+    #[arg(long, env = "KEIKO_MIN_CONNECTIONS")]
+    min_connections: Option<u32>,
+    /// Seconds to wait for a free database connection
+    #[arg(long, env = "KEIKO_ACQUIRE_TIMEOUT_SECS")]
+    acquire_timeout_secs: Option<u64>,
+    /// Seconds before an idle database connection is closed, 0 to keep them open
+    #[arg(long, env = "KEIKO_IDLE_TIMEOUT_SECS")]
+    idle_timeout_secs: Option<u64>,
+    /// One of off, error, warn, info, debug or trace
+    #[arg(long, env = "KEIKO_LOG_LEVEL")]
+    log_level: Option<LevelFilter>,
+    /// Write logs as `text` or as one `json` object per line
+    #[arg(long, env = "KEIKO_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+    /// Origins allowed to call the API from a browser, comma separated. All if unset
+    #[arg(long, env = "KEIKO_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+    /// PEM certificate chain, serves HTTPS along with --tls-key
+    #[arg(long, env = "KEIKO_TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key of --tls-cert
+    #[arg(long, env = "KEIKO_TLS_KEY")]
+    tls_key: Option<PathBuf>,
+    /// Days deleted courses, cards and quizzes stay restorable, 0 to keep them for good
+    #[arg(long, env = "KEIKO_TRASH_RETENTION_DAYS")]
+    trash_retention_days: Option<u32>,
+    /// Seconds between purges of the trash
+    #[arg(long, env = "KEIKO_TRASH_PURGE_INTERVAL_SECS")]
+    trash_purge_interval_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub log: LogConfig,
+    pub trash: TrashConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Empty allows every origin.
+    pub cors_origins: Vec<String>,
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// 0 keeps idle connections open.
+    pub idle_timeout_secs: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Applied again from the config file on SIGHUP.
+    pub level: LevelFilter,
+    pub format: LogFormat,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrashConfig {
+    /// 0 keeps trashed rows until they are restored.
+    pub retention_days: u32,
+    pub purge_interval_secs: u64,
+}
+
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_owned(),
+            port: 1107,
+            cors_origins: Vec::new(),
+            tls: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "postgres://postgres@localhost:5432/keiko".to_owned(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            format: LogFormat::Text,
+        }
+    }
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            purge_interval_secs: 3600,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, if any, and applies `args` on top.
+    pub fn load(args: ConfigArgs) -> Result<Self, String> {
+        let path = args.config.clone().or_else(|| {
+            let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+            default.exists().then_some(default)
+        });
+
+        let mut config = match path {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+
+        config.apply(args)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn apply(&mut self, args: ConfigArgs) -> Result<(), String> {
+        let ConfigArgs {
+            config: _,
+            database,
+            host,
+            port,
+            max_connections,
+            min_connections,
+            acquire_timeout_secs,
+            idle_timeout_secs,
+            log_level,
+            log_format,
+            cors_origins,
+            tls_cert,
+            tls_key,
+            trash_retention_days,
+            trash_purge_interval_secs,
+        } = args;
+
+        let server = &mut self.server;
+        if let Some(host) = host {
+            server.host = host;
+        }
+        server.port = port.unwrap_or(server.port);
+        if let Some(origins) = cors_origins {
+            server.cors_origins = origins.into_iter().filter(|o| !o.is_empty()).collect();
+        }
+        match (tls_cert, tls_key, &mut server.tls) {
+            (None, None, _) => {}
+            (Some(cert), Some(key), tls) => *tls = Some(TlsConfig { cert, key }),
+            (cert, key, Some(tls)) => {
+                if let Some(cert) = cert {
+                    tls.cert = cert;
+                }
+                if let Some(key) = key {
+                    tls.key = key;
+                }
+            }
+            _ => return Err("--tls-cert and --tls-key must be given together".to_owned()),
+        }
+
+        let db = &mut self.database;
+        if let Some(url) = database {
+            db.url = url;
+        }
+        db.max_connections = max_connections.unwrap_or(db.max_connections);
+        db.min_connections = min_connections.unwrap_or(db.min_connections);
+        db.acquire_timeout_secs = acquire_timeout_secs.unwrap_or(db.acquire_timeout_secs);
+        db.idle_timeout_secs = idle_timeout_secs.unwrap_or(db.idle_timeout_secs);
+
+        self.log.level = log_level.unwrap_or(self.log.level);
+        self.log.format = log_format.unwrap_or(self.log.format);
+
+        let trash = &mut self.trash;
+        trash.retention_days = trash_retention_days.unwrap_or(trash.retention_days);
+        trash.purge_interval_secs = trash_purge_interval_secs.unwrap_or(trash.purge_interval_secs);
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let db = &self.database;
+        if db.max_connections == 0 {
+            return Err("database.max_connections must be at least 1".to_owned());
+        }
+        if db.min_connections > db.max_connections {
+            return Err("database.min_connections must not exceed max_connections".to_owned());
+        }
+        if let Some(origin) = self
+            .server
+            .cors_origins
+            .iter()
+            .find(|o| o.parse::<ntex::http::Uri>().is_err())
+        {
+            return Err(format!("invalid CORS origin {}", origin));
+        }
+        if self.trash.purge_interval_secs < MIN_PURGE_INTERVAL_SECS {
+            return Err(format!(
+                "trash.purge_interval_secs must be at least {}",
+                MIN_PURGE_INTERVAL_SECS
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl TrashConfig {
+    /// Time between purges, never shorter than `MIN_PURGE_INTERVAL_SECS` even if validation
+    /// was skipped.
+    pub fn purge_interval(&self) -> Duration {
+        Duration::from_secs(self.purge_interval_secs.max(MIN_PURGE_INTERVAL_SECS))
+    }
+}
+
+impl DatabaseConfig {
+    /// Pool sizing and timeouts, for either backend.
+    pub fn pool_options<DB: sqlx::Database>(&self) -> PoolOptions<DB> {
+        PoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(
+                (self.idle_timeout_secs > 0).then(|| Duration::from_secs(self.idle_timeout_secs)),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purge_interval_is_never_zero() {
+        let mut config = Config::default();
+        config.trash.purge_interval_secs = 0;
+        assert!(config.validate().is_err());
+        assert_eq!(
+            config.trash.purge_interval(),
+            Duration::from_secs(MIN_PURGE_INTERVAL_SECS)
+        );
+
+        config.trash.purge_interval_secs = 600;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.trash.purge_interval(), Duration::from_secs(600));
+    }
+
+    /// Writes `text` to a config file of its own and loads it under `args`.
+    fn load(name: &str, text: &str, args: ConfigArgs) -> Result<Config, String> {
+        let path = std::env::temp_dir().join(format!("keiko-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let config = Config::load(ConfigArgs {
+            config: Some(path.clone()),
+            ..args
+        });
+        std::fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn defaults_apply_without_a_file_or_flags() {
+        let mut config = Config::default();
+        config.apply(ConfigArgs::default()).unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 1107);
+        assert!(config.server.tls.is_none());
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.log.level, LevelFilter::Info);
+        assert_eq!(config.trash.retention_days, 30);
+    }
+
+    #[test]
+    fn file_overrides_defaults_and_flags_override_the_file() {
+        let text = r#"
+            [server]
+            host = "127.0.0.1"
+            port = 8000
+
+            [database]
+            max_connections = 10
+
+            [trash]
+            retention_days = 7
+        "#;
+        let args = ConfigArgs {
+            port: Some(9000),
+            min_connections: Some(2),
+            log_format: Some(LogFormat::Json),
+            ..Default::default()
+        };
+        let config = load("layers", text, args).unwrap();
+
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.database.max_connections, 10);
+        assert_eq!(config.database.min_connections, 2);
+        assert_eq!(config.database.acquire_timeout_secs, 30);
+        assert_eq!(config.log.format, LogFormat::Json);
+        assert_eq!(config.trash.retention_days, 7);
+        assert_eq!(config.trash.purge_interval_secs, 3600);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected_with_the_file_name() {
+        let error = load("unknown", "[server]\nprot = 8000\n", ConfigArgs::default()).unwrap_err();
+        assert!(error.contains("keiko-unknown-"), "{}", error);
+        assert!(error.contains("prot"), "{}", error);
+    }
+
+    #[test]
+    fn tls_flags_come_in_pairs_unless_the_file_has_both() {
+        let cert_only = || ConfigArgs {
+            tls_cert: Some("flag.crt".into()),
+            ..Default::default()
+        };
+        assert!(Config::default().apply(cert_only()).is_err());
+
+        let mut config = Config::default();
+        config
+            .apply(ConfigArgs {
+                tls_key: Some("flag.key".into()),
+                ..cert_only()
+            })
+            .unwrap();
+        let tls = config.server.tls.unwrap();
+        assert_eq!(tls.cert, PathBuf::from("flag.crt"));
+        assert_eq!(tls.key, PathBuf::from("flag.key"));
+
+        let text = "[server.tls]\ncert = \"file.crt\"\nkey = \"file.key\"\n";
+        let tls = load("tls", text, cert_only()).unwrap().server.tls.unwrap();
+        assert_eq!(tls.cert, PathBuf::from("flag.crt"));
+        assert_eq!(tls.key, PathBuf::from("file.key"));
+    }
+
+    #[test]
+    fn empty_cors_origins_are_dropped() {
+        let mut config = Config::default();
+        config
+            .apply(ConfigArgs {
+                cors_origins: Some(vec!["https://keiko.example".to_owned(), String::new()]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(config.server.cors_origins, ["https://keiko.example"]);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let mut config = Config::default();
+        config.database.max_connections = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.database.min_connections = 6;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.server.cors_origins = vec!["not an origin".to_owned()];
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Log output through `tracing`, as text or one JSON object per line. Records of the `log`
+//! crate, which ntex and this binary use, are forwarded to it.
+
+use std::io::IsTerminal;
+
+use log::{error, info, LevelFilter};
+use ntex::server::Signal;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{filter, fmt, reload, EnvFilter, Layer, Registry};
+
+use crate::config::{Config, ConfigArgs, LogConfig, LogFormat};
+
+/// Changes the level of the installed subscriber.
+pub struct LogHandle(reload::Handle<EnvFilter, Registry>);
+
+/// Installs the global subscriber. Every span is logged once it closes, with how long it took,
+/// so each request gets a line with its id, status and duration.
+pub fn init(config: &LogConfig) -> Result<LogHandle, String> {
+    let (filter, handle) = reload::Layer::new(env_filter(config.level));
+
+    let output = match config.format {
+        LogFormat::Text => fmt::layer()
+            .with_ansi(std::io::stdout().is_terminal())
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_span_list(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .try_init()
+        .map_err(|e| e.to_string())?;
+    log::set_max_level(config.level);
+
+    Ok(LogHandle(handle))
+}
+
+impl LogHandle {
+    pub fn set_level(&self, level: LevelFilter) -> Result<(), String> {
+        self.0
+            .reload(env_filter(level))
+            .map_err(|e| e.to_string())?;
+        log::set_max_level(level);
+
+        Ok(())
+    }
+
+    /// Reads the settings again on every SIGHUP and applies their log level.
+    pub fn reload_on_hangup(self, args: ConfigArgs) {
+        ntex::rt::spawn(async move {
+            while let Ok(signal) = ntex::server::signal().await {
+                if signal != Signal::Hup {
+                    continue;
+                }
+
+                match Config::load(args.clone()).and_then(|config| {
+                    self.set_level(config.log.level)?;
+                    Ok(config.log.level)
+                }) {
+                    Ok(level) => info!("Log level set to {}", level),
+                    Err(e) => error!("Failed to reload settings: {}", e),
+                }
+            }
+        });
+    }
+}
+
+fn env_filter(level: LevelFilter) -> EnvFilter {
+    let level = match level {
+        LevelFilter::Off => filter::LevelFilter::OFF,
+        LevelFilter::Error => filter::LevelFilter::ERROR,
+        LevelFilter::Warn => filter::LevelFilter::WARN,
+        LevelFilter::Info => filter::LevelFilter::INFO,
+        LevelFilter::Debug => filter::LevelFilter::DEBUG,
+        LevelFilter::Trace => filter::LevelFilter::TRACE,
+    };
+
+    EnvFilter::default().add_directive(level.into())
+}
@@ -1,96 +1,336 @@
-#[cfg(not(debug_assertions))]
-use clap::Parser;
-use fern::colors::{Color, ColoredLevelConfig};
-use log::error;
-use ntex::web::middleware::Logger;
+// The HTTPS server service is nested deep enough to need more than the default 128.
+#![recursion_limit = "256"]
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use log::{error, info};
 use ntex::web::{self, App, HttpServer, ServiceConfig};
 use ntex_cors::Cors;
-use routes::{card, course, health, quiz, KeikoDatabase};
-use sqlx::postgres::PgPoolOptions;
-use sqlx::Executor;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+use routes::auth_api::AuthAPI;
+use routes::delimited::{self, DelimitedFormat, DelimitedImportOptions};
+use routes::import_api::ImportAPI;
+use routes::metrics::RequestMetrics;
+use routes::request_id::{RequestId, REQUEST_ID_HEADER};
+use routes::session::Authentication;
+use routes::trash_api::TrashAPI;
+#[cfg(feature = "sqlite")]
+use routes::KeikoSqlite;
+use routes::{
+    auth, card, category, course, health, import, list, metrics, migrate, quiz, review, search,
+    trash, KeikoDatabase, KeikoError,
+};
+use sqlx::Postgres;
+
+use config::{Config, ConfigArgs, ServerConfig, TrashConfig};
+
+mod config;
+mod logging;
 
-#[cfg(not(debug_assertions))]
 #[derive(Parser)]
-struct Config {
-    #[arg(default_value = "")]
-    db_pass: String,
-    #[arg(default_value = "localhost")]
-    addr: String,
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage database schema migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Import cards from a CSV or TSV file
+    Import(ImportArgs),
+}
+
+#[derive(Subcommand)]
+enum MigrateAction {
+    /// Apply every pending migration
+    Up,
+    /// Revert the latest applied migration
+    Down,
+    /// List migrations and whether they have been applied
+    Status,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    file: PathBuf,
+    /// User the cards are imported as
+    #[arg(long)]
+    username: String,
+    /// Read tab separated values instead of CSV
+    #[arg(long)]
+    tsv: bool,
+    /// The first row is data, columns are picked by index
+    #[arg(long)]
+    no_headers: bool,
+    /// Report errors and duplicates without writing anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Question column, by header name or zero-based index
+    #[arg(long)]
+    question: Option<String>,
+    /// Answer column, by header name or zero-based index
+    #[arg(long)]
+    answer: Option<String>,
+    /// Category column, by header name or zero-based index
+    #[arg(long)]
+    category: Option<String>,
+    /// Course code column, by header name or zero-based index
+    #[arg(long)]
+    course_code: Option<String>,
+    /// Hint column, by header name or zero-based index
+    #[arg(long)]
+    hint: Option<String>,
+}
+
+async fn import<S: ImportAPI + AuthAPI>(stack: S, args: ImportArgs) -> routes::KeikoResult<()> {
+    let data = std::fs::read(&args.file)
+        .map_err(|e| KeikoError::Validation(format!("{}: {}", args.file.display(), e)))?;
+    let user = stack.get_user_by_username(&args.username).await?;
+    let options = DelimitedImportOptions {
+        format: if args.tsv {
+            DelimitedFormat::Tsv
+        } else {
+            DelimitedFormat::Csv
+        },
+        has_headers: !args.no_headers,
+        dry_run: args.dry_run,
+        question: args.question,
+        answer: args.answer,
+        category: args.category,
+        course_code: args.course_code,
+        hint: args.hint,
+    };
+
+    let report = delimited::import(&stack, &user, &data, &options).await?;
+
+    for e in &report.errors {
+        error!("Row {}: {}", e.row, e.message);
+    }
+    for d in &report.duplicates {
+        info!("Skipping row {}: {}", d.row, d.message);
+    }
+
+    match report.summary {
+        Some(summary) => info!("Imported {} cards", summary.cards_created),
+        None if report.errors.is_empty() => info!(
+            "{} of {} rows would be imported",
+            report.rows - report.duplicates.len() as u64,
+            report.rows
+        ),
+        None => {
+            return Err(KeikoError::Validation(format!(
+                "{} of {} rows are invalid, nothing was imported",
+                report.errors.len(),
+                report.rows
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+async fn migrate(pool: &sqlx::PgPool, action: MigrateAction) -> routes::KeikoResult<()> {
+    match action {
+        MigrateAction::Up => {
+            migrate::up(pool).await?;
+            info!("Database schema is up to date");
+        }
+        MigrateAction::Down => match migrate::down(pool).await? {
+            Some(version) => info!("Reverted migration {}", version),
+            None => info!("No migrations to revert"),
+        },
+        MigrateAction::Status => {
+            for m in migrate::status(pool).await? {
+                println!("{:>4} {:<20} {:?}", m.version, m.description, m.state);
+            }
+        }
+    }
+
+    Ok(())
 }
 
 #[ntex::main]
 async fn main() -> std::io::Result<()> {
-    #[cfg(not(debug_assertions))]
-    let args = Config::parse();
-
-    let log_colors = ColoredLevelConfig::new()
-        .info(Color::Green)
-        .warn(Color::Yellow)
-        .error(Color::Red)
-        .trace(Color::Magenta)
-        .debug(Color::Cyan);
-
-    fern::Dispatch::new()
-        .format(move |out, message, record| {
-            out.finish(format_args!(
-                "[{} {}] {}",
-                log_colors.color(record.level()),
-                record.target(),
-                message
-            ))
-        })
-        .level(log::LevelFilter::Trace)
-        .chain(std::io::stdout())
-        .apply()
-        .unwrap_or_else(|e| {
-            panic!("Failed to initialize logging: {:?}", e);
-        });
+    let args = Cli::parse();
+    let config = Config::load(args.config.clone()).map_err(std::io::Error::other)?;
 
-    #[cfg(debug_assertions)]
-    let connection_string = "postgres://postgres@localhost:5432/keiko";
+    let log = logging::init(&config.log).map_err(std::io::Error::other)?;
+    log.reload_on_hangup(args.config.clone());
 
-    #[cfg(not(debug_assertions))]
-    let connection_string = format!(
-        "postgres://postgres:{}@{}:5432/keiko",
-        args.db_pass, args.addr
-    );
+    if config.database.url.starts_with("sqlite:") {
+        return serve_sqlite(config, args.command).await;
+    }
 
-    #[cfg(not(debug_assertions))]
-    let connection_string = connection_string.as_str();
-
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(connection_string)
+    let pool = config
+        .database
+        .pool_options::<Postgres>()
+        .connect(&config.database.url)
         .await
         .unwrap_or_else(|e| {
             panic!("Failed to initialize database: {:?}", e);
         });
 
-    pool.execute(routes::SCHEMA).await.unwrap_or_else(|e| {
-        error!("Failed to initialize schema: {:?}", e);
+    if let Some(command) = args.command {
+        return match command {
+            Command::Migrate { action } => migrate(&pool, action).await,
+            Command::Import(args) => import(KeikoDatabase::new(pool), args).await,
+        }
+        .map_err(std::io::Error::other);
+    }
+
+    migrate::up(&pool).await.unwrap_or_else(|e| {
+        error!("Failed to migrate schema: {}", e);
         std::process::exit(1);
     });
 
-    let config = move |cfg: &mut ServiceConfig| {
+    let stack = KeikoDatabase::new(pool);
+    purge_trash(stack.clone(), &config.trash);
+    let services = move |cfg: &mut ServiceConfig| {
+        cfg.state(stack.clone());
+        metrics::service::<KeikoDatabase>(cfg);
         cfg.service(
             web::scope("/api")
-                .state(KeikoDatabase::new(pool))
-                .configure(health::service)
+                .wrap(Authentication::new(stack.clone()))
+                .state(stack.clone())
+                .configure(health::service::<KeikoDatabase>)
+                .configure(auth::service::<KeikoDatabase>)
                 .configure(card::service::<KeikoDatabase>)
+                .configure(category::service::<KeikoDatabase>)
                 .configure(course::service::<KeikoDatabase>)
-                .configure(quiz::service::<KeikoDatabase>),
+                .configure(import::service::<KeikoDatabase>)
+                .configure(quiz::service::<KeikoDatabase>)
+                .configure(review::service::<KeikoDatabase>)
+                .configure(search::service::<KeikoDatabase>)
+                .configure(trash::service::<KeikoDatabase>),
+        );
+    };
+
+    serve(&config.server, services).await
+}
+
+#[cfg(feature = "sqlite")]
+async fn serve_sqlite(config: Config, command: Option<Command>) -> std::io::Result<()> {
+    let stack = KeikoSqlite::connect(&config.database.url, config.database.pool_options())
+        .await
+        .unwrap_or_else(|e| {
+            panic!("Failed to initialize database: {:?}", e);
+        });
+
+    match command {
+        Some(Command::Migrate { .. }) => {
+            return Err(std::io::Error::other(
+                "migrate only works on Postgres, SQLite databases are migrated when opened",
+            ))
+        }
+        Some(Command::Import(args)) => {
+            return import(stack, args).await.map_err(std::io::Error::other)
+        }
+        None => {}
+    }
+    purge_trash(stack.clone(), &config.trash);
+
+    let services = move |cfg: &mut ServiceConfig| {
+        cfg.state(stack.clone());
+        metrics::service::<KeikoSqlite>(cfg);
+        cfg.service(
+            web::scope("/api")
+                .wrap(Authentication::new(stack.clone()))
+                .state(stack.clone())
+                .configure(health::service::<KeikoSqlite>)
+                .configure(auth::service::<KeikoSqlite>)
+                .configure(card::service::<KeikoSqlite>)
+                .configure(category::service::<KeikoSqlite>)
+                .configure(course::service::<KeikoSqlite>)
+                .configure(import::service::<KeikoSqlite>)
+                .configure(quiz::service::<KeikoSqlite>)
+                .configure(review::service::<KeikoSqlite>)
+                .configure(search::service::<KeikoSqlite>)
+                .configure(trash::service::<KeikoSqlite>),
         );
     };
 
-    HttpServer::new(move || {
-        let cors = Cors::default();
+    serve(&config.server, services).await
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn serve_sqlite(_config: Config, _command: Option<Command>) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "SQLite databases need keiko-backend built with the sqlite feature",
+    ))
+}
+
+/// Deletes what has been in the trash longer than the retention for good, now and then every
+/// `purge_interval_secs`.
+fn purge_trash<S: TrashAPI + Clone>(stack: S, trash: &TrashConfig) {
+    if trash.retention_days == 0 {
+        return;
+    }
+
+    let retention = chrono::TimeDelta::days(i64::from(trash.retention_days));
+    let interval = trash.purge_interval();
+    ntex::rt::spawn(async move {
+        loop {
+            match stack.purge(chrono::Utc::now() - retention).await {
+                Ok(purged) if purged != Default::default() => info!(
+                    "Purged {} courses, {} cards and {} quizzes from the trash",
+                    purged.courses, purged.cards, purged.quizzes
+                ),
+                Ok(_) => {}
+                Err(e) => error!("Failed to purge the trash: {}", e),
+            }
+            ntex::time::sleep(interval).await;
+        }
+    });
+}
+
+async fn serve<F>(server: &ServerConfig, services: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut ServiceConfig) + Clone + Send + 'static,
+{
+    let origins = server.cors_origins.clone();
+    let http = HttpServer::new(move || {
+        let cors = origins
+            .iter()
+            .fold(Cors::new(), |cors, origin| cors.allowed_origin(origin))
+            .expose_headers([
+                list::TOTAL_COUNT_HEADER,
+                list::NEXT_CURSOR_HEADER,
+                REQUEST_ID_HEADER,
+            ])
+            .finish();
 
         App::new()
             .wrap(cors)
-            .wrap(Logger::default())
-            .configure(config.clone())
-    })
-    .bind(("0.0.0.0", 1107))?
-    .run()
-    .await
+            .wrap(RequestMetrics)
+            .wrap(RequestId)
+            .configure(services.clone())
+    });
+
+    let addr = (server.host.as_str(), server.port);
+    let http = match &server.tls {
+        Some(tls) => {
+            let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls())
+                .map_err(std::io::Error::other)?;
+            acceptor
+                .set_certificate_chain_file(&tls.cert)
+                .and_then(|_| acceptor.set_private_key_file(&tls.key, SslFiletype::PEM))
+                .map_err(|e| std::io::Error::other(format!("TLS: {}", e)))?;
+
+            info!("Listening on https://{}:{}", server.host, server.port);
+            http.bind_openssl(addr, acceptor)?
+        }
+        None => {
+            info!("Listening on http://{}:{}", server.host, server.port);
+            http.bind(addr)?
+        }
+    };
+
+    http.run().await
 }
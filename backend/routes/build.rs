@@ -0,0 +1,39 @@
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+
+    // `/version` reports the commit and time of the build. Builds without a git checkout, such
+    // as container images, can pass the commit in `KEIKO_GIT_COMMIT`, and reproducible builds
+    // the time in `SOURCE_DATE_EPOCH`.
+    println!("cargo:rerun-if-env-changed=KEIKO_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let commit = std::env::var("KEIKO_GIT_COMMIT")
+        .ok()
+        .or_else(|| git(&["rev-parse", "--short", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_owned());
+    println!("cargo:rustc-env=KEIKO_GIT_COMMIT={}", commit);
+
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/refs/heads", git_dir);
+    }
+
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(chrono::Utc::now);
+    println!(
+        "cargo:rustc-env=KEIKO_BUILD_TIME={}",
+        built_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+
+    (output.status.success() && !text.trim().is_empty()).then(|| text.trim().to_owned())
+}
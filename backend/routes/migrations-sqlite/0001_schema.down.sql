@@ -0,0 +1,13 @@
+DROP VIEW quizzes_view;
+DROP VIEW courses_view;
+DROP VIEW course_roles;
+
+DROP TABLE quiz_hints;
+DROP TABLE quiz_answers;
+DROP TABLE quizzes;
+DROP TABLE cards;
+DROP TABLE course_members;
+DROP TABLE courses;
+DROP TABLE api_tokens;
+DROP TABLE sessions;
+DROP TABLE users;
@@ -0,0 +1,254 @@
+-- Schema of `KeikoSqlite`, equivalent to the Postgres migrations up to 0009 minus the tables
+-- behind review, search and import. Ids are generated by the backend, timestamps are RFC 3339
+-- text in UTC so they compare in order, and the plpgsql functions are transactions in Rust.
+
+CREATE TABLE users
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL
+);
+
+CREATE TABLE sessions
+(
+    token_hash TEXT NOT NULL PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+CREATE INDEX sessions_user_id_idx ON sessions (user_id);
+
+CREATE TABLE api_tokens
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scope TEXT NOT NULL CHECK (scope IN ('read', 'cards:write', 'admin')),
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    last_used_at TEXT,
+    expires_at TEXT
+);
+
+CREATE INDEX api_tokens_user_id_idx ON api_tokens (user_id);
+
+CREATE TABLE courses
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    user_id BLOB REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    course_code TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    updated_at TEXT
+);
+
+CREATE INDEX courses_user_id_idx ON courses (user_id);
+
+CREATE TABLE course_members
+(
+    course_id BLOB NOT NULL REFERENCES courses (id) ON DELETE CASCADE,
+    user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('viewer', 'editor')),
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    PRIMARY KEY (course_id, user_id)
+);
+
+CREATE INDEX course_members_user_id_idx ON course_members (user_id);
+
+CREATE TABLE cards
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    course_code TEXT NOT NULL,
+    category TEXT NOT NULL,
+    hint TEXT,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    updated_at TEXT
+);
+
+CREATE INDEX cards_course_code_category_idx ON cards (course_code, category);
+
+CREATE TABLE quizzes
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    user_id BLOB REFERENCES users (id) ON DELETE CASCADE,
+    course_code TEXT NOT NULL,
+    category TEXT NOT NULL,
+    current_index INTEGER DEFAULT 0 NOT NULL,
+    correct_count INTEGER DEFAULT 0 NOT NULL,
+    is_completed BOOLEAN DEFAULT FALSE NOT NULL,
+    hint_used BOOLEAN DEFAULT FALSE NOT NULL,
+    started_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    completed_at TEXT,
+    seed INTEGER DEFAULT (abs(random() % 9007199254740991)) NOT NULL,
+    distractor_count INTEGER DEFAULT 3 NOT NULL CHECK (distractor_count BETWEEN 1 AND 10),
+    hint_budget INTEGER DEFAULT 3 NOT NULL CHECK (hint_budget >= 0),
+    hint_penalty REAL DEFAULT 0.5 NOT NULL CHECK (hint_penalty BETWEEN 0 AND 1)
+);
+
+CREATE INDEX quizzes_user_id_idx ON quizzes (user_id);
+
+CREATE TABLE quiz_answers
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    quiz_id BLOB NOT NULL REFERENCES quizzes (id) ON DELETE CASCADE,
+    card_id BLOB NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+    given_answer TEXT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    hint_used BOOLEAN DEFAULT FALSE NOT NULL,
+    elapsed_ms INTEGER NOT NULL CHECK (elapsed_ms >= 0),
+    answered_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    UNIQUE (quiz_id, card_id)
+);
+
+CREATE TABLE quiz_hints
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    quiz_id BLOB NOT NULL REFERENCES quizzes (id) ON DELETE CASCADE,
+    card_id BLOB NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    revealed TEXT NOT NULL,
+    used_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    UNIQUE (quiz_id, card_id, kind)
+);
+
+-- Stands in for the `course_role()` function: one row per user and course they have a role
+-- in. Courses without an owner predate user accounts and every user owns them.
+CREATE VIEW course_roles AS
+SELECT c.id AS course_id, u.id AS user_id, 'owner' AS role
+FROM courses c
+JOIN users u ON c.user_id IS NULL OR c.user_id = u.id
+UNION ALL
+SELECT m.course_id, m.user_id, m.role
+FROM course_members m
+JOIN courses c ON c.id = m.course_id
+WHERE c.user_id IS NOT NULL AND c.user_id <> m.user_id;
+
+-- Progress is worked out as a fraction first, then rounded half to even as Postgres rounds
+-- double precision values. `categories` is a JSON array.
+CREATE VIEW courses_view AS
+SELECT
+    id,
+    user_id,
+    name,
+    course_code,
+    description,
+    created_at,
+    updated_at,
+    questions,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress,
+    categories
+FROM (
+    SELECT
+        c.*,
+        COALESCE(f.questions, 0) AS questions,
+        CASE
+            WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0.0
+            ELSE CAST(q.completed_quizzes AS REAL) / q.total_quizzes * 100
+        END AS progress,
+        COALESCE(cat.categories, json_array()) AS categories
+    FROM courses c
+    LEFT JOIN (
+        SELECT course_code, COUNT(*) AS questions
+        FROM cards
+        GROUP BY course_code
+    ) f ON c.course_code = f.course_code
+    LEFT JOIN (
+        SELECT
+            course_code,
+            COUNT(*) AS total_quizzes,
+            COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+        FROM quizzes
+        GROUP BY course_code
+    ) q ON c.course_code = q.course_code
+    LEFT JOIN (
+        SELECT course_code, json_group_array(category) AS categories
+        FROM (SELECT DISTINCT course_code, category FROM quizzes ORDER BY category)
+        GROUP BY course_code
+    ) cat ON c.course_code = cat.course_code
+);
+
+CREATE VIEW quizzes_view AS
+SELECT
+    id,
+    user_id,
+    course_code,
+    category,
+    current_index,
+    correct_count,
+    is_completed,
+    hint_used,
+    hint_budget,
+    hints_used,
+    score,
+    started_at,
+    completed_at,
+    card_count,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress
+FROM (
+    SELECT
+        q.id,
+        q.user_id,
+        q.course_code,
+        q.category,
+        q.current_index,
+        CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.correct END AS correct_count,
+        q.is_completed,
+        q.hint_used,
+        q.hint_budget,
+        COALESCE(h.hints_used, 0) AS hints_used,
+        CAST(CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.score END AS REAL) AS score,
+        q.started_at,
+        q.completed_at,
+        COALESCE(f.card_count, 0) AS card_count,
+        CASE
+            WHEN q.is_completed THEN 100.0
+            WHEN COALESCE(f.card_count, 0) = 0 THEN 0.0
+            WHEN a.answered IS NOT NULL
+                THEN MIN(CAST(a.answered AS REAL) / f.card_count * 100, 100.0)
+            ELSE CAST(q.current_index AS REAL) / MAX(f.card_count - 1, 1) * 100
+        END AS progress
+    FROM quizzes q
+    LEFT JOIN (
+        SELECT course_code, category, COUNT(*) AS card_count
+        FROM cards
+        GROUP BY course_code, category
+    ) f ON q.course_code = f.course_code AND q.category = f.category
+    LEFT JOIN (
+        SELECT
+            qa.quiz_id,
+            COUNT(*) AS answered,
+            COUNT(CASE WHEN qa.correct THEN 1 END) AS correct,
+            SUM(
+                CASE
+                    WHEN qa.correct THEN MAX(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                    ELSE 0
+                END
+            ) AS score
+        FROM quiz_answers qa
+        JOIN quizzes qz ON qz.id = qa.quiz_id
+        LEFT JOIN (
+            SELECT quiz_id, card_id, COUNT(*) AS hints
+            FROM quiz_hints
+            GROUP BY quiz_id, card_id
+        ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+        GROUP BY qa.quiz_id
+    ) a ON q.id = a.quiz_id
+    LEFT JOIN (
+        SELECT quiz_id, COUNT(*) AS hints_used
+        FROM quiz_hints
+        GROUP BY quiz_id
+    ) h ON q.id = h.quiz_id
+);
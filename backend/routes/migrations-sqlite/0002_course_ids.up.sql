@@ -0,0 +1,225 @@
+-- Cards and quizzes belong to a course through `course_id`, as in Postgres migration 0010.
+-- SQLite cannot add a foreign key to a table, so both are copied into new ones. Migrations
+-- run with foreign keys off, so dropping the old tables leaves the answers and hints that
+-- refer to them in place.
+
+DROP VIEW quizzes_view;
+DROP VIEW courses_view;
+
+CREATE UNIQUE INDEX courses_id_course_code_key ON courses (id, course_code);
+
+-- Rows whose code matches no course get a course of their own, owned by whoever started a
+-- quiz in it, or by nobody.
+INSERT INTO courses (id, name, course_code, description, user_id)
+SELECT
+    randomblob(16),
+    o.course_code,
+    o.course_code,
+    '',
+    (
+        SELECT q.user_id
+        FROM quizzes q
+        WHERE q.course_code = o.course_code AND q.user_id IS NOT NULL
+        ORDER BY q.started_at
+        LIMIT 1
+    )
+FROM (
+    SELECT course_code FROM cards
+    UNION
+    SELECT course_code FROM quizzes
+) o
+WHERE NOT EXISTS (SELECT 1 FROM courses c WHERE c.course_code = o.course_code);
+
+CREATE TABLE new_cards
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    course_id BLOB NOT NULL,
+    course_code TEXT NOT NULL,
+    category TEXT NOT NULL,
+    hint TEXT,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    updated_at TEXT,
+    FOREIGN KEY (course_id, course_code) REFERENCES courses (id, course_code)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO new_cards
+    (id, question, answer, course_id, course_code, category, hint, created_at, updated_at)
+SELECT
+    cards.id, question, answer, c.id, cards.course_code, category, hint, cards.created_at,
+    cards.updated_at
+FROM cards
+JOIN courses c ON c.course_code = cards.course_code;
+
+DROP TABLE cards;
+ALTER TABLE new_cards RENAME TO cards;
+
+CREATE INDEX cards_course_id_category_idx ON cards (course_id, category);
+
+CREATE TABLE new_quizzes
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    user_id BLOB REFERENCES users (id) ON DELETE CASCADE,
+    course_id BLOB NOT NULL,
+    course_code TEXT NOT NULL,
+    category TEXT NOT NULL,
+    current_index INTEGER DEFAULT 0 NOT NULL,
+    correct_count INTEGER DEFAULT 0 NOT NULL,
+    is_completed BOOLEAN DEFAULT FALSE NOT NULL,
+    hint_used BOOLEAN DEFAULT FALSE NOT NULL,
+    started_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    completed_at TEXT,
+    seed INTEGER DEFAULT (abs(random() % 9007199254740991)) NOT NULL,
+    distractor_count INTEGER DEFAULT 3 NOT NULL CHECK (distractor_count BETWEEN 1 AND 10),
+    hint_budget INTEGER DEFAULT 3 NOT NULL CHECK (hint_budget >= 0),
+    hint_penalty REAL DEFAULT 0.5 NOT NULL CHECK (hint_penalty BETWEEN 0 AND 1),
+    FOREIGN KEY (course_id, course_code) REFERENCES courses (id, course_code)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+INSERT INTO new_quizzes (
+    id, user_id, course_id, course_code, category, current_index, correct_count,
+    is_completed, hint_used, started_at, completed_at, seed, distractor_count, hint_budget,
+    hint_penalty
+)
+SELECT
+    q.id, q.user_id, c.id, q.course_code, q.category, q.current_index, q.correct_count,
+    q.is_completed, q.hint_used, q.started_at, q.completed_at, q.seed, q.distractor_count,
+    q.hint_budget, q.hint_penalty
+FROM quizzes q
+JOIN courses c ON c.course_code = q.course_code;
+
+DROP TABLE quizzes;
+ALTER TABLE new_quizzes RENAME TO quizzes;
+
+CREATE INDEX quizzes_user_id_idx ON quizzes (user_id);
+CREATE INDEX quizzes_course_id_category_idx ON quizzes (course_id, category);
+
+-- Progress is worked out as a fraction first, then rounded half to even as Postgres rounds
+-- double precision values. `categories` is a JSON array.
+CREATE VIEW courses_view AS
+SELECT
+    id,
+    user_id,
+    name,
+    course_code,
+    description,
+    created_at,
+    updated_at,
+    questions,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress,
+    categories
+FROM (
+    SELECT
+        c.*,
+        COALESCE(f.questions, 0) AS questions,
+        CASE
+            WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0.0
+            ELSE CAST(q.completed_quizzes AS REAL) / q.total_quizzes * 100
+        END AS progress,
+        COALESCE(cat.categories, json_array()) AS categories
+    FROM courses c
+    LEFT JOIN (
+        SELECT course_id, COUNT(*) AS questions
+        FROM cards
+        GROUP BY course_id
+    ) f ON c.id = f.course_id
+    LEFT JOIN (
+        SELECT
+            course_id,
+            COUNT(*) AS total_quizzes,
+            COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+        FROM quizzes
+        GROUP BY course_id
+    ) q ON c.id = q.course_id
+    LEFT JOIN (
+        SELECT course_id, json_group_array(category) AS categories
+        FROM (SELECT DISTINCT course_id, category FROM quizzes ORDER BY category)
+        GROUP BY course_id
+    ) cat ON c.id = cat.course_id
+);
+
+CREATE VIEW quizzes_view AS
+SELECT
+    id,
+    user_id,
+    course_id,
+    course_code,
+    category,
+    current_index,
+    correct_count,
+    is_completed,
+    hint_used,
+    hint_budget,
+    hints_used,
+    score,
+    started_at,
+    completed_at,
+    card_count,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress
+FROM (
+    SELECT
+        q.id,
+        q.user_id,
+        q.course_id,
+        q.course_code,
+        q.category,
+        q.current_index,
+        CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.correct END AS correct_count,
+        q.is_completed,
+        q.hint_used,
+        q.hint_budget,
+        COALESCE(h.hints_used, 0) AS hints_used,
+        CAST(CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.score END AS REAL) AS score,
+        q.started_at,
+        q.completed_at,
+        COALESCE(f.card_count, 0) AS card_count,
+        CASE
+            WHEN q.is_completed THEN 100.0
+            WHEN COALESCE(f.card_count, 0) = 0 THEN 0.0
+            WHEN a.answered IS NOT NULL
+                THEN MIN(CAST(a.answered AS REAL) / f.card_count * 100, 100.0)
+            ELSE CAST(q.current_index AS REAL) / MAX(f.card_count - 1, 1) * 100
+        END AS progress
+    FROM quizzes q
+    LEFT JOIN (
+        SELECT course_id, category, COUNT(*) AS card_count
+        FROM cards
+        GROUP BY course_id, category
+    ) f ON q.course_id = f.course_id AND q.category = f.category
+    LEFT JOIN (
+        SELECT
+            qa.quiz_id,
+            COUNT(*) AS answered,
+            COUNT(CASE WHEN qa.correct THEN 1 END) AS correct,
+            SUM(
+                CASE
+                    WHEN qa.correct THEN MAX(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                    ELSE 0
+                END
+            ) AS score
+        FROM quiz_answers qa
+        JOIN quizzes qz ON qz.id = qa.quiz_id
+        LEFT JOIN (
+            SELECT quiz_id, card_id, COUNT(*) AS hints
+            FROM quiz_hints
+            GROUP BY quiz_id, card_id
+        ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+        GROUP BY qa.quiz_id
+    ) a ON q.id = a.quiz_id
+    LEFT JOIN (
+        SELECT quiz_id, COUNT(*) AS hints_used
+        FROM quiz_hints
+        GROUP BY quiz_id
+    ) h ON q.id = h.quiz_id
+);
@@ -0,0 +1,165 @@
+DROP VIEW quizzes_view;
+DROP VIEW courses_view;
+DROP VIEW course_roles;
+
+DROP INDEX quizzes_deleted_at_idx;
+DROP INDEX cards_deleted_at_idx;
+DROP INDEX courses_deleted_at_idx;
+
+-- Trashed rows would reappear once the column is gone, so they are deleted for good first.
+-- Migrations run with foreign keys off, so whatever refers to them goes explicitly.
+DELETE FROM quizzes
+WHERE deleted_at IS NOT NULL
+OR course_id IN (SELECT id FROM courses WHERE deleted_at IS NOT NULL);
+DELETE FROM cards
+WHERE deleted_at IS NOT NULL
+OR course_id IN (SELECT id FROM courses WHERE deleted_at IS NOT NULL);
+DELETE FROM categories WHERE course_id IN (SELECT id FROM courses WHERE deleted_at IS NOT NULL);
+DELETE FROM course_members
+WHERE course_id IN (SELECT id FROM courses WHERE deleted_at IS NOT NULL);
+DELETE FROM courses WHERE deleted_at IS NOT NULL;
+DELETE FROM quiz_answers
+WHERE quiz_id NOT IN (SELECT id FROM quizzes) OR card_id NOT IN (SELECT id FROM cards);
+DELETE FROM quiz_hints
+WHERE quiz_id NOT IN (SELECT id FROM quizzes) OR card_id NOT IN (SELECT id FROM cards);
+
+ALTER TABLE quizzes DROP COLUMN deleted_at;
+ALTER TABLE cards DROP COLUMN deleted_at;
+ALTER TABLE courses DROP COLUMN deleted_at;
+
+CREATE VIEW course_roles AS
+SELECT c.id AS course_id, u.id AS user_id, 'owner' AS role
+FROM courses c
+JOIN users u ON c.user_id IS NULL OR c.user_id = u.id
+UNION ALL
+SELECT m.course_id, m.user_id, m.role
+FROM course_members m
+JOIN courses c ON c.id = m.course_id
+WHERE c.user_id IS NOT NULL AND c.user_id <> m.user_id;
+
+-- Progress is worked out as a fraction first, then rounded half to even as Postgres rounds
+-- double precision values. `categories` is a JSON array.
+CREATE VIEW courses_view AS
+SELECT
+    id,
+    user_id,
+    name,
+    course_code,
+    description,
+    created_at,
+    updated_at,
+    questions,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress,
+    categories
+FROM (
+    SELECT
+        c.*,
+        COALESCE(f.questions, 0) AS questions,
+        CASE
+            WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0.0
+            ELSE CAST(q.completed_quizzes AS REAL) / q.total_quizzes * 100
+        END AS progress,
+        COALESCE(cat.categories, json_array()) AS categories
+    FROM courses c
+    LEFT JOIN (
+        SELECT course_id, COUNT(*) AS questions
+        FROM cards
+        GROUP BY course_id
+    ) f ON c.id = f.course_id
+    LEFT JOIN (
+        SELECT
+            course_id,
+            COUNT(*) AS total_quizzes,
+            COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+        FROM quizzes
+        GROUP BY course_id
+    ) q ON c.id = q.course_id
+    LEFT JOIN (
+        SELECT course_id, json_group_array(name) AS categories
+        FROM (SELECT course_id, name FROM categories ORDER BY position)
+        GROUP BY course_id
+    ) cat ON c.id = cat.course_id
+);
+
+CREATE VIEW quizzes_view AS
+SELECT
+    id,
+    user_id,
+    course_id,
+    course_code,
+    category,
+    current_index,
+    correct_count,
+    is_completed,
+    hint_used,
+    hint_budget,
+    hints_used,
+    score,
+    started_at,
+    completed_at,
+    card_count,
+    CASE
+        WHEN progress - CAST(progress AS INTEGER) = 0.5
+            THEN CAST(progress AS INTEGER) + CAST(progress AS INTEGER) % 2
+        ELSE CAST(ROUND(progress) AS INTEGER)
+    END AS progress
+FROM (
+    SELECT
+        q.id,
+        q.user_id,
+        q.course_id,
+        q.course_code,
+        q.category,
+        q.current_index,
+        CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.correct END AS correct_count,
+        q.is_completed,
+        q.hint_used,
+        q.hint_budget,
+        COALESCE(h.hints_used, 0) AS hints_used,
+        CAST(CASE WHEN a.answered IS NULL THEN q.correct_count ELSE a.score END AS REAL) AS score,
+        q.started_at,
+        q.completed_at,
+        COALESCE(f.card_count, 0) AS card_count,
+        CASE
+            WHEN q.is_completed THEN 100.0
+            WHEN COALESCE(f.card_count, 0) = 0 THEN 0.0
+            WHEN a.answered IS NOT NULL
+                THEN MIN(CAST(a.answered AS REAL) / f.card_count * 100, 100.0)
+            ELSE CAST(q.current_index AS REAL) / MAX(f.card_count - 1, 1) * 100
+        END AS progress
+    FROM quizzes q
+    LEFT JOIN (
+        SELECT course_id, category, COUNT(*) AS card_count
+        FROM cards
+        GROUP BY course_id, category
+    ) f ON q.course_id = f.course_id AND q.category = f.category
+    LEFT JOIN (
+        SELECT
+            qa.quiz_id,
+            COUNT(*) AS answered,
+            COUNT(CASE WHEN qa.correct THEN 1 END) AS correct,
+            SUM(
+                CASE
+                    WHEN qa.correct THEN MAX(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                    ELSE 0
+                END
+            ) AS score
+        FROM quiz_answers qa
+        JOIN quizzes qz ON qz.id = qa.quiz_id
+        LEFT JOIN (
+            SELECT quiz_id, card_id, COUNT(*) AS hints
+            FROM quiz_hints
+            GROUP BY quiz_id, card_id
+        ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+        GROUP BY qa.quiz_id
+    ) a ON q.id = a.quiz_id
+    LEFT JOIN (
+        SELECT quiz_id, COUNT(*) AS hints_used
+        FROM quiz_hints
+        GROUP BY quiz_id
+    ) h ON q.id = h.quiz_id
+);
@@ -0,0 +1,17 @@
+-- Card revisions as in Postgres migration 0014.
+CREATE TABLE card_revisions
+(
+    card_id BLOB NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    category TEXT NOT NULL,
+    hint TEXT,
+    user_id BLOB REFERENCES users (id) ON DELETE SET NULL,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL,
+    PRIMARY KEY (card_id, revision)
+);
+
+INSERT INTO card_revisions (card_id, revision, question, answer, category, hint, created_at)
+SELECT id, 1, question, answer, category, hint, COALESCE(updated_at, created_at)
+FROM cards;
@@ -0,0 +1,12 @@
+DROP VIEW course_roles;
+
+CREATE VIEW course_roles AS
+SELECT c.id AS course_id, u.id AS user_id, 'owner' AS role
+FROM courses c
+JOIN users u ON c.user_id IS NULL OR c.user_id = u.id
+WHERE c.deleted_at IS NULL
+UNION ALL
+SELECT m.course_id, m.user_id, m.role
+FROM course_members m
+JOIN courses c ON c.id = m.course_id
+WHERE c.user_id IS NOT NULL AND c.user_id <> m.user_id AND c.deleted_at IS NULL;
@@ -0,0 +1,18 @@
+-- Legacy owners as in Postgres migration 0016.
+UPDATE courses SET user_id = (SELECT id FROM users ORDER BY created_at, id LIMIT 1)
+WHERE user_id IS NULL;
+
+UPDATE quizzes SET user_id = (SELECT id FROM users ORDER BY created_at, id LIMIT 1)
+WHERE user_id IS NULL;
+
+DROP VIEW course_roles;
+
+CREATE VIEW course_roles AS
+SELECT c.id AS course_id, c.user_id, 'owner' AS role
+FROM courses c
+WHERE c.user_id IS NOT NULL AND c.deleted_at IS NULL
+UNION ALL
+SELECT m.course_id, m.user_id, m.role
+FROM course_members m
+JOIN courses c ON c.id = m.course_id
+WHERE (c.user_id IS NULL OR c.user_id <> m.user_id) AND c.deleted_at IS NULL;
@@ -0,0 +1,34 @@
+-- Reviews as in Postgres migrations 0002 and 0017, kept per user from the start.
+CREATE TABLE card_reviews
+(
+    user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    card_id BLOB NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+    algorithm TEXT NOT NULL,
+    ease REAL NOT NULL,
+    stability REAL NOT NULL,
+    difficulty REAL NOT NULL,
+    interval_days INTEGER NOT NULL,
+    repetitions INTEGER NOT NULL,
+    lapses INTEGER NOT NULL,
+    due_at TEXT NOT NULL,
+    last_reviewed_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, card_id)
+);
+
+CREATE INDEX card_reviews_card_id_idx ON card_reviews (card_id);
+CREATE INDEX card_reviews_due_at_idx ON card_reviews (due_at);
+
+CREATE TABLE review_log
+(
+    id BLOB NOT NULL PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    card_id BLOB NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+    algorithm TEXT NOT NULL,
+    grade INTEGER NOT NULL CHECK (grade BETWEEN 1 AND 4),
+    elapsed_days REAL NOT NULL,
+    interval_days INTEGER NOT NULL,
+    reviewed_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')) NOT NULL
+);
+
+CREATE INDEX review_log_card_id_idx ON review_log (card_id);
+CREATE INDEX review_log_user_id_idx ON review_log (user_id);
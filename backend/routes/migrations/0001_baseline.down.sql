@@ -0,0 +1,10 @@
+DROP FUNCTION IF EXISTS delete_course(UUID);
+DROP FUNCTION IF EXISTS delete_quiz(UUID);
+DROP FUNCTION IF EXISTS update_category(TEXT, TEXT, TEXT);
+
+DROP VIEW IF EXISTS quizzes_view;
+DROP VIEW IF EXISTS courses_view;
+
+DROP TABLE IF EXISTS quizzes;
+DROP TABLE IF EXISTS courses;
+DROP TABLE IF EXISTS cards;
@@ -1,3 +1,6 @@
+-- Baseline schema. Statements stay idempotent so databases created before
+-- migrations existed can adopt this version without losing data.
+
 CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
 
 CREATE TABLE IF NOT EXISTS cards
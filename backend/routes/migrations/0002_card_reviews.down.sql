@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS review_log;
+DROP TABLE IF EXISTS card_reviews;
@@ -0,0 +1,30 @@
+CREATE TABLE card_reviews
+(
+    card_id uuid NOT NULL CONSTRAINT card_reviews_pkey PRIMARY KEY
+        CONSTRAINT card_reviews_card_id_fkey REFERENCES cards (id) ON DELETE CASCADE,
+    algorithm text NOT NULL,
+    ease double precision NOT NULL,
+    stability double precision NOT NULL,
+    difficulty double precision NOT NULL,
+    interval_days integer NOT NULL,
+    repetitions integer NOT NULL,
+    lapses integer NOT NULL,
+    due_at timestamp with time zone NOT NULL,
+    last_reviewed_at timestamp with time zone NOT NULL
+);
+
+CREATE INDEX card_reviews_due_at_idx ON card_reviews (due_at);
+
+CREATE TABLE review_log
+(
+    id uuid DEFAULT uuid_generate_v1() NOT NULL CONSTRAINT review_log_pkey PRIMARY KEY,
+    card_id uuid NOT NULL
+        CONSTRAINT review_log_card_id_fkey REFERENCES cards (id) ON DELETE CASCADE,
+    algorithm text NOT NULL,
+    grade smallint NOT NULL CONSTRAINT review_log_grade_check CHECK (grade BETWEEN 1 AND 4),
+    elapsed_days double precision NOT NULL,
+    interval_days integer NOT NULL,
+    reviewed_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX review_log_card_id_idx ON review_log (card_id);
@@ -0,0 +1,20 @@
+DROP VIEW quizzes_view;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.*,
+    COALESCE(COUNT(f.id), 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COUNT(f.id) = 0 THEN 0
+        ELSE ROUND((q.current_index::float / GREATEST(COUNT(f.id) - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+LEFT JOIN
+    cards f ON q.course_code = f.course_code AND q.category = f.category
+GROUP BY
+    q.id, q.course_code, q.category, q.current_index, q.is_completed,
+    q.correct_count, q.started_at, q.completed_at, q.hint_used;
+
+DROP TABLE IF EXISTS quiz_answers;
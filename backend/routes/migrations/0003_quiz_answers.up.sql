@@ -0,0 +1,61 @@
+CREATE TABLE quiz_answers
+(
+    id uuid DEFAULT uuid_generate_v1() NOT NULL CONSTRAINT quiz_answers_pkey PRIMARY KEY,
+    quiz_id uuid NOT NULL
+        CONSTRAINT quiz_answers_quiz_id_fkey REFERENCES quizzes (id) ON DELETE CASCADE,
+    card_id uuid NOT NULL
+        CONSTRAINT quiz_answers_card_id_fkey REFERENCES cards (id) ON DELETE CASCADE,
+    given_answer text NOT NULL,
+    correct boolean NOT NULL,
+    hint_used boolean DEFAULT false NOT NULL,
+    elapsed_ms integer NOT NULL CONSTRAINT quiz_answers_elapsed_ms_check CHECK (elapsed_ms >= 0),
+    answered_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT quiz_answers_quiz_id_card_id_key UNIQUE (quiz_id, card_id)
+);
+
+-- Attempts recorded before quiz_answers existed keep their stored counters.
+DROP VIEW quizzes_view;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.id,
+    q.course_code,
+    q.category,
+    q.current_index,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count
+        ELSE a.correct::integer
+    END AS correct_count,
+    q.is_completed,
+    q.hint_used,
+    q.started_at,
+    q.completed_at,
+    COALESCE(f.card_count, 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COALESCE(f.card_count, 0) = 0 THEN 0
+        WHEN a.answered IS NOT NULL THEN LEAST(ROUND((a.answered::float / f.card_count) * 100), 100)::integer
+        ELSE ROUND((q.current_index::float / GREATEST(f.card_count - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+LEFT JOIN (
+    SELECT
+        course_code,
+        category,
+        COUNT(*) AS card_count
+    FROM
+        cards
+    GROUP BY
+        course_code, category
+) f ON q.course_code = f.course_code AND q.category = f.category
+LEFT JOIN (
+    SELECT
+        quiz_id,
+        COUNT(*) AS answered,
+        COUNT(*) FILTER (WHERE correct) AS correct
+    FROM
+        quiz_answers
+    GROUP BY
+        quiz_id
+) a ON q.id = a.quiz_id;
@@ -0,0 +1,3 @@
+ALTER TABLE quizzes
+    DROP COLUMN IF EXISTS distractor_count,
+    DROP COLUMN IF EXISTS seed;
@@ -0,0 +1,6 @@
+-- Seeds the arrangement of server-generated answer options, so reloading a quiz shows the
+-- same choices in the same order.
+ALTER TABLE quizzes
+    ADD COLUMN seed bigint DEFAULT floor(random() * 9007199254740991)::bigint NOT NULL,
+    ADD COLUMN distractor_count integer DEFAULT 3 NOT NULL
+        CONSTRAINT quizzes_distractor_count_check CHECK (distractor_count BETWEEN 1 AND 10);
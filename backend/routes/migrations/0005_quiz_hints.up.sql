@@ -0,0 +1,99 @@
+ALTER TABLE cards ADD COLUMN hint text;
+
+ALTER TABLE quizzes
+    ADD COLUMN hint_budget integer DEFAULT 3 NOT NULL
+        CONSTRAINT quizzes_hint_budget_check CHECK (hint_budget >= 0),
+    ADD COLUMN hint_penalty double precision DEFAULT 0.5 NOT NULL
+        CONSTRAINT quizzes_hint_penalty_check CHECK (hint_penalty BETWEEN 0 AND 1);
+
+CREATE TABLE quiz_hints
+(
+    id uuid DEFAULT uuid_generate_v1() NOT NULL CONSTRAINT quiz_hints_pkey PRIMARY KEY,
+    quiz_id uuid NOT NULL
+        CONSTRAINT quiz_hints_quiz_id_fkey REFERENCES quizzes (id) ON DELETE CASCADE,
+    card_id uuid NOT NULL
+        CONSTRAINT quiz_hints_card_id_fkey REFERENCES cards (id) ON DELETE CASCADE,
+    kind text NOT NULL,
+    revealed text NOT NULL,
+    used_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT quiz_hints_quiz_id_card_id_kind_key UNIQUE (quiz_id, card_id, kind)
+);
+
+DROP VIEW quizzes_view;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.id,
+    q.course_code,
+    q.category,
+    q.current_index,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count
+        ELSE a.correct::integer
+    END AS correct_count,
+    q.is_completed,
+    q.hint_used,
+    q.hint_budget,
+    COALESCE(h.hints_used, 0) AS hints_used,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count::float
+        ELSE a.score
+    END AS score,
+    q.started_at,
+    q.completed_at,
+    COALESCE(f.card_count, 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COALESCE(f.card_count, 0) = 0 THEN 0
+        WHEN a.answered IS NOT NULL THEN LEAST(ROUND((a.answered::float / f.card_count) * 100), 100)::integer
+        ELSE ROUND((q.current_index::float / GREATEST(f.card_count - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+LEFT JOIN (
+    SELECT
+        course_code,
+        category,
+        COUNT(*) AS card_count
+    FROM
+        cards
+    GROUP BY
+        course_code, category
+) f ON q.course_code = f.course_code AND q.category = f.category
+LEFT JOIN (
+    SELECT
+        qa.quiz_id,
+        COUNT(*) AS answered,
+        COUNT(*) FILTER (WHERE qa.correct) AS correct,
+        SUM(
+            CASE
+                WHEN qa.correct THEN GREATEST(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                ELSE 0
+            END
+        )::float AS score
+    FROM
+        quiz_answers qa
+    JOIN
+        quizzes qz ON qz.id = qa.quiz_id
+    LEFT JOIN (
+        SELECT
+            quiz_id,
+            card_id,
+            COUNT(*) AS hints
+        FROM
+            quiz_hints
+        GROUP BY
+            quiz_id, card_id
+    ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+    GROUP BY
+        qa.quiz_id
+) a ON q.id = a.quiz_id
+LEFT JOIN (
+    SELECT
+        quiz_id,
+        COUNT(*) AS hints_used
+    FROM
+        quiz_hints
+    GROUP BY
+        quiz_id
+) h ON q.id = h.quiz_id;
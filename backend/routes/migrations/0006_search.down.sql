@@ -0,0 +1,5 @@
+DROP INDEX courses_search_idx;
+DROP INDEX cards_search_idx;
+
+ALTER TABLE courses DROP COLUMN search;
+ALTER TABLE cards DROP COLUMN search;
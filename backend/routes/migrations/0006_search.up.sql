@@ -0,0 +1,14 @@
+ALTER TABLE cards
+    ADD COLUMN search tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', question), 'A') ||
+        setweight(to_tsvector('english', answer), 'B')
+    ) STORED;
+
+ALTER TABLE courses
+    ADD COLUMN search tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', name), 'A') ||
+        setweight(to_tsvector('english', description), 'B')
+    ) STORED;
+
+CREATE INDEX cards_search_idx ON cards USING gin (search);
+CREATE INDEX courses_search_idx ON courses USING gin (search);
@@ -0,0 +1,133 @@
+DROP VIEW courses_view;
+DROP VIEW quizzes_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+LEFT JOIN (
+    SELECT
+        course_code,
+        COUNT(*) AS questions
+    FROM
+        cards
+    GROUP BY
+        course_code
+) f ON c.course_code = f.course_code
+LEFT JOIN (
+    SELECT
+        course_code,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    GROUP BY
+        course_code
+) q ON c.course_code = q.course_code
+LEFT JOIN (
+    SELECT
+        course_code,
+        ARRAY_AGG(DISTINCT category) AS categories
+    FROM
+        quizzes
+    GROUP BY
+        course_code
+) cat ON c.course_code = cat.course_code;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.id,
+    q.course_code,
+    q.category,
+    q.current_index,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count
+        ELSE a.correct::integer
+    END AS correct_count,
+    q.is_completed,
+    q.hint_used,
+    q.hint_budget,
+    COALESCE(h.hints_used, 0) AS hints_used,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count::float
+        ELSE a.score
+    END AS score,
+    q.started_at,
+    q.completed_at,
+    COALESCE(f.card_count, 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COALESCE(f.card_count, 0) = 0 THEN 0
+        WHEN a.answered IS NOT NULL THEN LEAST(ROUND((a.answered::float / f.card_count) * 100), 100)::integer
+        ELSE ROUND((q.current_index::float / GREATEST(f.card_count - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+LEFT JOIN (
+    SELECT
+        course_code,
+        category,
+        COUNT(*) AS card_count
+    FROM
+        cards
+    GROUP BY
+        course_code, category
+) f ON q.course_code = f.course_code AND q.category = f.category
+LEFT JOIN (
+    SELECT
+        qa.quiz_id,
+        COUNT(*) AS answered,
+        COUNT(*) FILTER (WHERE qa.correct) AS correct,
+        SUM(
+            CASE
+                WHEN qa.correct THEN GREATEST(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                ELSE 0
+            END
+        )::float AS score
+    FROM
+        quiz_answers qa
+    JOIN
+        quizzes qz ON qz.id = qa.quiz_id
+    LEFT JOIN (
+        SELECT
+            quiz_id,
+            card_id,
+            COUNT(*) AS hints
+        FROM
+            quiz_hints
+        GROUP BY
+            quiz_id, card_id
+    ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+    GROUP BY
+        qa.quiz_id
+) a ON q.id = a.quiz_id
+LEFT JOIN (
+    SELECT
+        quiz_id,
+        COUNT(*) AS hints_used
+    FROM
+        quiz_hints
+    GROUP BY
+        quiz_id
+) h ON q.id = h.quiz_id;
+
+DROP INDEX quizzes_user_id_idx;
+DROP INDEX courses_user_id_idx;
+
+ALTER TABLE quizzes DROP COLUMN user_id;
+ALTER TABLE courses DROP COLUMN user_id;
+
+DROP TABLE sessions;
+DROP TABLE users;
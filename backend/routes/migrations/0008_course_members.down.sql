@@ -0,0 +1,2 @@
+DROP FUNCTION course_role(UUID, UUID);
+DROP TABLE course_members;
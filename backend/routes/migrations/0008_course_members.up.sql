@@ -0,0 +1,30 @@
+-- Users the owner has shared a course with. The owner itself is `courses.user_id`.
+CREATE TABLE course_members
+(
+    course_id uuid NOT NULL
+        CONSTRAINT course_members_course_id_fkey REFERENCES courses (id) ON DELETE CASCADE,
+    user_id uuid NOT NULL
+        CONSTRAINT course_members_user_id_fkey REFERENCES users (id) ON DELETE CASCADE,
+    role text NOT NULL CONSTRAINT course_members_role_check CHECK (role IN ('viewer', 'editor')),
+    created_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT course_members_pkey PRIMARY KEY (course_id, user_id)
+);
+
+CREATE INDEX course_members_user_id_idx ON course_members (user_id);
+
+-- Role of a user in a course, NULL if the course is not shared with them. Courses without an
+-- owner predate user accounts and every user owns them.
+CREATE OR REPLACE FUNCTION course_role(p_course_id UUID, p_user_id UUID)
+RETURNS text AS $$
+    SELECT
+        CASE
+            WHEN c.user_id IS NULL OR c.user_id = p_user_id THEN 'owner'
+            ELSE (
+                SELECT m.role
+                FROM course_members m
+                WHERE m.course_id = c.id AND m.user_id = p_user_id
+            )
+        END
+    FROM courses c
+    WHERE c.id = p_course_id;
+$$ LANGUAGE sql STABLE;
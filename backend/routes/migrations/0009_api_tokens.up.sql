@@ -0,0 +1,16 @@
+-- Personal access tokens for scripts. As with sessions, only a SHA-256 digest is stored.
+CREATE TABLE api_tokens
+(
+    id uuid DEFAULT uuid_generate_v1() NOT NULL CONSTRAINT api_tokens_pkey PRIMARY KEY,
+    user_id uuid NOT NULL
+        CONSTRAINT api_tokens_user_id_fkey REFERENCES users (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    token_hash text NOT NULL CONSTRAINT api_tokens_token_hash_key UNIQUE,
+    scope text NOT NULL
+        CONSTRAINT api_tokens_scope_check CHECK (scope IN ('read', 'cards:write', 'admin')),
+    created_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    last_used_at timestamp with time zone,
+    expires_at timestamp with time zone
+);
+
+CREATE INDEX api_tokens_user_id_idx ON api_tokens (user_id);
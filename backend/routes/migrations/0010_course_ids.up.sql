@@ -0,0 +1,230 @@
+-- Cards and quizzes belong to a course through `course_id`. They keep `course_code`, which
+-- routes filter on, and the foreign key over both columns carries a renamed code along.
+ALTER TABLE courses
+    ADD CONSTRAINT courses_id_course_code_key UNIQUE (id, course_code);
+
+ALTER TABLE cards ADD COLUMN course_id uuid;
+ALTER TABLE quizzes ADD COLUMN course_id uuid;
+
+-- Rows whose code matches no course, such as those left behind when a course code was
+-- changed, get a course of their own. It is owned by whoever started a quiz in it, or by
+-- nobody when no one has, which leaves it visible to every user like other ownerless rows.
+INSERT INTO courses (name, course_code, description, user_id)
+SELECT
+    o.course_code,
+    o.course_code,
+    '',
+    (
+        SELECT q.user_id
+        FROM quizzes q
+        WHERE q.course_code = o.course_code AND q.user_id IS NOT NULL
+        ORDER BY q.started_at
+        LIMIT 1
+    )
+FROM (
+    SELECT course_code FROM cards
+    UNION
+    SELECT course_code FROM quizzes
+) o
+WHERE NOT EXISTS (SELECT 1 FROM courses c WHERE c.course_code = o.course_code);
+
+UPDATE cards SET course_id = c.id FROM courses c WHERE c.course_code = cards.course_code;
+UPDATE quizzes SET course_id = c.id FROM courses c WHERE c.course_code = quizzes.course_code;
+
+ALTER TABLE cards
+    ALTER COLUMN course_id SET NOT NULL,
+    ADD CONSTRAINT cards_course_id_fkey FOREIGN KEY (course_id, course_code)
+        REFERENCES courses (id, course_code) ON UPDATE CASCADE ON DELETE CASCADE;
+
+ALTER TABLE quizzes
+    ALTER COLUMN course_id SET NOT NULL,
+    ADD CONSTRAINT quizzes_course_id_fkey FOREIGN KEY (course_id, course_code)
+        REFERENCES courses (id, course_code) ON UPDATE CASCADE ON DELETE CASCADE;
+
+CREATE INDEX cards_course_id_category_idx ON cards (course_id, category);
+CREATE INDEX quizzes_course_id_category_idx ON quizzes (course_id, category);
+
+DROP VIEW courses_view;
+DROP VIEW quizzes_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.user_id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS questions
+    FROM
+        cards
+    GROUP BY
+        course_id
+) f ON c.id = f.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    GROUP BY
+        course_id
+) q ON c.id = q.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        ARRAY_AGG(DISTINCT category) AS categories
+    FROM
+        quizzes
+    GROUP BY
+        course_id
+) cat ON c.id = cat.course_id;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.id,
+    q.user_id,
+    q.course_id,
+    q.course_code,
+    q.category,
+    q.current_index,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count
+        ELSE a.correct::integer
+    END AS correct_count,
+    q.is_completed,
+    q.hint_used,
+    q.hint_budget,
+    COALESCE(h.hints_used, 0) AS hints_used,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count::float
+        ELSE a.score
+    END AS score,
+    q.started_at,
+    q.completed_at,
+    COALESCE(f.card_count, 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COALESCE(f.card_count, 0) = 0 THEN 0
+        WHEN a.answered IS NOT NULL THEN LEAST(ROUND((a.answered::float / f.card_count) * 100), 100)::integer
+        ELSE ROUND((q.current_index::float / GREATEST(f.card_count - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+LEFT JOIN (
+    SELECT
+        course_id,
+        category,
+        COUNT(*) AS card_count
+    FROM
+        cards
+    GROUP BY
+        course_id, category
+) f ON q.course_id = f.course_id AND q.category = f.category
+LEFT JOIN (
+    SELECT
+        qa.quiz_id,
+        COUNT(*) AS answered,
+        COUNT(*) FILTER (WHERE qa.correct) AS correct,
+        SUM(
+            CASE
+                WHEN qa.correct THEN GREATEST(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                ELSE 0
+            END
+        )::float AS score
+    FROM
+        quiz_answers qa
+    JOIN
+        quizzes qz ON qz.id = qa.quiz_id
+    LEFT JOIN (
+        SELECT
+            quiz_id,
+            card_id,
+            COUNT(*) AS hints
+        FROM
+            quiz_hints
+        GROUP BY
+            quiz_id, card_id
+    ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+    GROUP BY
+        qa.quiz_id
+) a ON q.id = a.quiz_id
+LEFT JOIN (
+    SELECT
+        quiz_id,
+        COUNT(*) AS hints_used
+    FROM
+        quiz_hints
+    GROUP BY
+        quiz_id
+) h ON q.id = h.quiz_id;
+
+CREATE OR REPLACE FUNCTION update_category(
+    p_course_code TEXT,
+    p_old_category TEXT,
+    p_new_category TEXT
+)
+RETURNS void AS $$
+DECLARE
+    v_course_id UUID;
+BEGIN
+    SELECT id
+    INTO v_course_id
+    FROM courses
+    WHERE course_code = p_course_code;
+
+    UPDATE cards
+    SET category = p_new_category
+    WHERE course_id = v_course_id
+    AND category = p_old_category;
+
+    UPDATE quizzes
+    SET category = p_new_category
+    WHERE course_id = v_course_id
+    AND category = p_old_category;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION delete_quiz(p_quiz_id UUID)
+RETURNS UUID AS $$
+DECLARE
+    v_course_id UUID;
+    v_category TEXT;
+BEGIN
+    SELECT course_id, category
+    INTO v_course_id, v_category
+    FROM quizzes
+    WHERE id = p_quiz_id;
+
+    DELETE FROM cards
+    WHERE course_id = v_course_id
+    AND category = v_category;
+
+    DELETE FROM quizzes
+    WHERE id = p_quiz_id
+    RETURNING id INTO p_quiz_id;
+
+    RETURN p_quiz_id;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Quizzes and cards go with the course through their foreign keys.
+CREATE OR REPLACE FUNCTION delete_course(p_course_id UUID)
+RETURNS UUID AS $$
+    DELETE FROM courses
+    WHERE id = p_course_id
+    RETURNING id;
+$$ LANGUAGE sql;
@@ -0,0 +1,81 @@
+DROP FUNCTION category_lock(UUID, TEXT, UUID);
+DROP FUNCTION ensure_category(UUID, TEXT);
+
+DROP VIEW courses_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.user_id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS questions
+    FROM
+        cards
+    GROUP BY
+        course_id
+) f ON c.id = f.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    GROUP BY
+        course_id
+) q ON c.id = q.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        ARRAY_AGG(DISTINCT category) AS categories
+    FROM
+        quizzes
+    GROUP BY
+        course_id
+) cat ON c.id = cat.course_id;
+
+CREATE OR REPLACE FUNCTION update_category(
+    p_course_code TEXT,
+    p_old_category TEXT,
+    p_new_category TEXT
+)
+RETURNS void AS $$
+DECLARE
+    v_course_id UUID;
+BEGIN
+    SELECT id
+    INTO v_course_id
+    FROM courses
+    WHERE course_code = p_course_code;
+
+    UPDATE cards
+    SET category = p_new_category
+    WHERE course_id = v_course_id
+    AND category = p_old_category;
+
+    UPDATE quizzes
+    SET category = p_new_category
+    WHERE course_id = v_course_id
+    AND category = p_old_category;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE quizzes DROP CONSTRAINT quizzes_category_fkey;
+ALTER TABLE cards DROP CONSTRAINT cards_category_fkey;
+
+DROP TABLE categories;
@@ -0,0 +1,116 @@
+-- The lessons of a course, in the order they are taken. A category with `unlock_after` can
+-- only be quizzed on once a quiz on that other category has been completed.
+CREATE TABLE categories
+(
+    id uuid DEFAULT uuid_generate_v1() NOT NULL CONSTRAINT categories_pkey PRIMARY KEY,
+    course_id uuid NOT NULL
+        CONSTRAINT categories_course_id_fkey REFERENCES courses (id) ON DELETE CASCADE,
+    name text NOT NULL,
+    description text DEFAULT '' NOT NULL,
+    position integer NOT NULL,
+    unlock_after uuid
+        CONSTRAINT categories_unlock_after_fkey REFERENCES categories (id) ON DELETE SET NULL,
+    created_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    updated_at timestamp with time zone,
+    CONSTRAINT categories_course_id_name_key UNIQUE (course_id, name)
+);
+
+-- Existing categories are ordered by when their first card or quiz was created.
+INSERT INTO categories (course_id, name, position, created_at)
+SELECT
+    course_id,
+    category,
+    ROW_NUMBER() OVER (PARTITION BY course_id ORDER BY MIN(created_at), category) - 1,
+    MIN(created_at)
+FROM (
+    SELECT course_id, category, created_at FROM cards
+    UNION ALL
+    SELECT course_id, category, started_at FROM quizzes
+) c
+GROUP BY course_id, category;
+
+-- Cards and quizzes keep the category's name, which follows a rename through these keys.
+ALTER TABLE cards
+    ADD CONSTRAINT cards_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE ON DELETE CASCADE;
+
+ALTER TABLE quizzes
+    ADD CONSTRAINT quizzes_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE ON DELETE CASCADE;
+
+DROP FUNCTION update_category(TEXT, TEXT, TEXT);
+
+DROP VIEW courses_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.user_id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS questions
+    FROM
+        cards
+    GROUP BY
+        course_id
+) f ON c.id = f.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    GROUP BY
+        course_id
+) q ON c.id = q.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        ARRAY_AGG(name ORDER BY position) AS categories
+    FROM
+        categories
+    GROUP BY
+        course_id
+) cat ON c.id = cat.course_id;
+
+-- Adds a category to the end of a course unless it already has one by that name, for cards
+-- and quizzes that name a category of their own.
+CREATE OR REPLACE FUNCTION ensure_category(p_course_id UUID, p_name TEXT)
+RETURNS void AS $$
+    INSERT INTO categories (course_id, name, position)
+    SELECT p_course_id, p_name, COALESCE(MAX(position) + 1, 0)
+    FROM categories
+    WHERE course_id = p_course_id
+    ON CONFLICT (course_id, name) DO NOTHING;
+$$ LANGUAGE sql;
+
+-- Name of the category a user must still complete a quiz on before starting one on the given
+-- category, NULL if it is unlocked.
+CREATE OR REPLACE FUNCTION category_lock(p_course_id UUID, p_name TEXT, p_user_id UUID)
+RETURNS text AS $$
+    SELECT r.name
+    FROM categories c
+    JOIN categories r ON r.id = c.unlock_after
+    WHERE c.course_id = p_course_id AND c.name = p_name
+    AND NOT EXISTS (
+        SELECT 1
+        FROM quizzes q
+        WHERE q.course_id = r.course_id AND q.category = r.name AND q.is_completed
+        AND (q.user_id IS NULL OR q.user_id = p_user_id)
+    );
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,24 @@
+DROP INDEX quizzes_user_id_course_id_category_idx;
+
+CREATE OR REPLACE FUNCTION delete_quiz(p_quiz_id UUID)
+RETURNS UUID AS $$
+DECLARE
+    v_course_id UUID;
+    v_category TEXT;
+BEGIN
+    SELECT course_id, category
+    INTO v_course_id, v_category
+    FROM quizzes
+    WHERE id = p_quiz_id;
+
+    DELETE FROM cards
+    WHERE course_id = v_course_id
+    AND category = v_category;
+
+    DELETE FROM quizzes
+    WHERE id = p_quiz_id
+    RETURNING id INTO p_quiz_id;
+
+    RETURN p_quiz_id;
+END;
+$$ LANGUAGE plpgsql;
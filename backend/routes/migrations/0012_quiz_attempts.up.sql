@@ -0,0 +1,10 @@
+-- A quiz is one attempt at a category, so deleting it leaves the category's cards alone. Its
+-- answers and hints go with it through their foreign keys.
+CREATE OR REPLACE FUNCTION delete_quiz(p_quiz_id UUID)
+RETURNS UUID AS $$
+    DELETE FROM quizzes
+    WHERE id = p_quiz_id
+    RETURNING id;
+$$ LANGUAGE sql;
+
+CREATE INDEX quizzes_user_id_course_id_category_idx ON quizzes (user_id, course_id, category);
@@ -0,0 +1,196 @@
+-- Deleting a course, card or quiz moves it to the trash by setting `deleted_at`, and it can be
+-- restored until the purge job deletes it for good. A trashed course has no members, which
+-- takes its cards and quizzes out of sight along with it.
+ALTER TABLE courses ADD COLUMN deleted_at timestamp with time zone;
+ALTER TABLE cards ADD COLUMN deleted_at timestamp with time zone;
+ALTER TABLE quizzes ADD COLUMN deleted_at timestamp with time zone;
+
+CREATE INDEX courses_deleted_at_idx ON courses (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX cards_deleted_at_idx ON cards (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX quizzes_deleted_at_idx ON quizzes (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION course_role(p_course_id UUID, p_user_id UUID)
+RETURNS text AS $$
+    SELECT
+        CASE
+            WHEN c.user_id IS NULL OR c.user_id = p_user_id THEN 'owner'
+            ELSE (
+                SELECT m.role
+                FROM course_members m
+                WHERE m.course_id = c.id AND m.user_id = p_user_id
+            )
+        END
+    FROM courses c
+    WHERE c.id = p_course_id AND c.deleted_at IS NULL;
+$$ LANGUAGE sql STABLE;
+
+DROP VIEW courses_view;
+DROP VIEW quizzes_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.user_id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS questions
+    FROM
+        cards
+    WHERE
+        deleted_at IS NULL
+    GROUP BY
+        course_id
+) f ON c.id = f.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    WHERE
+        deleted_at IS NULL
+    GROUP BY
+        course_id
+) q ON c.id = q.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        ARRAY_AGG(name ORDER BY position) AS categories
+    FROM
+        categories
+    GROUP BY
+        course_id
+) cat ON c.id = cat.course_id
+WHERE
+    c.deleted_at IS NULL;
+
+CREATE VIEW quizzes_view AS
+SELECT
+    q.id,
+    q.user_id,
+    q.course_id,
+    q.course_code,
+    q.category,
+    q.current_index,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count
+        ELSE a.correct::integer
+    END AS correct_count,
+    q.is_completed,
+    q.hint_used,
+    q.hint_budget,
+    COALESCE(h.hints_used, 0) AS hints_used,
+    CASE
+        WHEN a.answered IS NULL THEN q.correct_count::float
+        ELSE a.score
+    END AS score,
+    q.started_at,
+    q.completed_at,
+    COALESCE(f.card_count, 0) AS card_count,
+    CASE
+        WHEN q.is_completed THEN 100
+        WHEN COALESCE(f.card_count, 0) = 0 THEN 0
+        WHEN a.answered IS NOT NULL THEN LEAST(ROUND((a.answered::float / f.card_count) * 100), 100)::integer
+        ELSE ROUND((q.current_index::float / GREATEST(f.card_count - 1, 1)) * 100)::integer
+    END AS progress
+FROM
+    quizzes q
+JOIN
+    courses co ON co.id = q.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        category,
+        COUNT(*) AS card_count
+    FROM
+        cards
+    WHERE
+        deleted_at IS NULL
+    GROUP BY
+        course_id, category
+) f ON q.course_id = f.course_id AND q.category = f.category
+LEFT JOIN (
+    SELECT
+        qa.quiz_id,
+        COUNT(*) AS answered,
+        COUNT(*) FILTER (WHERE qa.correct) AS correct,
+        SUM(
+            CASE
+                WHEN qa.correct THEN GREATEST(1 - qz.hint_penalty * COALESCE(ch.hints, 0), 0)
+                ELSE 0
+            END
+        )::float AS score
+    FROM
+        quiz_answers qa
+    JOIN
+        quizzes qz ON qz.id = qa.quiz_id
+    LEFT JOIN (
+        SELECT
+            quiz_id,
+            card_id,
+            COUNT(*) AS hints
+        FROM
+            quiz_hints
+        GROUP BY
+            quiz_id, card_id
+    ) ch ON ch.quiz_id = qa.quiz_id AND ch.card_id = qa.card_id
+    GROUP BY
+        qa.quiz_id
+) a ON q.id = a.quiz_id
+LEFT JOIN (
+    SELECT
+        quiz_id,
+        COUNT(*) AS hints_used
+    FROM
+        quiz_hints
+    GROUP BY
+        quiz_id
+) h ON q.id = h.quiz_id
+WHERE
+    q.deleted_at IS NULL AND co.deleted_at IS NULL;
+
+CREATE OR REPLACE FUNCTION delete_quiz(p_quiz_id UUID)
+RETURNS UUID AS $$
+    UPDATE quizzes
+    SET deleted_at = now()
+    WHERE id = p_quiz_id AND deleted_at IS NULL
+    RETURNING id;
+$$ LANGUAGE sql;
+
+CREATE OR REPLACE FUNCTION delete_course(p_course_id UUID)
+RETURNS UUID AS $$
+    UPDATE courses
+    SET deleted_at = now()
+    WHERE id = p_course_id AND deleted_at IS NULL
+    RETURNING id;
+$$ LANGUAGE sql;
+
+CREATE OR REPLACE FUNCTION category_lock(p_course_id UUID, p_name TEXT, p_user_id UUID)
+RETURNS text AS $$
+    SELECT r.name
+    FROM categories c
+    JOIN categories r ON r.id = c.unlock_after
+    WHERE c.course_id = p_course_id AND c.name = p_name
+    AND NOT EXISTS (
+        SELECT 1
+        FROM quizzes q
+        WHERE q.course_id = r.course_id AND q.category = r.name AND q.is_completed
+        AND q.deleted_at IS NULL
+        AND (q.user_id IS NULL OR q.user_id = p_user_id)
+    );
+$$ LANGUAGE sql STABLE;
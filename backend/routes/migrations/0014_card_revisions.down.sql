@@ -0,0 +1,3 @@
+DROP FUNCTION add_card_revision(UUID, UUID);
+
+DROP TABLE card_revisions;
@@ -0,0 +1,39 @@
+-- Every version of a card, numbered from 1, with who wrote it. The card itself holds the
+-- latest one.
+CREATE TABLE card_revisions
+(
+    card_id uuid NOT NULL
+        CONSTRAINT card_revisions_card_id_fkey REFERENCES cards (id) ON DELETE CASCADE,
+    revision integer NOT NULL,
+    question text NOT NULL,
+    answer text NOT NULL,
+    category text NOT NULL,
+    hint text,
+    user_id uuid
+        CONSTRAINT card_revisions_user_id_fkey REFERENCES users (id) ON DELETE SET NULL,
+    created_at timestamp with time zone default CURRENT_TIMESTAMP NOT NULL,
+    CONSTRAINT card_revisions_pkey PRIMARY KEY (card_id, revision)
+);
+
+-- What existing cards say now becomes their first revision, by an unknown author.
+INSERT INTO card_revisions (card_id, revision, question, answer, category, hint, created_at)
+SELECT id, 1, question, answer, category, hint, COALESCE(updated_at, created_at)
+FROM cards;
+
+-- Records what a card says now as its next revision. Called after every write to the card,
+-- whose row lock keeps two writers from taking the same number.
+CREATE OR REPLACE FUNCTION add_card_revision(p_card_id UUID, p_user_id UUID)
+RETURNS integer AS $$
+    INSERT INTO card_revisions (card_id, revision, question, answer, category, hint, user_id)
+    SELECT
+        c.id,
+        COALESCE((SELECT MAX(r.revision) FROM card_revisions r WHERE r.card_id = c.id), 0) + 1,
+        c.question,
+        c.answer,
+        c.category,
+        c.hint,
+        p_user_id
+    FROM cards c
+    WHERE c.id = p_card_id
+    RETURNING revision;
+$$ LANGUAGE sql;
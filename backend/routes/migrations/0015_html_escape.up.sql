@@ -0,0 +1,7 @@
+-- Escapes text for HTML, so search can highlight matches with <mark> in text that may itself
+-- look like markup. ts_headline treats the entities as single tokens and never splits them.
+CREATE OR REPLACE FUNCTION html_escape(p_text text)
+RETURNS text AS $$
+    SELECT replace(replace(replace(replace(replace(
+        p_text, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;');
+$$ LANGUAGE sql IMMUTABLE STRICT;
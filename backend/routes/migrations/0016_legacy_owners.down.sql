@@ -0,0 +1,30 @@
+-- The owners handed out stay, only ownerless rows are shared with everyone again.
+CREATE OR REPLACE FUNCTION course_role(p_course_id UUID, p_user_id UUID)
+RETURNS text AS $$
+    SELECT
+        CASE
+            WHEN c.user_id IS NULL OR c.user_id = p_user_id THEN 'owner'
+            ELSE (
+                SELECT m.role
+                FROM course_members m
+                WHERE m.course_id = c.id AND m.user_id = p_user_id
+            )
+        END
+    FROM courses c
+    WHERE c.id = p_course_id AND c.deleted_at IS NULL;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION category_lock(p_course_id UUID, p_name TEXT, p_user_id UUID)
+RETURNS text AS $$
+    SELECT r.name
+    FROM categories c
+    JOIN categories r ON r.id = c.unlock_after
+    WHERE c.course_id = p_course_id AND c.name = p_name
+    AND NOT EXISTS (
+        SELECT 1
+        FROM quizzes q
+        WHERE q.course_id = r.course_id AND q.category = r.name AND q.is_completed
+        AND q.deleted_at IS NULL
+        AND (q.user_id IS NULL OR q.user_id = p_user_id)
+    );
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,37 @@
+-- Courses and quizzes from before user accounts had no owner, and every user owned them. They
+-- now belong to the first user to register, and a missing owner grants nothing. Without any
+-- users yet, the first one to register claims them.
+UPDATE courses SET user_id = (SELECT id FROM users ORDER BY created_at, id LIMIT 1)
+WHERE user_id IS NULL;
+
+UPDATE quizzes SET user_id = (SELECT id FROM users ORDER BY created_at, id LIMIT 1)
+WHERE user_id IS NULL;
+
+CREATE OR REPLACE FUNCTION course_role(p_course_id UUID, p_user_id UUID)
+RETURNS text AS $$
+    SELECT
+        CASE
+            WHEN c.user_id = p_user_id THEN 'owner'
+            ELSE (
+                SELECT m.role
+                FROM course_members m
+                WHERE m.course_id = c.id AND m.user_id = p_user_id
+            )
+        END
+    FROM courses c
+    WHERE c.id = p_course_id AND c.deleted_at IS NULL;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION category_lock(p_course_id UUID, p_name TEXT, p_user_id UUID)
+RETURNS text AS $$
+    SELECT r.name
+    FROM categories c
+    JOIN categories r ON r.id = c.unlock_after
+    WHERE c.course_id = p_course_id AND c.name = p_name
+    AND NOT EXISTS (
+        SELECT 1
+        FROM quizzes q
+        WHERE q.course_id = r.course_id AND q.category = r.name AND q.is_completed
+        AND q.deleted_at IS NULL AND q.user_id = p_user_id
+    );
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,15 @@
+-- Only the most recent review state of each card can be kept.
+DELETE FROM card_reviews r
+WHERE EXISTS (
+    SELECT 1 FROM card_reviews o
+    WHERE o.card_id = r.card_id
+    AND (o.last_reviewed_at, o.user_id) > (r.last_reviewed_at, r.user_id)
+);
+
+DROP INDEX IF EXISTS review_log_user_id_idx;
+ALTER TABLE review_log DROP COLUMN IF EXISTS user_id;
+
+DROP INDEX IF EXISTS card_reviews_card_id_idx;
+ALTER TABLE card_reviews DROP CONSTRAINT card_reviews_pkey;
+ALTER TABLE card_reviews ADD CONSTRAINT card_reviews_pkey PRIMARY KEY (card_id);
+ALTER TABLE card_reviews DROP COLUMN IF EXISTS user_id;
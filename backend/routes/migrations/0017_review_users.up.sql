@@ -0,0 +1,33 @@
+-- Every user who could see a card shared its review state. State and log now belong to the
+-- user who reviewed, and what was recorded so far goes to the owner of the card's course.
+ALTER TABLE card_reviews
+    ADD COLUMN user_id uuid
+        CONSTRAINT card_reviews_user_id_fkey REFERENCES users (id) ON DELETE CASCADE;
+
+UPDATE card_reviews r SET user_id = co.user_id
+FROM cards c
+JOIN courses co ON co.id = c.course_id
+WHERE c.id = r.card_id;
+
+DELETE FROM card_reviews WHERE user_id IS NULL;
+
+ALTER TABLE card_reviews ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE card_reviews DROP CONSTRAINT card_reviews_pkey;
+ALTER TABLE card_reviews ADD CONSTRAINT card_reviews_pkey PRIMARY KEY (user_id, card_id);
+
+CREATE INDEX card_reviews_card_id_idx ON card_reviews (card_id);
+
+ALTER TABLE review_log
+    ADD COLUMN user_id uuid
+        CONSTRAINT review_log_user_id_fkey REFERENCES users (id) ON DELETE CASCADE;
+
+UPDATE review_log l SET user_id = co.user_id
+FROM cards c
+JOIN courses co ON co.id = c.course_id
+WHERE c.id = l.card_id;
+
+DELETE FROM review_log WHERE user_id IS NULL;
+
+ALTER TABLE review_log ALTER COLUMN user_id SET NOT NULL;
+
+CREATE INDEX review_log_user_id_idx ON review_log (user_id);
@@ -0,0 +1,9 @@
+ALTER TABLE cards
+    DROP CONSTRAINT cards_category_fkey,
+    ADD CONSTRAINT cards_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE ON DELETE CASCADE;
+
+ALTER TABLE quizzes
+    DROP CONSTRAINT quizzes_category_fkey,
+    ADD CONSTRAINT quizzes_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE ON DELETE CASCADE;
@@ -0,0 +1,11 @@
+-- Deleting a category took its cards and quizzes along, the ones in the trash as well. A
+-- category now has to be emptied before it can be deleted.
+ALTER TABLE cards
+    DROP CONSTRAINT cards_category_fkey,
+    ADD CONSTRAINT cards_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE;
+
+ALTER TABLE quizzes
+    DROP CONSTRAINT quizzes_category_fkey,
+    ADD CONSTRAINT quizzes_category_fkey FOREIGN KEY (course_id, category)
+        REFERENCES categories (course_id, name) ON UPDATE CASCADE;
@@ -0,0 +1,74 @@
+-- Course progress counted the quizzes of everyone in the course, so members of a shared course
+-- saw each other's progress blended into their own. The view now has a row per member of each
+-- course, `member_id`, with progress over only their quizzes.
+DROP VIEW courses_view;
+
+CREATE VIEW courses_view AS
+SELECT
+    c.id,
+    c.user_id,
+    m.user_id AS member_id,
+    c.name,
+    c.course_code,
+    c.description,
+    c.created_at,
+    c.updated_at,
+    COALESCE(f.questions, 0) AS questions,
+    CASE
+        WHEN COALESCE(q.total_quizzes, 0) = 0 THEN 0
+        ELSE ROUND((COALESCE(q.completed_quizzes, 0)::float / q.total_quizzes) * 100)::integer
+    END AS progress,
+    COALESCE(cat.categories, ARRAY[]::text[]) AS categories
+FROM
+    courses c
+JOIN (
+    SELECT
+        id AS course_id,
+        user_id
+    FROM
+        courses
+    WHERE
+        user_id IS NOT NULL
+    UNION
+    SELECT
+        course_id,
+        user_id
+    FROM
+        course_members
+) m ON c.id = m.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        COUNT(*) AS questions
+    FROM
+        cards
+    WHERE
+        deleted_at IS NULL
+    GROUP BY
+        course_id
+) f ON c.id = f.course_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        user_id,
+        COUNT(*) AS total_quizzes,
+        COUNT(CASE WHEN is_completed THEN 1 END) AS completed_quizzes
+    FROM
+        quizzes
+    WHERE
+        deleted_at IS NULL
+    GROUP BY
+        course_id,
+        user_id
+) q ON c.id = q.course_id AND m.user_id = q.user_id
+LEFT JOIN (
+    SELECT
+        course_id,
+        ARRAY_AGG(name ORDER BY position) AS categories
+    FROM
+        categories
+    GROUP BY
+        course_id
+) cat ON c.id = cat.course_id
+WHERE
+    c.deleted_at IS NULL;
@@ -0,0 +1,73 @@
+//! Visibility checks shared by the Postgres implementations. Courses are visible to their
+//! owner and members, quizzes to the user who started them. Rows without an owner predate
+//! user accounts and are handed to the first user to register, and grant nothing to anyone
+//! else. Trashed rows are not visible to anyone, nor is anything in a trashed course, which
+//! `course_role()` already leaves without members.
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::{KeikoError, KeikoResult};
+
+/// The id of the course, failing with not found unless `user` is a member of it.
+pub(crate) async fn course<'e>(
+    executor: impl PgExecutor<'e>,
+    user: &User,
+    course_code: &str,
+) -> KeikoResult<Uuid> {
+    sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM courses WHERE course_code = $1 AND course_role(id, $2) IS NOT NULL",
+    )
+    .bind(course_code)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::not_found("course"))
+}
+
+/// Fails with not found unless `user` is a member of the card's course.
+pub(crate) async fn card<'e>(
+    executor: impl PgExecutor<'e>,
+    user: &User,
+    card_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT c.id
+        FROM cards c
+        JOIN courses co ON co.id = c.course_id
+        WHERE c.id = $1 AND c.deleted_at IS NULL AND co.deleted_at IS NULL
+        AND course_role(co.id, $2) IS NOT NULL
+        "#,
+    )
+    .bind(card_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| KeikoError::not_found("card"))
+}
+
+/// Fails with not found unless `user` owns the quiz.
+pub(crate) async fn quiz<'e>(
+    executor: impl PgExecutor<'e>,
+    user: &User,
+    quiz_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT q.id
+        FROM quizzes q
+        JOIN courses co ON co.id = q.course_id
+        WHERE q.id = $1 AND q.user_id = $2
+        AND q.deleted_at IS NULL AND co.deleted_at IS NULL
+        "#,
+    )
+    .bind(quiz_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| KeikoError::not_found("quiz"))
+}
@@ -0,0 +1,825 @@
+//! Reading and writing Anki `.apkg` packages, using the legacy collection schema (version 11)
+//! that every Anki release can open.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+
+use ntex::util::Bytes;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection, Executor, SqliteConnection};
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::card_api::{Card, CreateCard};
+use crate::course_api::{CourseView, CreateCourse};
+use crate::import_api::{ImportAPI, ImportBatch, ImportSummary};
+use crate::{KeikoError, KeikoResult};
+
+const DEFAULT_CATEGORY: &str = "General";
+const FIELD_SEPARATOR: char = '\x1f';
+const DECK_SEPARATOR: &str = "::";
+/// Limit on the unpacked collection, so a small upload cannot inflate into an unbounded buffer.
+const MAX_COLLECTION_SIZE: u64 = 256 * 1024 * 1024;
+/// Fixed so that re-exported decks update the same note type in Anki instead of adding a new one.
+const MODEL_ID: i64 = 1_700_000_000_000;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct AnkiImportOptions {
+    /// Put every note into this course instead of one course per top-level deck.
+    pub course_code: Option<String>,
+    /// Name for the course when it has to be created, defaults to the deck name.
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AnkiModel {
+    #[serde(rename = "type", default)]
+    kind: i64,
+    flds: Vec<AnkiField>,
+}
+
+#[derive(Deserialize)]
+struct AnkiField {
+    name: String,
+    ord: usize,
+}
+
+#[derive(Deserialize)]
+struct AnkiDeck {
+    name: String,
+}
+
+/// A collection database in the temp directory, removed on drop.
+struct TempCollection(PathBuf);
+
+impl TempCollection {
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("keiko-{}.anki2", Uuid::new_v4())))
+    }
+
+    async fn connect(&self) -> KeikoResult<SqliteConnection> {
+        SqliteConnectOptions::new()
+            .filename(&self.0)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Delete)
+            .connect()
+            .await
+            .map_err(invalid)
+    }
+}
+
+impl Drop for TempCollection {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn invalid(e: impl std::fmt::Display) -> KeikoError {
+    KeikoError::Validation(format!("invalid Anki package: {}", e))
+}
+
+/// Runs `f` on the blocking thread pool. Packages are unzipped and zipped and collections
+/// written out there, as a large one would otherwise hold up every request on the worker.
+async fn blocking<T, F>(f: F) -> KeikoResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> KeikoResult<T> + Send + 'static,
+{
+    ntex::rt::spawn_blocking(f)
+        .await
+        .map_err(|e| KeikoError::Database(e.to_string()))?
+}
+
+/// Imports an uploaded `.apkg`. Notes whose question the course has already, or that repeat an
+/// earlier note, are skipped, so importing the same package again adds nothing.
+pub async fn import<S: ImportAPI>(
+    stack: &S,
+    user: &User,
+    bytes: Bytes,
+    options: &AnkiImportOptions,
+) -> KeikoResult<ImportSummary> {
+    let mut batch = read_package(bytes, options).await?;
+    let check = stack.check_batch(user, &batch).await?;
+
+    let existing: HashSet<usize> = check.existing_cards.into_iter().collect();
+    let mut seen = HashSet::new();
+    let cards = std::mem::take(&mut batch.cards);
+    let count = cards.len();
+
+    batch.cards = cards
+        .into_iter()
+        .enumerate()
+        .filter(|(i, card)| {
+            !existing.contains(i) && seen.insert((card.course_code.clone(), card.question.clone()))
+        })
+        .map(|(_, card)| card)
+        .collect();
+
+    let duplicates_skipped = (count - batch.cards.len()) as u64;
+    let summary = if batch.cards.is_empty() {
+        ImportSummary::default()
+    } else {
+        stack.import_batch(user, &batch).await?
+    };
+
+    Ok(ImportSummary {
+        duplicates_skipped,
+        ..summary
+    })
+}
+
+/// Turns an uploaded `.apkg` into courses and cards. Subdecks become categories.
+pub async fn read_package(bytes: Bytes, options: &AnkiImportOptions) -> KeikoResult<ImportBatch> {
+    let file = blocking(move || {
+        let collection = extract_collection(&bytes, MAX_COLLECTION_SIZE)?;
+        let file = TempCollection::new();
+        std::fs::write(&file.0, collection).map_err(|e| KeikoError::Database(e.to_string()))?;
+        Ok(file)
+    })
+    .await?;
+
+    let mut conn = file.connect().await?;
+
+    let (models, decks) = sqlx::query_as::<_, (String, String)>("SELECT models, decks FROM col")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(invalid)?;
+    let models: HashMap<String, AnkiModel> = serde_json::from_str(&models).map_err(invalid)?;
+    let decks: HashMap<String, AnkiDeck> = serde_json::from_str(&decks).map_err(invalid)?;
+
+    let notes = sqlx::query_as::<_, (i64, String, Option<i64>)>(
+        r#"
+        SELECT n.mid, n.flds, (SELECT c.did FROM cards c WHERE c.nid = n.id ORDER BY c.ord LIMIT 1)
+        FROM notes n
+        ORDER BY n.id
+        "#,
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(invalid)?;
+
+    conn.close().await.map_err(invalid)?;
+
+    let mut batch = ImportBatch::default();
+
+    for (mid, flds, did) in notes {
+        let Some(deck) = did.and_then(|did| decks.get(&did.to_string())) else {
+            continue;
+        };
+        let Some((question, answer, hint)) = note_to_card(models.get(&mid.to_string()), &flds)
+        else {
+            continue;
+        };
+
+        let mut path = deck.name.split(DECK_SEPARATOR);
+        let deck_name = path.next().unwrap_or_default().trim().to_owned();
+        let category = path.collect::<Vec<_>>().join(DECK_SEPARATOR);
+        let category = if category.trim().is_empty() {
+            DEFAULT_CATEGORY.to_owned()
+        } else {
+            category
+        };
+
+        let course_code = options
+            .course_code
+            .clone()
+            .unwrap_or_else(|| course_code_for(&deck_name));
+
+        if !batch.courses.iter().any(|c| c.course_code == course_code) {
+            batch.courses.push(CreateCourse {
+                name: options.name.clone().unwrap_or(deck_name),
+                course_code: course_code.clone(),
+                description: "Imported from Anki".to_owned(),
+            });
+        }
+
+        batch.cards.push(CreateCard {
+            question,
+            answer,
+            course_code,
+            category,
+            hint,
+        });
+    }
+
+    if batch.cards.is_empty() {
+        return Err(invalid("no notes found"));
+    }
+
+    Ok(batch)
+}
+
+fn extract_collection(bytes: &[u8], limit: u64) -> KeikoResult<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+
+    // Newer exports keep the real collection in `collection.anki21b`, compressed in a format
+    // older Anki versions cannot read, and leave a placeholder deck in `collection.anki2`.
+    let name = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|name| archive.index_for_name(name).is_some());
+
+    let name = match name {
+        Some("collection.anki2") if archive.index_for_name("collection.anki21b").is_some() => {
+            return Err(invalid(
+                "re-export with \"Support older Anki versions\" enabled",
+            ))
+        }
+        Some(name) => name,
+        None => return Err(invalid("no collection found")),
+    };
+
+    let mut collection = Vec::new();
+    archive
+        .by_name(name)
+        .map_err(invalid)?
+        .take(limit + 1)
+        .read_to_end(&mut collection)
+        .map_err(invalid)?;
+
+    if collection.len() as u64 > limit {
+        return Err(invalid(format!(
+            "the collection is larger than {} MiB",
+            limit / (1024 * 1024)
+        )));
+    }
+
+    Ok(collection)
+}
+
+fn note_to_card(model: Option<&AnkiModel>, flds: &str) -> Option<(String, String, Option<String>)> {
+    let fields: Vec<&str> = flds.split(FIELD_SEPARATOR).collect();
+    let field = |name: &str, fallback: Option<usize>| {
+        model
+            .and_then(|m| m.flds.iter().find(|f| f.name.eq_ignore_ascii_case(name)))
+            .map(|f| f.ord)
+            .or(fallback)
+            .and_then(|ord| fields.get(ord))
+            .map(|f| html_to_text(f))
+    };
+
+    let (question, answer, hint) = if model.is_some_and(|m| m.kind == 1) {
+        let text = field("Text", Some(0))?;
+        (cloze_question(&text), cloze_answer(&text), None)
+    } else {
+        (
+            field("Front", Some(0))?,
+            field("Back", Some(1))?,
+            field("Hint", None).filter(|h| !h.is_empty()),
+        )
+    };
+
+    if question.is_empty() || answer.is_empty() {
+        return None;
+    }
+
+    Some((question, answer, hint))
+}
+
+/// Splits `{{c1::answer::hint}}` deletions into (text before, answer, rest).
+fn next_cloze(text: &str) -> Option<(&str, &str, &str)> {
+    let start = text.find("{{c")?;
+    let body = &text[start + 3..];
+    let sep = body.find("::")?;
+    if !body[..sep].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let end = body.find("}}")?;
+    let content = body.get(sep + 2..end)?;
+    let answer = content.split("::").next().unwrap_or(content);
+
+    Some((&text[..start], answer, &body[end + 2..]))
+}
+
+fn cloze_question(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some((before, _, after)) = next_cloze(rest) {
+        out.push_str(before);
+        out.push_str("[...]");
+        rest = after;
+    }
+    out.push_str(rest);
+
+    out
+}
+
+fn cloze_answer(text: &str) -> String {
+    let mut answers = Vec::new();
+    let mut rest = text;
+    while let Some((_, answer, after)) = next_cloze(rest) {
+        answers.push(answer.trim());
+        rest = after;
+    }
+
+    answers.join(", ")
+}
+
+/// Derives a course code such as `BIOLOGY-101` from a deck name.
+fn course_code_for(deck_name: &str) -> String {
+    let code = deck_name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_uppercase();
+
+    if code.is_empty() {
+        "ANKI".to_owned()
+    } else {
+        code
+    }
+}
+
+/// Converts an Anki HTML field to Keiko's plain text.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            text.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let tag = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if matches!(tag.as_str(), "br" | "div" | "p" | "li" | "tr") && !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    while let Some(start) = text.find("[sound:") {
+        match text[start..].find(']') {
+            Some(end) => text.replace_range(start..start + end + 1, ""),
+            None => break,
+        }
+    }
+
+    decode_entities(&text)
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_owned()
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let c = match entity {
+                    "nbsp" => Some(' '),
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" | "#39" => Some('\''),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .map(|hex| u32::from_str_radix(hex, 16))
+                        .or_else(|| entity.strip_prefix('#').map(str::parse))
+                        .and_then(Result::ok)
+                        .and_then(char::from_u32),
+                }?;
+                Some((c, end + 2))
+            });
+
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Converts Keiko's plain text to an Anki HTML field.
+pub fn text_to_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+const COLLECTION_SCHEMA: &str = r#"
+CREATE TABLE col (
+    id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+    conf text not null, models text not null, decks text not null, dconf text not null,
+    tags text not null
+);
+CREATE TABLE notes (
+    id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null,
+    csum integer not null, flags integer not null, data text not null
+);
+CREATE TABLE cards (
+    id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null,
+    due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null, odid integer not null,
+    flags integer not null, data text not null
+);
+CREATE TABLE revlog (
+    id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+    ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+    type integer not null
+);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+"#;
+
+fn deck_json(id: i64, name: &str, description: &str, now: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "desc": description,
+        "conf": 1,
+        "dyn": 0,
+        "collapsed": false,
+        "extendNew": 10,
+        "extendRev": 50,
+        "mod": now,
+        "usn": -1,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+    })
+}
+
+fn model_json(deck_id: i64, now: i64) -> serde_json::Value {
+    let field = |name: &str, ord: usize| {
+        json!({
+            "name": name,
+            "ord": ord,
+            "font": "Arial",
+            "size": 20,
+            "media": [],
+            "rtl": false,
+            "sticky": false,
+        })
+    };
+
+    json!({
+        "id": MODEL_ID,
+        "name": "Keiko",
+        "type": 0,
+        "did": deck_id,
+        "mod": now,
+        "usn": -1,
+        "sortf": 0,
+        "tags": [],
+        "vers": [],
+        "flds": [field("Front", 0), field("Back", 1), field("Hint", 2)],
+        "tmpls": [{
+            "name": "Card 1",
+            "ord": 0,
+            "qfmt": "{{Front}}{{#Hint}}<br>{{hint:Hint}}{{/Hint}}",
+            "afmt": "{{FrontSide}}<hr id=answer>{{Back}}",
+            "bqfmt": "",
+            "bafmt": "",
+            "did": null,
+        }],
+        "req": [[0, "all", [0]]],
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+    })
+}
+
+fn deck_config_json() -> serde_json::Value {
+    json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": 0,
+            "usn": 0,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "new": {
+                "bury": true,
+                "delays": [1, 10],
+                "initialFactor": 2500,
+                "ints": [1, 4, 7],
+                "order": 1,
+                "perDay": 20,
+                "separate": true,
+            },
+            "rev": {
+                "bury": true,
+                "ease4": 1.3,
+                "fuzz": 0.05,
+                "ivlFct": 1,
+                "maxIvl": 36500,
+                "minSpace": 1,
+                "perDay": 100,
+            },
+            "lapse": {
+                "delays": [10],
+                "leechAction": 0,
+                "leechFails": 8,
+                "minInt": 1,
+                "mult": 0,
+            },
+        }
+    })
+}
+
+/// Anki's duplicate check: the first 8 hex digits of the SHA-1 of the sort field.
+fn field_checksum(text: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(text).digest().bytes();
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+/// Builds an `.apkg` for a course, with one subdeck per category.
+pub async fn write_package(course: &CourseView, cards: &[Card]) -> KeikoResult<Vec<u8>> {
+    let now = chrono::Utc::now();
+    let (now_secs, now_ms) = (now.timestamp(), now.timestamp_millis());
+
+    let course_deck_id = now_ms;
+    let categories: BTreeSet<&str> = cards.iter().map(|c| c.category.as_str()).collect();
+    let deck_ids: HashMap<&str, i64> = categories
+        .iter()
+        .zip(1..)
+        .map(|(category, i)| (*category, course_deck_id + i))
+        .collect();
+
+    let mut decks = serde_json::Map::new();
+    decks.insert("1".to_owned(), deck_json(1, "Default", "", now_secs));
+    decks.insert(
+        course_deck_id.to_string(),
+        deck_json(course_deck_id, &course.name, &course.description, now_secs),
+    );
+    for (category, id) in &deck_ids {
+        let name = format!("{}{}{}", course.name, DECK_SEPARATOR, category);
+        decks.insert(id.to_string(), deck_json(*id, &name, "", now_secs));
+    }
+
+    let models = json!({ MODEL_ID.to_string(): model_json(course_deck_id, now_secs) });
+    let conf = json!({
+        "activeDecks": [1],
+        "curDeck": 1,
+        "curModel": MODEL_ID.to_string(),
+        "nextPos": cards.len() + 1,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    });
+
+    let file = TempCollection::new();
+    let mut conn = file.connect().await?;
+    let mut tx = conn.begin().await?;
+
+    tx.execute(COLLECTION_SCHEMA).await?;
+
+    sqlx::query("INSERT INTO col VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')")
+        .bind(now_secs - now_secs % 86_400)
+        .bind(now_ms)
+        .bind(now_ms)
+        .bind(conf.to_string())
+        .bind(models.to_string())
+        .bind(serde_json::Value::Object(decks).to_string())
+        .bind(deck_config_json().to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    for (card, (position, id)) in cards.iter().zip((1..).map(|i| (i, now_ms + i))) {
+        let fields = [
+            text_to_html(&card.question),
+            text_to_html(&card.answer),
+            text_to_html(card.hint.as_deref().unwrap_or_default()),
+        ]
+        .join(&FIELD_SEPARATOR.to_string());
+
+        // The Keiko card id doubles as the note guid, so re-importing updates the note.
+        sqlx::query("INSERT INTO notes VALUES (?, ?, ?, ?, -1, '', ?, ?, ?, 0, '')")
+            .bind(id)
+            .bind(card.id.simple().to_string())
+            .bind(MODEL_ID)
+            .bind(now_secs)
+            .bind(fields)
+            .bind(&card.question)
+            .bind(field_checksum(&card.question))
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO cards VALUES (?, ?, ?, 0, ?, -1, 0, 0, ?, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+        )
+        .bind(id)
+        .bind(id)
+        .bind(deck_ids[card.category.as_str()])
+        .bind(now_secs)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    conn.close().await?;
+
+    blocking(move || {
+        let collection = std::fs::read(&file.0).map_err(|e| KeikoError::Database(e.to_string()))?;
+
+        let mut package = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        let zip_error = |e: zip::result::ZipError| KeikoError::Database(e.to_string());
+
+        package
+            .start_file("collection.anki2", options)
+            .map_err(zip_error)?;
+        package
+            .write_all(&collection)
+            .map_err(|e| KeikoError::Database(e.to_string()))?;
+        package.start_file("media", options).map_err(zip_error)?;
+        package
+            .write_all(b"{}")
+            .map_err(|e| KeikoError::Database(e.to_string()))?;
+
+        Ok(package.finish().map_err(zip_error)?.into_inner())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut package = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            package
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            package.write_all(contents).unwrap();
+        }
+        package.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn html_to_text_keeps_line_breaks_and_drops_markup() {
+        assert_eq!(
+            html_to_text("<b>bold</b> and <i>italic</i>"),
+            "bold and italic"
+        );
+        assert_eq!(html_to_text("one<br>two<br/>three"), "one\ntwo\nthree");
+        assert_eq!(html_to_text("<div>one</div><div>two</div>"), "one\ntwo");
+        assert_eq!(html_to_text("<ul><li>a</li><li>b</li></ul>"), "a\nb");
+        assert_eq!(html_to_text("  <p>padded</p>  "), "padded");
+        assert_eq!(html_to_text("a < b"), "a < b");
+    }
+
+    #[test]
+    fn html_to_text_decodes_entities_and_strips_sounds() {
+        assert_eq!(
+            html_to_text("Tom &amp; Jerry&nbsp;&lt;3 &quot;hi&quot; &#39;x&#39;"),
+            "Tom & Jerry <3 \"hi\" 'x'"
+        );
+        assert_eq!(html_to_text("caf&#233; &#xE9;"), "café é");
+        assert_eq!(html_to_text("AT&T &bogus; &"), "AT&T &bogus; &");
+        assert_eq!(html_to_text("hello [sound:hello.mp3]"), "hello");
+    }
+
+    #[test]
+    fn text_to_html_round_trips() {
+        let text = "if a < b && c > \"d\"\nthen";
+        assert_eq!(html_to_text(&text_to_html(text)), text);
+    }
+
+    #[test]
+    fn cloze_deletions_become_question_and_answer() {
+        let text = "{{c1::Paris}} is the capital of {{c2::France::country}}";
+        assert_eq!(cloze_question(text), "[...] is the capital of [...]");
+        assert_eq!(cloze_answer(text), "Paris, France");
+
+        assert_eq!(cloze_question("no deletions"), "no deletions");
+        assert_eq!(cloze_answer("no deletions"), "");
+        assert_eq!(cloze_question("{{cx::not a cloze}}"), "{{cx::not a cloze}}");
+    }
+
+    #[test]
+    fn cloze_notes_use_the_text_field() {
+        let model = AnkiModel {
+            kind: 1,
+            flds: vec![
+                AnkiField {
+                    name: "Extra".to_owned(),
+                    ord: 0,
+                },
+                AnkiField {
+                    name: "Text".to_owned(),
+                    ord: 1,
+                },
+            ],
+        };
+        let flds = format!("extra{}<b>{{{{c1::H2O}}}}</b> is water", FIELD_SEPARATOR);
+        assert_eq!(
+            note_to_card(Some(&model), &flds),
+            Some(("[...] is water".to_owned(), "H2O".to_owned(), None))
+        );
+    }
+
+    #[test]
+    fn basic_notes_fall_back_to_the_first_two_fields() {
+        let flds = format!("Front{}Back", FIELD_SEPARATOR);
+        assert_eq!(
+            note_to_card(None, &flds),
+            Some(("Front".to_owned(), "Back".to_owned(), None))
+        );
+        assert_eq!(note_to_card(None, "only a front"), None);
+        assert_eq!(
+            note_to_card(None, &format!("Front{}", FIELD_SEPARATOR)),
+            None
+        );
+    }
+
+    #[test]
+    fn course_codes_come_from_deck_names() {
+        assert_eq!(course_code_for("Biology 101"), "BIOLOGY-101");
+        assert_eq!(course_code_for("  Français: vocab "), "FRANÇAIS-VOCAB");
+        assert_eq!(course_code_for("::"), "ANKI");
+    }
+
+    #[test]
+    fn extracts_the_collection_within_the_limit() {
+        let bytes = package(&[("collection.anki2", b"legacy"), ("media", b"{}")]);
+        assert_eq!(extract_collection(&bytes, 6).unwrap(), b"legacy");
+
+        let bytes = package(&[
+            ("collection.anki2", b"legacy"),
+            ("collection.anki21", b"new"),
+        ]);
+        assert_eq!(extract_collection(&bytes, 6).unwrap(), b"new");
+    }
+
+    #[test]
+    fn oversized_collections_are_rejected() {
+        let bytes = package(&[("collection.anki2", &[0; 1024])]);
+        assert!(matches!(
+            extract_collection(&bytes, 1023),
+            Err(KeikoError::Validation(_))
+        ));
+        assert!(extract_collection(&bytes, 1024).is_ok());
+    }
+
+    #[test]
+    fn packages_without_a_readable_collection_are_rejected() {
+        for bytes in [
+            b"not a zip".to_vec(),
+            package(&[("media", b"{}")]),
+            package(&[("collection.anki2", b""), ("collection.anki21b", b"")]),
+        ] {
+            assert!(matches!(
+                extract_collection(&bytes, MAX_COLLECTION_SIZE),
+                Err(KeikoError::Validation(_))
+            ));
+        }
+    }
+}
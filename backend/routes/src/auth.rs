@@ -0,0 +1,162 @@
+use ntex::web::{self, types::State, HttpRequest, HttpResponse, ServiceConfig};
+
+use crate::auth_api::{AuthAPI, CreateApiToken, Credentials, User};
+use crate::extract::{Json, Path};
+use crate::session::bearer_token;
+use crate::KeikoResult;
+use uuid::Uuid;
+
+pub fn service<S: AuthAPI>(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/v1/auth")
+            .route("/register", web::post().to(register::<S>))
+            .route("/login", web::post().to(login::<S>))
+            .route("/logout", web::post().to(logout::<S>))
+            .route("/me", web::get().to(me))
+            .route("/tokens", web::get().to(get_api_tokens::<S>))
+            .route("/tokens", web::post().to(create_api_token::<S>))
+            .route(
+                "/tokens/{token_id}",
+                web::delete().to(revoke_api_token::<S>),
+            ),
+    );
+}
+
+/// POST /v1/auth/register
+async fn register<S: AuthAPI>(
+    credentials: Json<Credentials>,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let user = stack.register(&credentials).await?;
+    Ok(HttpResponse::Ok().json(&user))
+}
+
+/// POST /v1/auth/login
+async fn login<S: AuthAPI>(
+    credentials: Json<Credentials>,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let session = stack.login(&credentials).await?;
+    Ok(HttpResponse::Ok().json(&session))
+}
+
+/// POST /v1/auth/logout
+async fn logout<S: AuthAPI>(
+    req: HttpRequest,
+    _: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    if let Some(token) = bearer_token(req.headers()) {
+        stack.logout(token).await?;
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// GET /v1/auth/me
+async fn me(user: User) -> KeikoResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(&user))
+}
+
+/// GET /v1/auth/tokens
+async fn get_api_tokens<S: AuthAPI>(user: User, stack: State<S>) -> KeikoResult<HttpResponse> {
+    let tokens = stack.get_api_tokens(&user).await?;
+    Ok(HttpResponse::Ok().json(&tokens))
+}
+
+/// POST /v1/auth/tokens
+async fn create_api_token<S: AuthAPI>(
+    create_token: Json<CreateApiToken>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let token = stack.create_api_token(&user, &create_token).await?;
+    Ok(HttpResponse::Ok().json(&token))
+}
+
+/// DELETE /v1/auth/tokens/{token_id}
+async fn revoke_api_token<S: AuthAPI>(
+    token_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    stack.revoke_api_token(&user, &token_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn requests_without_a_session_are_unauthorized() {
+        let app = testing::app().await;
+
+        let (status, error) = app.send(Method::GET, "/v1/courses", None, ()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["error"], "unauthorized");
+        let (status, _) = app.get("/v1/courses", "not-a-token").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[ntex::test]
+    async fn logging_in_and_out() {
+        let app = testing::app().await;
+        let token = app.login("alice").await;
+
+        let (status, me) = app.get("/v1/auth/me", &token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(me["username"], "alice");
+
+        let wrong = json!({ "username": "alice", "password": "wrong horse" });
+        let (status, _) = app.send(Method::POST, "/v1/auth/login", None, wrong).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = app.post("/v1/auth/logout", &token, ()).await;
+        assert!(status.is_success());
+        let (status, _) = app.get("/v1/auth/me", &token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[ntex::test]
+    async fn usernames_are_taken_once() {
+        let app = testing::app().await;
+        app.login("alice").await;
+
+        let credentials = json!({ "username": "alice", "password": "another horse" });
+        let (status, _) = app
+            .send(Method::POST, "/v1/auth/register", None, credentials)
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[ntex::test]
+    async fn read_tokens_cannot_write() {
+        let app = testing::app().await;
+        let session = app.login("alice").await;
+
+        let create = json!({ "name": "script", "scope": "read" });
+        let (status, created) = app.post("/v1/auth/tokens", &session, create).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(created["expires_at"].is_string());
+        let token = created["token"].as_str().unwrap();
+
+        let (status, _) = app.get("/v1/courses", token).await;
+        assert_eq!(status, StatusCode::OK);
+        let course = json!({ "name": "Course", "course_code": "C1", "description": "" });
+        let (status, _) = app.post("/v1/courses", token, course).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = app
+            .delete(
+                &format!("/v1/auth/tokens/{}", created["id"].as_str().unwrap()),
+                &session,
+            )
+            .await;
+        assert!(status.is_success());
+        let (status, _) = app.get("/v1/courses", token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
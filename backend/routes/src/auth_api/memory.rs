@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::secret::{
+    hash_password, new_api_token, new_token, token_hash, validate, validate_api_token,
+    verify_password,
+};
+use super::{
+    ApiToken, AuthAPI, CreateApiToken, Credentials, NewApiToken, Session, TokenScope, User,
+    SESSION_DAYS,
+};
+use crate::memory::{ApiTokenRow, InMemoryKeiko, SessionRow, UserRow};
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl AuthAPI for InMemoryKeiko {
+    async fn register(&self, credentials: &Credentials) -> KeikoResult<User> {
+        validate(credentials)?;
+        let password_hash = hash_password(&credentials.password)?;
+
+        let mut state = self.state();
+        if state
+            .users
+            .iter()
+            .any(|u| u.user.username == credentials.username)
+        {
+            return Err(KeikoError::Conflict("username is taken".to_owned()));
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: credentials.username.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        state.users.push(UserRow {
+            user: user.clone(),
+            password_hash,
+        });
+
+        Ok(user)
+    }
+
+    async fn login(&self, credentials: &Credentials) -> KeikoResult<Session> {
+        let mut state = self.state();
+        let user = state
+            .users
+            .iter()
+            .find(|u| u.user.username == credentials.username)
+            .filter(|u| verify_password(&credentials.password, &u.password_hash))
+            .map(|u| u.user.clone())
+            .ok_or_else(|| KeikoError::Unauthorized("invalid username or password".to_owned()))?;
+
+        let token = new_token();
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::days(SESSION_DAYS);
+
+        state
+            .sessions
+            .retain(|s| s.user_id != user.id || s.expires_at > now);
+        state.sessions.push(SessionRow {
+            token_hash: token_hash(&token),
+            user_id: user.id,
+            expires_at,
+        });
+
+        Ok(Session {
+            token,
+            user,
+            expires_at,
+        })
+    }
+
+    async fn logout(&self, token: &str) -> KeikoResult<()> {
+        let hash = token_hash(token);
+        self.state().sessions.retain(|s| s.token_hash != hash);
+
+        Ok(())
+    }
+
+    async fn authenticate(&self, token: &str) -> KeikoResult<Option<User>> {
+        let hash = token_hash(token);
+        let state = self.state();
+
+        Ok(state
+            .sessions
+            .iter()
+            .find(|s| s.token_hash == hash && s.expires_at > chrono::Utc::now())
+            .and_then(|s| state.user(&s.user_id))
+            .cloned())
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> KeikoResult<User> {
+        self.state()
+            .users
+            .iter()
+            .map(|u| &u.user)
+            .find(|u| u.username == username)
+            .cloned()
+            .ok_or_else(|| KeikoError::not_found("user"))
+    }
+
+    async fn create_api_token(
+        &self,
+        user: &User,
+        create_token: &CreateApiToken,
+    ) -> KeikoResult<NewApiToken> {
+        validate_api_token(create_token)?;
+
+        let token = new_api_token();
+        let now = chrono::Utc::now();
+        let api_token = ApiToken {
+            id: Uuid::new_v4(),
+            name: create_token.name.trim().to_owned(),
+            scope: create_token.scope,
+            created_at: now,
+            last_used_at: None,
+            expires_at: create_token.expires_at(now),
+        };
+
+        self.state().api_tokens.push(ApiTokenRow {
+            api_token: api_token.clone(),
+            user_id: user.id,
+            token_hash: token_hash(&token),
+        });
+
+        Ok(NewApiToken { token, api_token })
+    }
+
+    async fn get_api_tokens(&self, user: &User) -> KeikoResult<Vec<ApiToken>> {
+        let mut tokens: Vec<ApiToken> = self
+            .state()
+            .api_tokens
+            .iter()
+            .filter(|t| t.user_id == user.id)
+            .map(|t| t.api_token.clone())
+            .collect();
+        tokens.sort_by_key(|t| t.created_at);
+
+        Ok(tokens)
+    }
+
+    async fn revoke_api_token(&self, user: &User, token_id: &Uuid) -> KeikoResult<()> {
+        let mut state = self.state();
+        let before = state.api_tokens.len();
+        state
+            .api_tokens
+            .retain(|t| !(t.api_token.id == *token_id && t.user_id == user.id));
+
+        if state.api_tokens.len() == before {
+            return Err(KeikoError::not_found("token"));
+        }
+
+        Ok(())
+    }
+
+    async fn authenticate_api_token(&self, token: &str) -> KeikoResult<Option<(User, TokenScope)>> {
+        let hash = token_hash(token);
+        let now = chrono::Utc::now();
+        let mut state = self.state();
+
+        let Some(row) = state
+            .api_tokens
+            .iter_mut()
+            .find(|t| t.token_hash == hash && t.api_token.expires_at.is_none_or(|e| e > now))
+        else {
+            return Ok(None);
+        };
+        row.api_token.last_used_at = Some(now);
+        let (user_id, scope) = (row.user_id, row.api_token.scope);
+
+        Ok(state.user(&user_id).map(|user| (user.clone(), scope)))
+    }
+}
@@ -0,0 +1,150 @@
+mod memory;
+mod schema;
+pub mod secret;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use ntex::http::Method;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::KeikoResult;
+
+/// How long a session stays valid after login.
+pub const SESSION_DAYS: i64 = 30;
+/// Lifetime of a personal access token created without one.
+pub const DEFAULT_API_TOKEN_DAYS: i64 = 90;
+/// Longest lifetime a personal access token can be given.
+pub const MAX_API_TOKEN_DAYS: i64 = 365;
+/// Start of every personal access token, telling them apart from session tokens.
+pub const API_TOKEN_PREFIX: &str = "keiko_";
+
+/// Paths under `/v1` a `cards:write` token may change.
+const CARD_WRITE_SCOPES: &[&str] = &["cards", "import"];
+
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Username and password, used both to register and to log in.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Sent back as `Authorization: Bearer <token>`. It cannot be retrieved again later.
+    pub token: String,
+    pub user: User,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// What a personal access token may do on behalf of its user, within the user's own roles.
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[sqlx(type_name = "text")]
+pub enum TokenScope {
+    /// Only safe requests, such as GET.
+    #[serde(rename = "read")]
+    #[sqlx(rename = "read")]
+    Read,
+    /// Also adds, edits and imports cards.
+    #[serde(rename = "cards:write")]
+    #[sqlx(rename = "cards:write")]
+    CardsWrite,
+    /// Anything the user can do.
+    #[serde(rename = "admin")]
+    #[sqlx(rename = "admin")]
+    Admin,
+}
+
+impl TokenScope {
+    /// Whether a token with this scope may make a request.
+    pub fn allows(self, method: &Method, path: &str) -> bool {
+        if method.is_safe() {
+            return true;
+        }
+
+        match self {
+            TokenScope::Read => false,
+            TokenScope::CardsWrite => path
+                .split_once("/v1/")
+                .and_then(|(_, rest)| rest.split('/').next())
+                .is_some_and(|scope| CARD_WRITE_SCOPES.contains(&scope)),
+            TokenScope::Admin => true,
+        }
+    }
+}
+
+/// A personal access token, without its secret.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `None` for tokens that never expire.
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CreateApiToken {
+    pub name: String,
+    pub scope: TokenScope,
+    /// Defaults to `DEFAULT_API_TOKEN_DAYS`.
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+    /// Asks for a token that never expires, instead of giving `expires_in_days`.
+    #[serde(default)]
+    pub never_expires: bool,
+}
+
+impl CreateApiToken {
+    pub fn expires_at(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        (!self.never_expires).then(|| {
+            now + chrono::Duration::days(self.expires_in_days.unwrap_or(DEFAULT_API_TOKEN_DAYS))
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NewApiToken {
+    /// Sent back as `Authorization: Bearer <token>`. It cannot be retrieved again later.
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+}
+
+#[async_trait]
+pub trait AuthAPI: Send + Sync + 'static {
+    async fn register(&self, credentials: &Credentials) -> KeikoResult<User>;
+    async fn login(&self, credentials: &Credentials) -> KeikoResult<Session>;
+    async fn logout(&self, token: &str) -> KeikoResult<()>;
+    /// Resolves a session token to its user, or `None` if it is unknown or expired.
+    async fn authenticate(&self, token: &str) -> KeikoResult<Option<User>>;
+    async fn get_user_by_username(&self, username: &str) -> KeikoResult<User>;
+    async fn create_api_token(
+        &self,
+        user: &User,
+        create_token: &CreateApiToken,
+    ) -> KeikoResult<NewApiToken>;
+    async fn get_api_tokens(&self, user: &User) -> KeikoResult<Vec<ApiToken>>;
+    async fn revoke_api_token(&self, user: &User, token_id: &Uuid) -> KeikoResult<()>;
+    /// Resolves a personal access token to its user and scope, or `None` if it is unknown,
+    /// revoked or expired. Records the time it was used.
+    async fn authenticate_api_token(&self, token: &str) -> KeikoResult<Option<(User, TokenScope)>>;
+}
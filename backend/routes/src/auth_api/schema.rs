@@ -0,0 +1,214 @@
+use async_trait::async_trait;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::secret::{
+    hash_password, new_api_token, new_token, token_hash, validate, validate_api_token,
+    verify_password,
+};
+use super::{
+    ApiToken, AuthAPI, CreateApiToken, Credentials, NewApiToken, Session, TokenScope, User,
+    SESSION_DAYS,
+};
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+#[derive(FromRow)]
+struct UserRow {
+    #[sqlx(flatten)]
+    user: User,
+    password_hash: String,
+}
+
+#[derive(FromRow)]
+struct TokenUserRow {
+    #[sqlx(flatten)]
+    user: User,
+    scope: TokenScope,
+}
+
+#[async_trait]
+impl AuthAPI for KeikoDatabase {
+    /// POST /v1/auth/register
+    async fn register(&self, credentials: &Credentials) -> KeikoResult<User> {
+        validate(credentials)?;
+        let password_hash = hash_password(&credentials.password)?;
+        let mut tx = self.pool.begin().await?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (username, password_hash)
+            VALUES ($1, $2)
+            RETURNING id, username, created_at
+            "#,
+        )
+        .bind(&credentials.username)
+        .bind(password_hash)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match KeikoError::from(e) {
+            KeikoError::Conflict(_) => KeikoError::Conflict("username is taken".to_owned()),
+            e => e,
+        })?;
+
+        // Courses and quizzes from before user accounts belong to the first user, so until
+        // someone registers there can still be some without an owner.
+        for table in ["courses", "quizzes"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET user_id = $1 WHERE user_id IS NULL",
+                table
+            ))
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    /// POST /v1/auth/login
+    async fn login(&self, credentials: &Credentials) -> KeikoResult<Session> {
+        let row = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE username = $1")
+            .bind(&credentials.username)
+            .fetch_optional(&self.pool)
+            .await?
+            .filter(|row| verify_password(&credentials.password, &row.password_hash))
+            .ok_or_else(|| KeikoError::Unauthorized("invalid username or password".to_owned()))?;
+
+        let token = new_token();
+        let expires_at = chrono::Utc::now() + chrono::Duration::days(SESSION_DAYS);
+
+        sqlx::query("DELETE FROM sessions WHERE user_id = $1 AND expires_at <= now()")
+            .bind(row.user.id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("INSERT INTO sessions (token_hash, user_id, expires_at) VALUES ($1, $2, $3)")
+            .bind(token_hash(&token))
+            .bind(row.user.id)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Session {
+            token,
+            user: row.user,
+            expires_at,
+        })
+    }
+
+    /// POST /v1/auth/logout
+    async fn logout(&self, token: &str) -> KeikoResult<()> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+            .bind(token_hash(token))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn authenticate(&self, token: &str) -> KeikoResult<Option<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            SELECT u.id, u.username, u.created_at
+            FROM sessions s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.token_hash = $1 AND s.expires_at > now()
+            "#,
+        )
+        .bind(token_hash(token))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> KeikoResult<User> {
+        sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("user"))
+    }
+
+    /// POST /v1/auth/tokens
+    async fn create_api_token(
+        &self,
+        user: &User,
+        create_token: &CreateApiToken,
+    ) -> KeikoResult<NewApiToken> {
+        validate_api_token(create_token)?;
+
+        let token = new_api_token();
+        let expires_at = create_token.expires_at(chrono::Utc::now());
+
+        let api_token = sqlx::query_as::<_, ApiToken>(
+            r#"
+            INSERT INTO api_tokens (user_id, name, token_hash, scope, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, scope, created_at, last_used_at, expires_at
+            "#,
+        )
+        .bind(user.id)
+        .bind(create_token.name.trim())
+        .bind(token_hash(&token))
+        .bind(create_token.scope)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(NewApiToken { token, api_token })
+    }
+
+    /// GET /v1/auth/tokens
+    async fn get_api_tokens(&self, user: &User) -> KeikoResult<Vec<ApiToken>> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"
+            SELECT id, name, scope, created_at, last_used_at, expires_at
+            FROM api_tokens
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    /// DELETE /v1/auth/tokens/{token_id}
+    async fn revoke_api_token(&self, user: &User, token_id: &Uuid) -> KeikoResult<()> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+            .bind(token_id)
+            .bind(user.id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(KeikoError::not_found("token"));
+        }
+
+        Ok(())
+    }
+
+    async fn authenticate_api_token(&self, token: &str) -> KeikoResult<Option<(User, TokenScope)>> {
+        let row = sqlx::query_as::<_, TokenUserRow>(
+            r#"
+            WITH t AS (
+                UPDATE api_tokens
+                SET last_used_at = now()
+                WHERE token_hash = $1 AND (expires_at IS NULL OR expires_at > now())
+                RETURNING user_id, scope
+            )
+            SELECT u.id, u.username, u.created_at, t.scope
+            FROM t
+            JOIN users u ON u.id = t.user_id
+            "#,
+        )
+        .bind(token_hash(token))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (row.user, row.scope)))
+    }
+}
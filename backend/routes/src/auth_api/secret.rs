@@ -0,0 +1,147 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use super::{CreateApiToken, Credentials, API_TOKEN_PREFIX, MAX_API_TOKEN_DAYS};
+use crate::{KeikoError, KeikoResult};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_PASSWORD_LENGTH: usize = 1024;
+const MAX_USERNAME_LENGTH: usize = 32;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn validate(credentials: &Credentials) -> KeikoResult<()> {
+    let username = &credentials.username;
+    if username.len() < 3 || username.len() > MAX_USERNAME_LENGTH {
+        return Err(KeikoError::Validation(format!(
+            "username must be between 3 and {} characters",
+            MAX_USERNAME_LENGTH
+        )));
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(KeikoError::Validation(
+            "username may only contain letters, digits, '_', '-' and '.'".to_owned(),
+        ));
+    }
+
+    let length = credentials.password.chars().count();
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length) {
+        return Err(KeikoError::Validation(format!(
+            "password must be between {} and {} characters",
+            MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Hashes a password with Argon2id and a random salt, in PHC string format.
+pub fn hash_password(password: &str) -> KeikoResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| KeikoError::Database(e.to_string()))
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .and_then(|hash| Argon2::default().verify_password(password.as_bytes(), &hash))
+        .is_ok()
+}
+
+/// A new random session token, 256 bits in hex.
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex(&bytes)
+}
+
+/// A new random personal access token, prefixed to tell it apart from session tokens.
+pub fn new_api_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, new_token())
+}
+
+pub fn validate_api_token(create_token: &CreateApiToken) -> KeikoResult<()> {
+    if create_token.name.trim().is_empty() {
+        return Err(KeikoError::Validation(
+            "token name must not be empty".to_owned(),
+        ));
+    }
+    if create_token.never_expires && create_token.expires_in_days.is_some() {
+        return Err(KeikoError::Validation(
+            "a token that never expires has no expires_in_days".to_owned(),
+        ));
+    }
+    if create_token
+        .expires_in_days
+        .is_some_and(|days| !(1..=MAX_API_TOKEN_DAYS).contains(&days))
+    {
+        return Err(KeikoError::Validation(format!(
+            "expires_in_days must be between 1 and {}",
+            MAX_API_TOKEN_DAYS
+        )));
+    }
+
+    Ok(())
+}
+
+/// The form a token is stored and looked up in.
+pub fn token_hash(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenScope, DEFAULT_API_TOKEN_DAYS};
+    use super::*;
+
+    fn create_token(expires_in_days: Option<i64>, never_expires: bool) -> CreateApiToken {
+        CreateApiToken {
+            name: "ci".to_owned(),
+            scope: TokenScope::Read,
+            expires_in_days,
+            never_expires,
+        }
+    }
+
+    #[test]
+    fn api_tokens_expire_unless_asked_not_to() {
+        let now = chrono::Utc::now();
+        assert_eq!(
+            create_token(None, false).expires_at(now),
+            Some(now + chrono::Duration::days(DEFAULT_API_TOKEN_DAYS))
+        );
+        assert_eq!(
+            create_token(Some(7), false).expires_at(now),
+            Some(now + chrono::Duration::days(7))
+        );
+        assert_eq!(create_token(None, true).expires_at(now), None);
+    }
+
+    #[test]
+    fn api_token_lifetime_is_bounded() {
+        assert!(validate_api_token(&create_token(None, false)).is_ok());
+        assert!(validate_api_token(&create_token(Some(1), false)).is_ok());
+        assert!(validate_api_token(&create_token(Some(MAX_API_TOKEN_DAYS), false)).is_ok());
+        assert!(validate_api_token(&create_token(None, true)).is_ok());
+        assert!(validate_api_token(&create_token(Some(0), false)).is_err());
+        assert!(validate_api_token(&create_token(Some(MAX_API_TOKEN_DAYS + 1), false)).is_err());
+        assert!(validate_api_token(&create_token(Some(30), true)).is_err());
+    }
+
+    #[test]
+    fn api_token_needs_a_name() {
+        let mut token = create_token(None, false);
+        token.name = "  ".to_owned();
+        assert!(validate_api_token(&token).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::secret::{
+    hash_password, new_api_token, new_token, token_hash, validate, validate_api_token,
+    verify_password,
+};
+use super::{
+    ApiToken, AuthAPI, CreateApiToken, Credentials, NewApiToken, Session, TokenScope, User,
+    SESSION_DAYS,
+};
+use crate::sqlite::KeikoSqlite;
+use crate::{KeikoError, KeikoResult};
+
+#[derive(FromRow)]
+struct UserRow {
+    #[sqlx(flatten)]
+    user: User,
+    password_hash: String,
+}
+
+#[async_trait]
+impl AuthAPI for KeikoSqlite {
+    async fn register(&self, credentials: &Credentials) -> KeikoResult<User> {
+        validate(credentials)?;
+        let password_hash = hash_password(&credentials.password)?;
+        let mut tx = self.pool.begin().await?;
+
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (id, username, password_hash)
+            VALUES ($1, $2, $3)
+            RETURNING id, username, created_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(&credentials.username)
+        .bind(password_hash)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match KeikoError::from(e) {
+            KeikoError::Conflict(_) => KeikoError::Conflict("username is taken".to_owned()),
+            e => e,
+        })?;
+
+        // Courses and quizzes from before user accounts belong to the first user, so until
+        // someone registers there can still be some without an owner.
+        for table in ["courses", "quizzes"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET user_id = $1 WHERE user_id IS NULL",
+                table
+            ))
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    async fn login(&self, credentials: &Credentials) -> KeikoResult<Session> {
+        let row = sqlx::query_as::<_, UserRow>("SELECT * FROM users WHERE username = $1")
+            .bind(&credentials.username)
+            .fetch_optional(&self.pool)
+            .await?
+            .filter(|row| verify_password(&credentials.password, &row.password_hash))
+            .ok_or_else(|| KeikoError::Unauthorized("invalid username or password".to_owned()))?;
+
+        let token = new_token();
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::days(SESSION_DAYS);
+
+        sqlx::query("DELETE FROM sessions WHERE user_id = $1 AND expires_at <= $2")
+            .bind(row.user.id)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("INSERT INTO sessions (token_hash, user_id, expires_at) VALUES ($1, $2, $3)")
+            .bind(token_hash(&token))
+            .bind(row.user.id)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Session {
+            token,
+            user: row.user,
+            expires_at,
+        })
+    }
+
+    async fn logout(&self, token: &str) -> KeikoResult<()> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+            .bind(token_hash(token))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn authenticate(&self, token: &str) -> KeikoResult<Option<User>> {
+        sqlx::query_as::<_, User>(
+            r#"
+            SELECT u.id, u.username, u.created_at
+            FROM sessions s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.token_hash = $1 AND s.expires_at > $2
+            "#,
+        )
+        .bind(token_hash(token))
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> KeikoResult<User> {
+        sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("user"))
+    }
+
+    async fn create_api_token(
+        &self,
+        user: &User,
+        create_token: &CreateApiToken,
+    ) -> KeikoResult<NewApiToken> {
+        validate_api_token(create_token)?;
+
+        let token = new_api_token();
+        let expires_at = create_token.expires_at(chrono::Utc::now());
+
+        let api_token = sqlx::query_as::<_, ApiToken>(
+            r#"
+            INSERT INTO api_tokens (id, user_id, name, token_hash, scope, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, scope, created_at, last_used_at, expires_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user.id)
+        .bind(create_token.name.trim())
+        .bind(token_hash(&token))
+        .bind(create_token.scope)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(NewApiToken { token, api_token })
+    }
+
+    async fn get_api_tokens(&self, user: &User) -> KeikoResult<Vec<ApiToken>> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"
+            SELECT id, name, scope, created_at, last_used_at, expires_at
+            FROM api_tokens
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    async fn revoke_api_token(&self, user: &User, token_id: &Uuid) -> KeikoResult<()> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+            .bind(token_id)
+            .bind(user.id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(KeikoError::not_found("token"));
+        }
+
+        Ok(())
+    }
+
+    async fn authenticate_api_token(&self, token: &str) -> KeikoResult<Option<(User, TokenScope)>> {
+        let Some((user_id, scope)) = sqlx::query_as::<_, (Uuid, TokenScope)>(
+            r#"
+            UPDATE api_tokens
+            SET last_used_at = $2
+            WHERE token_hash = $1 AND (expires_at IS NULL OR expires_at > $2)
+            RETURNING user_id, scope
+            "#,
+        )
+        .bind(token_hash(token))
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let user =
+            sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(user.map(|user| (user, scope)))
+    }
+}
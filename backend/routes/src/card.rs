@@ -1,12 +1,14 @@
+use crate::auth_api::User;
+use crate::card_api::revision::{self, DiffQuery};
 use crate::card_api::{CardAPI, CreateCard, UpdateCard};
-use ntex::web::{
-    self,
-    types::{Json, Path, State},
-    HttpResponse, ServiceConfig,
-};
+use crate::extract::{Json, Path, Query};
+use crate::list::ListQuery;
+use crate::member_api::{self, CourseRef, MemberAPI, Role};
+use crate::KeikoResult;
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
 use uuid::Uuid;
 
-pub fn service<S: CardAPI>(cfg: &mut ServiceConfig) {
+pub fn service<S: CardAPI + MemberAPI>(cfg: &mut ServiceConfig) {
     cfg.service(
         web::scope("/v1/cards")
             .route("", web::get().to(get_cards::<S>))
@@ -14,60 +16,258 @@ pub fn service<S: CardAPI>(cfg: &mut ServiceConfig) {
             .route("/quiz/{quiz_id}", web::get().to(get_cards_by_quiz_id::<S>))
             .route("", web::post().to(add_card::<S>))
             .route("", web::put().to(update_card::<S>))
-            .route("/id/{card_id}", web::delete().to(delete_card::<S>)),
+            .route("/id/{card_id}", web::delete().to(delete_card::<S>))
+            .route(
+                "/id/{card_id}/revisions",
+                web::get().to(get_card_revisions::<S>),
+            )
+            .route(
+                "/id/{card_id}/revisions/diff",
+                web::get().to(diff_card_revisions::<S>),
+            )
+            .route(
+                "/id/{card_id}/revisions/{revision}/revert",
+                web::post().to(revert_card::<S>),
+            ),
     );
 }
 
 /// GET /v1/cards
-async fn get_cards<S: CardAPI>(stack: State<S>) -> HttpResponse {
-    match stack.get_cards().await {
-        Ok(cards) => HttpResponse::Ok().json(&cards),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+///
+/// Answers and hints are blank in courses the user can only view.
+async fn get_cards<S: CardAPI + MemberAPI>(
+    query: ListQuery,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let mut cards = stack.get_cards(&user, &query).await?;
+    member_api::hide_answers(&*stack, &user, &mut cards.items).await?;
+    Ok(cards.respond())
 }
 
 /// GET /v1/cards/id/{card_id}
-async fn get_card<S: CardAPI>(card_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.get_card(&card_id).await {
-        Ok(card) => HttpResponse::Ok().json(&card),
-        Err(_) => HttpResponse::NotFound().body("Not found"),
-    }
+///
+/// The answer and hint are blank in courses the user can only view.
+async fn get_card<S: CardAPI + MemberAPI>(
+    card_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let mut card = stack.get_card(&user, &card_id).await?;
+    member_api::hide_answers(&*stack, &user, std::slice::from_mut(&mut card)).await?;
+    Ok(HttpResponse::Ok().json(&card))
 }
 
-/// GET /v1/cards/course/{course_code}
-async fn get_cards_by_quiz_id<S: CardAPI>(quiz_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.get_cards_by_quiz_id(&quiz_id).await {
-        Ok(cards) => HttpResponse::Ok().json(&cards),
-        Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Internal server error: {:?}", e))
-        }
-    }
+/// GET /v1/cards/quiz/{quiz_id}
+async fn get_cards_by_quiz_id<S: CardAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let cards = stack.get_cards_by_quiz_id(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&cards))
 }
 
 /// POST /v1/cards
-async fn add_card<S: CardAPI>(create_card: Json<CreateCard>, stack: State<S>) -> HttpResponse {
-    match stack.create_card(&create_card).await {
-        Ok(card) => HttpResponse::Ok().json(&card),
-        Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Internal server error: {:?}", e))
-        }
-    }
+async fn add_card<S: CardAPI + MemberAPI>(
+    create_card: Json<CreateCard>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Code(&create_card.course_code),
+        Role::Editor,
+    )
+    .await?;
+    let card = stack.create_card(&user, &create_card).await?;
+    Ok(HttpResponse::Ok().json(&card))
 }
 
 /// PUT /v1/cards
-async fn update_card<S: CardAPI>(card: Json<UpdateCard>, stack: State<S>) -> HttpResponse {
-    match stack.update_card(&card).await {
-        Ok(card) => HttpResponse::Ok().json(&card),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn update_card<S: CardAPI + MemberAPI>(
+    card: Json<UpdateCard>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Card(&card.id), Role::Editor).await?;
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Code(&card.course_code),
+        Role::Editor,
+    )
+    .await?;
+    let card = stack.update_card(&user, &card).await?;
+    Ok(HttpResponse::Ok().json(&card))
 }
 
 /// DELETE /v1/cards/id/{card_id}
-async fn delete_card<S: CardAPI>(card_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.delete_card(&card_id).await {
-        Ok(card) => HttpResponse::Ok().json(&card),
-        Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Internal server error: {:?}", e))
-        }
+async fn delete_card<S: CardAPI + MemberAPI>(
+    card_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Card(&card_id), Role::Editor).await?;
+    let card = stack.delete_card(&user, &card_id).await?;
+    Ok(HttpResponse::Ok().json(&card))
+}
+
+/// GET /v1/cards/id/{card_id}/revisions
+async fn get_card_revisions<S: CardAPI + MemberAPI>(
+    card_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Card(&card_id), Role::Editor).await?;
+    let revisions = stack.get_card_revisions(&user, &card_id).await?;
+    Ok(HttpResponse::Ok().json(&revisions))
+}
+
+/// GET /v1/cards/id/{card_id}/revisions/diff
+async fn diff_card_revisions<S: CardAPI + MemberAPI>(
+    card_id: Path<Uuid>,
+    query: Query<DiffQuery>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Card(&card_id), Role::Editor).await?;
+    let revisions = stack.get_card_revisions(&user, &card_id).await?;
+    let diff = revision::diff(revisions, &query)?;
+    Ok(HttpResponse::Ok().json(&diff))
+}
+
+/// POST /v1/cards/id/{card_id}/revisions/{revision}/revert
+async fn revert_card<S: CardAPI + MemberAPI>(
+    path: Path<(Uuid, i32)>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let (card_id, revision) = path.into_inner();
+    member_api::require(&*stack, &user, CourseRef::Card(&card_id), Role::Editor).await?;
+    let card = stack.revert_card(&user, &card_id, revision).await?;
+    Ok(HttpResponse::Ok().json(&card))
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use serde_json::json;
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn cards_are_added_listed_and_edited() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+        app.card(&alice, "C1", "Basics", 2).await;
+
+        let (status, cards) = app.get("/v1/cards?course_code=C1", &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cards.as_array().unwrap().len(), 2);
+
+        let mut update = card.clone();
+        update["answer"] = json!("Another answer");
+        let (status, updated) = app.put("/v1/cards", &alice, &update).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["answer"], "Another answer");
+
+        let id = card["id"].as_str().unwrap();
+        let (status, revisions) = app
+            .get(&format!("/v1/cards/id/{id}/revisions"), &alice)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(revisions.as_array().unwrap().len(), 2);
+
+        let (status, diff) = app
+            .get(&format!("/v1/cards/id/{id}/revisions/diff"), &alice)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(diff["from"]["revision"], 1);
+        assert_eq!(diff["to"]["revision"], 2);
+        assert_eq!(diff["fields"][0]["field"], "answer");
+    }
+
+    #[ntex::test]
+    async fn cards_need_a_role_in_their_course() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+        let path = format!("/v1/cards/id/{}", card["id"].as_str().unwrap());
+
+        let (status, _) = app.get(&path, &bob).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let members = format!("/v1/courses/id/{}/members", course["id"].as_str().unwrap());
+        let invite = json!({ "username": "bobby", "role": "viewer" });
+        app.post(&members, &alice, invite).await;
+
+        let (status, _) = app.get(&path, &bob).await;
+        assert_eq!(status, StatusCode::OK);
+        let new_card = json!({
+            "question": "Q",
+            "answer": "A",
+            "course_code": "C1",
+            "category": "Basics",
+        });
+        let (status, _) = app.post("/v1/cards", &bob, &new_card).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app.delete(&path, &bob).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[ntex::test]
+    async fn viewers_cannot_read_answers() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let carol = app.login("carol").await;
+        let course = app.course(&alice, "C1").await;
+        let members = format!("/v1/courses/id/{}/members", course["id"].as_str().unwrap());
+        let invite = json!({ "username": "bobby", "role": "viewer" });
+        app.post(&members, &alice, invite).await;
+        let invite = json!({ "username": "carol", "role": "editor" });
+        app.post(&members, &alice, invite).await;
+
+        let card = json!({
+            "question": "Q",
+            "answer": "A",
+            "course_code": "C1",
+            "category": "Basics",
+            "hint": "starts with A",
+        });
+        let (_, card) = app.post("/v1/cards", &alice, card).await;
+        let id = card["id"].as_str().unwrap();
+
+        let (status, cards) = app.get("/v1/cards?course_code=C1", &bob).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cards[0]["question"], "Q");
+        assert_eq!(cards[0]["answer"], "");
+        assert!(cards[0]["hint"].is_null());
+        let (status, card) = app.get(&format!("/v1/cards/id/{id}"), &bob).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(card["answer"], "");
+        let (status, _) = app.get(&format!("/v1/cards/id/{id}/revisions"), &bob).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app
+            .get(
+                &format!("/v1/cards/id/{id}/revisions/diff?from=1&to=1"),
+                &bob,
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (_, card) = app.get(&format!("/v1/cards/id/{id}"), &carol).await;
+        assert_eq!(card["answer"], "A");
+        assert_eq!(card["hint"], "starts with A");
+        let (status, _) = app
+            .get(&format!("/v1/cards/id/{id}/revisions"), &carol)
+            .await;
+        assert_eq!(status, StatusCode::OK);
     }
 }
@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::revision::CardRevision;
+use super::schema::CARDS;
+use super::{quiz_in_progress, Card, CardAPI, CreateCard, UpdateCard};
+use crate::auth_api::User;
+use crate::list::{Columns, FilterValue, ListQuery, Page, SortValue};
+use crate::memory::{InMemoryKeiko, Trashed};
+use crate::metrics::METRICS;
+use crate::{KeikoError, KeikoResult};
+
+impl Columns for Card {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "created_at" => Some(SortValue::Time(self.created_at)),
+            "question" => Some(SortValue::Text(self.question.clone())),
+            "category" => Some(SortValue::Text(self.category.clone())),
+            "course_code" => Some(SortValue::Text(self.course_code.clone())),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, filter: &str, value: &FilterValue<'_>) -> bool {
+        match (filter, value) {
+            ("course_code", FilterValue::Text(v)) => self.course_code == *v,
+            ("category", FilterValue::Text(v)) => self.category == *v,
+            ("created_after", FilterValue::Time(v)) => self.created_at > *v,
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl CardAPI for InMemoryKeiko {
+    async fn get_cards(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<Card>> {
+        let state = self.state();
+        let cards = state
+            .cards
+            .iter()
+            .filter(|c| state.card_is_visible(user, c))
+            .cloned()
+            .collect();
+
+        CARDS.page(cards, query)
+    }
+
+    async fn get_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card> {
+        self.state().visible_card(user, card_id).cloned()
+    }
+
+    async fn create_card(&self, user: &User, create_card: &CreateCard) -> KeikoResult<Card> {
+        let mut state = self.state();
+        let course_id = state.visible_course(user, &create_card.course_code)?.id;
+        state.ensure_category(&course_id, &create_card.category);
+
+        let card = Card {
+            id: Uuid::new_v4(),
+            question: create_card.question.clone(),
+            answer: create_card.answer.clone(),
+            course_id,
+            course_code: create_card.course_code.clone(),
+            category: create_card.category.clone(),
+            hint: create_card.hint.clone().filter(|h| !h.is_empty()),
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+        };
+        state.cards.push(card.clone());
+        state.add_card_revision(&card, user);
+        METRICS.cards_created.inc();
+
+        Ok(card)
+    }
+
+    async fn update_card(&self, user: &User, update_card: &UpdateCard) -> KeikoResult<Card> {
+        let mut state = self.state();
+        state.visible_card(user, &update_card.id)?;
+        let course_id = state.visible_course(user, &update_card.course_code)?.id;
+        state.ensure_category(&course_id, &update_card.category);
+
+        let card = state
+            .cards
+            .iter_mut()
+            .find(|c| c.id == update_card.id)
+            .ok_or_else(|| KeikoError::not_found("card"))?;
+
+        card.question = update_card.question.clone();
+        card.answer = update_card.answer.clone();
+        card.course_id = course_id;
+        card.course_code = update_card.course_code.clone();
+        card.category = update_card.category.clone();
+        if let Some(hint) = &update_card.hint {
+            card.hint = Some(hint.clone());
+        }
+        card.hint = card.hint.take().filter(|h| !h.is_empty());
+        card.updated_at = Some(chrono::Utc::now());
+
+        let card = card.clone();
+        state.add_card_revision(&card, user);
+
+        Ok(card)
+    }
+
+    async fn delete_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut state = self.state();
+        state.visible_card(user, card_id)?;
+        let index = state
+            .cards
+            .iter()
+            .position(|c| c.id == *card_id)
+            .ok_or_else(|| KeikoError::not_found("card"))?;
+        let row = state.cards.remove(index);
+        state.trashed_cards.push(Trashed {
+            row,
+            deleted_at: chrono::Utc::now(),
+        });
+
+        Ok(*card_id)
+    }
+
+    async fn get_cards_by_quiz_id(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<Card>> {
+        let state = self.state();
+        let quiz = &state.visible_quiz(user, quiz_id)?.quiz;
+        if !quiz.is_completed {
+            return Err(quiz_in_progress());
+        }
+
+        Ok(state
+            .quiz_cards(&quiz.course_id, &quiz.category)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_card_revisions(
+        &self,
+        user: &User,
+        card_id: &Uuid,
+    ) -> KeikoResult<Vec<CardRevision>> {
+        let state = self.state();
+        state.visible_card(user, card_id)?;
+
+        let mut revisions: Vec<CardRevision> = state
+            .card_revisions
+            .iter()
+            .filter(|r| r.card_id == *card_id)
+            .map(|r| CardRevision {
+                username: r
+                    .user_id
+                    .and_then(|id| state.user(&id))
+                    .map(|u| u.username.clone()),
+                ..r.clone()
+            })
+            .collect();
+        revisions.sort_by_key(|r| r.revision);
+
+        Ok(revisions)
+    }
+
+    async fn revert_card(&self, user: &User, card_id: &Uuid, revision: i32) -> KeikoResult<Card> {
+        let mut state = self.state();
+        let course_id = state.visible_card(user, card_id)?.course_id;
+        let old = state
+            .card_revisions
+            .iter()
+            .find(|r| r.card_id == *card_id && r.revision == revision)
+            .cloned()
+            .ok_or_else(|| KeikoError::not_found("revision"))?;
+        state.ensure_category(&course_id, &old.category);
+
+        let card = state
+            .cards
+            .iter_mut()
+            .find(|c| c.id == *card_id)
+            .ok_or_else(|| KeikoError::not_found("card"))?;
+
+        card.question = old.question;
+        card.answer = old.answer;
+        card.category = old.category;
+        card.hint = old.hint;
+        card.updated_at = Some(chrono::Utc::now());
+
+        let card = card.clone();
+        state.add_card_revision(&card, user);
+
+        Ok(card)
+    }
+}
@@ -1,11 +1,19 @@
+mod memory;
+pub mod revision;
 mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-use crate::KeikoResult;
+use crate::auth_api::User;
+use crate::list::{ListQuery, Page};
+use crate::member_api::Answered;
+use crate::{KeikoError, KeikoResult};
+use revision::CardRevision;
 
 #[derive(
     Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
@@ -14,8 +22,10 @@ pub struct Card {
     pub id: Uuid,
     pub question: String,
     pub answer: String,
+    pub course_id: Uuid,
     pub course_code: String,
     pub category: String,
+    pub hint: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -28,6 +38,8 @@ pub struct CreateCard {
     pub answer: String,
     pub course_code: String,
     pub category: String,
+    #[serde(default)]
+    pub hint: Option<String>,
 }
 
 #[derive(
@@ -39,14 +51,42 @@ pub struct UpdateCard {
     pub answer: String,
     pub course_code: String,
     pub category: String,
+    /// Omitted keeps the current hint, an empty string clears it.
+    #[serde(default)]
+    pub hint: Option<String>,
 }
 
 #[async_trait]
 pub trait CardAPI: Send + Sync + 'static {
-    async fn get_cards(&self) -> KeikoResult<Vec<Card>>;
-    async fn get_card(&self, card_id: &Uuid) -> KeikoResult<Card>;
-    async fn create_card(&self, create_card: &CreateCard) -> KeikoResult<Card>;
-    async fn update_card(&self, update_card: &UpdateCard) -> KeikoResult<Card>;
-    async fn delete_card(&self, card_id: &Uuid) -> KeikoResult<Uuid>;
-    async fn get_cards_by_quiz_id(&self, quiz_id: &Uuid) -> KeikoResult<Vec<Card>>;
+    async fn get_cards(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<Card>>;
+    async fn get_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card>;
+    async fn create_card(&self, user: &User, create_card: &CreateCard) -> KeikoResult<Card>;
+    async fn update_card(&self, user: &User, update_card: &UpdateCard) -> KeikoResult<Card>;
+    async fn delete_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Uuid>;
+    /// The cards of the quiz's category. They carry their answers, so this fails with a
+    /// conflict until the quiz is completed.
+    async fn get_cards_by_quiz_id(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<Card>>;
+    /// Every revision of the card, oldest first.
+    async fn get_card_revisions(
+        &self,
+        user: &User,
+        card_id: &Uuid,
+    ) -> KeikoResult<Vec<CardRevision>>;
+    /// Makes the card say what it did at `revision` again, recorded as a new revision.
+    async fn revert_card(&self, user: &User, card_id: &Uuid, revision: i32) -> KeikoResult<Card>;
+}
+
+fn quiz_in_progress() -> KeikoError {
+    KeikoError::Conflict("the answers of a quiz are hidden until it is completed".to_owned())
+}
+
+impl Answered for Card {
+    fn course_code(&self) -> &str {
+        &self.course_code
+    }
+
+    fn hide_answer(&mut self) {
+        self.answer.clear();
+        self.hint = None;
+    }
 }
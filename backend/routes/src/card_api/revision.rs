@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::{KeikoError, KeikoResult};
+
+/// A version of a card, numbered from 1 in the order they were written. The last one is what
+/// the card says now.
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct CardRevision {
+    pub card_id: Uuid,
+    pub revision: i32,
+    pub question: String,
+    pub answer: String,
+    pub category: String,
+    pub hint: Option<String>,
+    /// Who wrote it, `None` for cards that predate revisions or a deleted user.
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Revisions to compare. `to` defaults to the latest revision and `from` to the one before
+/// `to`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiffQuery {
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A run of words both revisions share, or that only one of them has.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub text: String,
+}
+
+/// How one field changed. Joining the `equal` and `delete` runs gives the old text, the
+/// `equal` and `insert` runs the new one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CardDiff {
+    pub card_id: Uuid,
+    pub from: CardRevision,
+    pub to: CardRevision,
+    /// Only the fields that differ, out of question, answer, category and hint.
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Compares two of `revisions`, which must be a card's revisions in order, word by word.
+pub fn diff(revisions: Vec<CardRevision>, query: &DiffQuery) -> KeikoResult<CardDiff> {
+    let find = |revision: i32| {
+        revisions
+            .iter()
+            .find(|r| r.revision == revision)
+            .cloned()
+            .ok_or_else(|| KeikoError::not_found("revision"))
+    };
+
+    let latest = revisions
+        .last()
+        .ok_or_else(|| KeikoError::not_found("revision"))?;
+    let to = find(query.to.unwrap_or(latest.revision))?;
+    let from = find(query.from.unwrap_or(to.revision - 1))?;
+
+    let fields = [
+        ("question", from.question.as_str(), to.question.as_str()),
+        ("answer", from.answer.as_str(), to.answer.as_str()),
+        ("category", from.category.as_str(), to.category.as_str()),
+        (
+            "hint",
+            from.hint.as_deref().unwrap_or_default(),
+            to.hint.as_deref().unwrap_or_default(),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .map(|(field, old, new)| FieldDiff {
+        field: field.to_owned(),
+        changes: changes(old, new),
+    })
+    .collect();
+
+    Ok(CardDiff {
+        card_id: to.card_id,
+        from,
+        to,
+        fields,
+    })
+}
+
+/// Word level changes from `old` to `new`, with neighbouring words of the same kind merged.
+fn changes(old: &str, new: &str) -> Vec<Change> {
+    let mut changes: Vec<Change> = Vec::new();
+
+    for change in TextDiff::from_words(old, new).iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => ChangeKind::Equal,
+            ChangeTag::Delete => ChangeKind::Delete,
+            ChangeTag::Insert => ChangeKind::Insert,
+        };
+        match changes.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(change.value()),
+            _ => changes.push(Change {
+                kind,
+                text: change.value().to_owned(),
+            }),
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revisions(texts: &[(&str, &str, Option<&str>)]) -> Vec<CardRevision> {
+        let card_id = Uuid::new_v4();
+        texts
+            .iter()
+            .zip(1..)
+            .map(|(&(question, answer, hint), revision)| CardRevision {
+                card_id,
+                revision,
+                question: question.to_owned(),
+                answer: answer.to_owned(),
+                category: "Basics".to_owned(),
+                hint: hint.map(str::to_owned),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn query(from: Option<i32>, to: Option<i32>) -> DiffQuery {
+        DiffQuery { from, to }
+    }
+
+    /// The old and new text a field diff stands for.
+    fn texts(field: &FieldDiff) -> (String, String) {
+        let join = |skip: ChangeKind| {
+            field
+                .changes
+                .iter()
+                .filter(|c| c.kind != skip)
+                .map(|c| c.text.as_str())
+                .collect()
+        };
+        (join(ChangeKind::Insert), join(ChangeKind::Delete))
+    }
+
+    fn revisions_of_three() -> Vec<CardRevision> {
+        revisions(&[("Q", "one", None), ("Q", "two", None), ("Q", "three", None)])
+    }
+
+    #[test]
+    fn compares_the_latest_revision_with_the_one_before() {
+        let diff = diff(revisions_of_three(), &DiffQuery::default()).unwrap();
+        assert_eq!((diff.from.revision, diff.to.revision), (2, 3));
+    }
+
+    #[test]
+    fn compares_the_revisions_asked_for() {
+        let before = diff(revisions_of_three(), &query(None, Some(2))).unwrap();
+        assert_eq!((before.from.revision, before.to.revision), (1, 2));
+
+        let between = diff(revisions_of_three(), &query(Some(1), Some(3))).unwrap();
+        assert_eq!((between.from.revision, between.to.revision), (1, 3));
+        assert_eq!(
+            texts(&between.fields[0]),
+            ("one".to_owned(), "three".to_owned())
+        );
+
+        let backwards = diff(revisions_of_three(), &query(Some(3), Some(1))).unwrap();
+        assert_eq!(
+            texts(&backwards.fields[0]),
+            ("three".to_owned(), "one".to_owned())
+        );
+    }
+
+    #[test]
+    fn missing_revisions_are_not_found() {
+        for query in [
+            query(None, Some(4)),
+            query(Some(0), None),
+            query(Some(9), Some(1)),
+        ] {
+            let error = diff(revisions_of_three(), &query).unwrap_err();
+            assert!(matches!(error, KeikoError::NotFound(_)), "{:?}", query);
+        }
+        // A card's first revision has nothing before it.
+        let error = diff(revisions_of_three(), &query(None, Some(1))).unwrap_err();
+        assert!(matches!(error, KeikoError::NotFound(_)));
+        let error = diff(Vec::new(), &DiffQuery::default()).unwrap_err();
+        assert!(matches!(error, KeikoError::NotFound(_)));
+    }
+
+    #[test]
+    fn lists_only_the_fields_that_changed() {
+        let revisions = revisions(&[("Q", "A", None), ("Q", "A", None), ("Q2", "A", None)]);
+        let unchanged = diff(revisions.clone(), &query(Some(1), Some(2))).unwrap();
+        assert!(unchanged.fields.is_empty());
+
+        let changed = diff(revisions, &DiffQuery::default()).unwrap();
+        let fields: Vec<&str> = changed.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, ["question"]);
+    }
+
+    #[test]
+    fn merges_neighbouring_words_of_the_same_kind() {
+        let old = "the quick brown fox jumps";
+        let new = "the slow red fox jumps high";
+        let revisions = revisions(&[(old, "A", None), (new, "A", None)]);
+        let diff = diff(revisions, &DiffQuery::default()).unwrap();
+
+        let question = &diff.fields[0];
+        let kinds: Vec<ChangeKind> = question.changes.iter().map(|c| c.kind).collect();
+        assert!(kinds.windows(2).all(|w| w[0] != w[1]), "{:?}", kinds);
+        assert_eq!(question.changes[0].kind, ChangeKind::Equal);
+        assert_eq!(question.changes[0].text, "the ");
+        assert_eq!(texts(question), (old.to_owned(), new.to_owned()));
+    }
+
+    #[test]
+    fn a_missing_hint_compares_as_empty() {
+        let revisions = revisions(&[
+            ("Q", "A", None),
+            ("Q", "A", Some("")),
+            ("Q", "A", Some("think")),
+        ]);
+        let cleared = diff(revisions.clone(), &query(Some(1), Some(2))).unwrap();
+        assert!(cleared.fields.is_empty());
+
+        let added = diff(revisions, &query(Some(1), Some(3))).unwrap();
+        assert_eq!(added.fields.len(), 1);
+        assert_eq!(added.fields[0].field, "hint");
+        assert_eq!(
+            added.fields[0].changes,
+            [Change {
+                kind: ChangeKind::Insert,
+                text: "think".to_owned(),
+            }]
+        );
+    }
+}
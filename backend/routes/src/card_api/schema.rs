@@ -1,51 +1,128 @@
-use super::{Card, CardAPI, CreateCard, UpdateCard};
-use crate::{KeikoDatabase, KeikoResult};
+use super::revision::CardRevision;
+use super::{quiz_in_progress, Card, CardAPI, CreateCard, UpdateCard};
+use crate::access;
+use crate::auth_api::User;
+use crate::list::{Filter, ListQuery, Listing, Page, SortField};
+use crate::metrics::METRICS;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
 use async_trait::async_trait;
+use sqlx::PgExecutor;
+use tracing::instrument;
 use uuid::Uuid;
 
+pub(super) const CARDS: Listing = Listing {
+    name: "cards",
+    from: "cards",
+    visible_to: "deleted_at IS NULL \
+                 AND course_id IN (SELECT id FROM courses WHERE course_role(id, $?) IS NOT NULL)",
+    sort_fields: &[
+        SortField {
+            column: "created_at",
+            sql_type: "timestamptz",
+        },
+        SortField {
+            column: "question",
+            sql_type: "text",
+        },
+        SortField {
+            column: "category",
+            sql_type: "text",
+        },
+        SortField {
+            column: "course_code",
+            sql_type: "text",
+        },
+    ],
+    filters: &[
+        Filter {
+            name: "course_code",
+            condition: "course_code = $?",
+        },
+        Filter {
+            name: "category",
+            condition: "category = $?",
+        },
+        Filter {
+            name: "created_after",
+            condition: "created_at > $?",
+        },
+    ],
+};
+
 #[async_trait]
 impl CardAPI for KeikoDatabase {
     /// GET /v1/cards
-    async fn get_cards(&self) -> KeikoResult<Vec<Card>> {
-        sqlx::query_as::<_, Card>("SELECT * FROM cards")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_cards(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<Card>> {
+        CARDS.fetch(&self.pool, user, query).await
     }
 
     /// GET /v1/cards/id/{card_id}
-    async fn get_card(&self, card_id: &Uuid) -> KeikoResult<Card> {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card> {
+        access::card(&self.pool, user, card_id).await?;
+
         sqlx::query_as::<_, Card>("SELECT * FROM cards WHERE id = $1")
             .bind(card_id)
-            .fetch_one(&self.pool)
+            .fetch_optional(&self.pool)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(KeikoError::from)?
+            .ok_or_else(|| KeikoError::not_found("card"))
     }
 
     /// POST /v1/cards
-    async fn create_card(&self, create_card: &CreateCard) -> KeikoResult<Card> {
-        sqlx::query_as::<_, Card>(
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_card(&self, user: &User, create_card: &CreateCard) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        let course_id = access::course(&mut *tx, user, &create_card.course_code).await?;
+
+        sqlx::query_scalar::<_, ()>("SELECT ensure_category($1, $2)")
+            .bind(course_id)
+            .bind(&create_card.category)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let card = sqlx::query_as::<_, Card>(
             r#"
-      INSERT INTO cards (question, answer, course_code, category)
-      VALUES ($1, $2, $3, $4)
+      INSERT INTO cards (question, answer, course_id, course_code, category, hint)
+      VALUES ($1, $2, $3, $4, $5, NULLIF($6, ''))
       RETURNING *
       "#,
         )
         .bind(&create_card.question)
         .bind(&create_card.answer)
+        .bind(course_id)
         .bind(&create_card.course_code)
         .bind(&create_card.category)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&create_card.hint)
+        .fetch_one(&mut *tx)
+        .await?;
+        add_revision(&mut *tx, user, &card.id).await?;
+
+        tx.commit().await?;
+        METRICS.cards_created.inc();
+
+        Ok(card)
     }
 
     /// PUT /v1/cards
-    async fn update_card(&self, update_card: &UpdateCard) -> KeikoResult<Card> {
-        sqlx::query_as::<_, Card>(
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_card(&self, user: &User, update_card: &UpdateCard) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        access::card(&mut *tx, user, &update_card.id).await?;
+        let course_id = access::course(&mut *tx, user, &update_card.course_code).await?;
+
+        sqlx::query_scalar::<_, ()>("SELECT ensure_category($1, $2)")
+            .bind(course_id)
+            .bind(&update_card.category)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let card = sqlx::query_as::<_, Card>(
             r#"
       UPDATE cards
-      SET question = $2, answer = $3, course_code = $4, category = $5, updated_at = now()
+      SET question = $2, answer = $3, course_id = $4, course_code = $5, category = $6,
+          hint = NULLIF(COALESCE($7, hint), ''), updated_at = now()
       WHERE id = $1
       RETURNING *
       "#,
@@ -53,35 +130,143 @@ impl CardAPI for KeikoDatabase {
         .bind(update_card.id)
         .bind(&update_card.question)
         .bind(&update_card.answer)
+        .bind(course_id)
         .bind(&update_card.course_code)
         .bind(&update_card.category)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(&update_card.hint)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("card"))?;
+        add_revision(&mut *tx, user, &card.id).await?;
+
+        tx.commit().await?;
+
+        Ok(card)
     }
 
     /// DELETE /v1/cards/id/{card_id}
-    async fn delete_card(&self, card_id: &Uuid) -> KeikoResult<Uuid> {
-        sqlx::query_scalar::<_, Uuid>("DELETE FROM cards WHERE id = $1 RETURNING id")
-            .bind(card_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Uuid> {
+        access::card(&self.pool, user, card_id).await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            "UPDATE cards SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL RETURNING id",
+        )
+        .bind(card_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("card"))
     }
 
     /// GET /v1/cards/quiz/{quiz_id}
-    async fn get_cards_by_quiz_id(&self, quiz_id: &Uuid) -> KeikoResult<Vec<Card>> {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_cards_by_quiz_id(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<Card>> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        let completed =
+            sqlx::query_scalar::<_, bool>("SELECT is_completed FROM quizzes WHERE id = $1")
+                .bind(quiz_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| KeikoError::not_found("quiz"))?;
+        if !completed {
+            return Err(quiz_in_progress());
+        }
+
         sqlx::query_as::<_, Card>(
             r#"
             SELECT c.*
             FROM cards c
-            JOIN quizzes q ON c.course_code = q.course_code AND c.category = q.category
-            WHERE q.id = $1;
+            JOIN quizzes q ON c.course_id = q.course_id AND c.category = q.category
+            WHERE q.id = $1 AND c.deleted_at IS NULL
+            ORDER BY c.created_at, c.id;
             "#,
         )
         .bind(quiz_id)
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(KeikoError::from)
+    }
+
+    /// GET /v1/cards/id/{card_id}/revisions
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_card_revisions(
+        &self,
+        user: &User,
+        card_id: &Uuid,
+    ) -> KeikoResult<Vec<CardRevision>> {
+        access::card(&self.pool, user, card_id).await?;
+
+        sqlx::query_as::<_, CardRevision>(
+            r#"
+            SELECT r.*, u.username
+            FROM card_revisions r
+            LEFT JOIN users u ON u.id = r.user_id
+            WHERE r.card_id = $1
+            ORDER BY r.revision
+            "#,
+        )
+        .bind(card_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
     }
+
+    /// POST /v1/cards/id/{card_id}/revisions/{revision}/revert
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn revert_card(&self, user: &User, card_id: &Uuid, revision: i32) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        access::card(&mut *tx, user, card_id).await?;
+
+        // The category may have been renamed or deleted since.
+        sqlx::query_scalar::<_, ()>(
+            r#"
+            SELECT ensure_category(c.course_id, r.category)
+            FROM cards c
+            JOIN card_revisions r ON r.card_id = c.id
+            WHERE c.id = $1 AND r.revision = $2
+            "#,
+        )
+        .bind(card_id)
+        .bind(revision)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("revision"))?;
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            UPDATE cards c
+            SET question = r.question, answer = r.answer, category = r.category, hint = r.hint,
+                updated_at = now()
+            FROM card_revisions r
+            WHERE c.id = $1 AND r.card_id = c.id AND r.revision = $2
+            RETURNING c.*
+            "#,
+        )
+        .bind(card_id)
+        .bind(revision)
+        .fetch_one(&mut *tx)
+        .await?;
+        add_revision(&mut *tx, user, card_id).await?;
+
+        tx.commit().await?;
+
+        Ok(card)
+    }
+}
+
+/// `add_card_revision()`: records what the card says now as its next revision, by `user`.
+async fn add_revision<'e>(
+    executor: impl PgExecutor<'e>,
+    user: &User,
+    card_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query("SELECT add_card_revision($1, $2)")
+        .bind(card_id)
+        .bind(user.id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
 }
@@ -0,0 +1,222 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::revision::CardRevision;
+use super::schema::CARDS;
+use super::{quiz_in_progress, Card, CardAPI, CreateCard, UpdateCard};
+use crate::auth_api::User;
+use crate::list::{ListQuery, Page};
+use crate::metrics::METRICS;
+use crate::sqlite::{
+    add_card_revision, ensure_category, visible_card, visible_course, visible_quiz, KeikoSqlite,
+};
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl CardAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_cards(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<Card>> {
+        let cards = sqlx::query_as::<_, Card>(
+            r#"
+            SELECT * FROM cards
+            WHERE deleted_at IS NULL
+            AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $1)
+            "#,
+        )
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        CARDS.page(cards, query)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card> {
+        visible_card(&self.pool, user, card_id).await?;
+
+        sqlx::query_as::<_, Card>("SELECT * FROM cards WHERE id = $1")
+            .bind(card_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(KeikoError::from)?
+            .ok_or_else(|| KeikoError::not_found("card"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_card(&self, user: &User, create_card: &CreateCard) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        let course_id = visible_course(&mut *tx, user, &create_card.course_code).await?;
+        ensure_category(&mut *tx, &course_id, &create_card.category).await?;
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            INSERT INTO cards (id, question, answer, course_id, course_code, category, hint)
+            VALUES ($1, $2, $3, $4, $5, $6, NULLIF($7, ''))
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(&create_card.question)
+        .bind(&create_card.answer)
+        .bind(course_id)
+        .bind(&create_card.course_code)
+        .bind(&create_card.category)
+        .bind(&create_card.hint)
+        .fetch_one(&mut *tx)
+        .await?;
+        add_card_revision(&mut *tx, user, &card.id).await?;
+
+        tx.commit().await?;
+        METRICS.cards_created.inc();
+
+        Ok(card)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_card(&self, user: &User, update_card: &UpdateCard) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        visible_card(&mut *tx, user, &update_card.id).await?;
+        let course_id = visible_course(&mut *tx, user, &update_card.course_code).await?;
+        ensure_category(&mut *tx, &course_id, &update_card.category).await?;
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            UPDATE cards
+            SET question = $2, answer = $3, course_id = $4, course_code = $5, category = $6,
+                hint = NULLIF(COALESCE($7, hint), ''), updated_at = $8
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(update_card.id)
+        .bind(&update_card.question)
+        .bind(&update_card.answer)
+        .bind(course_id)
+        .bind(&update_card.course_code)
+        .bind(&update_card.category)
+        .bind(&update_card.hint)
+        .bind(chrono::Utc::now())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("card"))?;
+        add_card_revision(&mut *tx, user, &card.id).await?;
+
+        tx.commit().await?;
+
+        Ok(card)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Uuid> {
+        visible_card(&self.pool, user, card_id).await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            "UPDATE cards SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL RETURNING id",
+        )
+        .bind(card_id)
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("card"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_cards_by_quiz_id(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<Card>> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        let completed =
+            sqlx::query_scalar::<_, bool>("SELECT is_completed FROM quizzes WHERE id = $1")
+                .bind(quiz_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| KeikoError::not_found("quiz"))?;
+        if !completed {
+            return Err(quiz_in_progress());
+        }
+
+        sqlx::query_as::<_, Card>(
+            r#"
+            SELECT c.*
+            FROM cards c
+            JOIN quizzes q ON c.course_id = q.course_id AND c.category = q.category
+            WHERE q.id = $1 AND c.deleted_at IS NULL
+            ORDER BY c.created_at, c.id
+            "#,
+        )
+        .bind(quiz_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_card_revisions(
+        &self,
+        user: &User,
+        card_id: &Uuid,
+    ) -> KeikoResult<Vec<CardRevision>> {
+        visible_card(&self.pool, user, card_id).await?;
+
+        sqlx::query_as::<_, CardRevision>(
+            r#"
+            SELECT r.*, u.username
+            FROM card_revisions r
+            LEFT JOIN users u ON u.id = r.user_id
+            WHERE r.card_id = $1
+            ORDER BY r.revision
+            "#,
+        )
+        .bind(card_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn revert_card(&self, user: &User, card_id: &Uuid, revision: i32) -> KeikoResult<Card> {
+        let mut tx = self.pool.begin().await?;
+        visible_card(&mut *tx, user, card_id).await?;
+
+        let (course_id, category) = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT c.course_id, r.category
+            FROM cards c
+            JOIN card_revisions r ON r.card_id = c.id
+            WHERE c.id = $1 AND r.revision = $2
+            "#,
+        )
+        .bind(card_id)
+        .bind(revision)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("revision"))?;
+        // The category may have been renamed or deleted since.
+        ensure_category(&mut *tx, &course_id, &category).await?;
+
+        let card = sqlx::query_as::<_, Card>(
+            r#"
+            UPDATE cards
+            SET (question, answer, category, hint) = (
+                    SELECT question, answer, category, hint
+                    FROM card_revisions
+                    WHERE card_id = $1 AND revision = $2
+                ),
+                updated_at = $3
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(card_id)
+        .bind(revision)
+        .bind(chrono::Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+        add_card_revision(&mut *tx, user, card_id).await?;
+
+        tx.commit().await?;
+
+        Ok(card)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::auth_api::User;
+use crate::category_api::{CategoryAPI, CreateCategory, RenameCategory, UpdateCategory};
+use crate::extract::{Json, Path};
+use crate::member_api::{self, CourseRef, MemberAPI, Role};
+use crate::KeikoResult;
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
+use uuid::Uuid;
+
+pub fn service<S: CategoryAPI + MemberAPI>(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/v1/categories")
+            .route("/course/{course_id}", web::get().to(get_categories::<S>))
+            .route("/id/{category_id}", web::get().to(get_category::<S>))
+            .route("", web::post().to(create_category::<S>))
+            .route("", web::put().to(update_category::<S>))
+            .route("/id/{category_id}", web::delete().to(delete_category::<S>))
+            .route(
+                "/course/{course_id}/order",
+                web::put().to(reorder_categories::<S>),
+            )
+            .route(
+                "/rename/{course_code}",
+                web::post().to(rename_category::<S>),
+            ),
+    );
+}
+
+/// GET /v1/categories/course/{course_id}
+async fn get_categories<S: CategoryAPI>(
+    course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let categories = stack.get_categories(&user, &course_id).await?;
+    Ok(HttpResponse::Ok().json(&categories))
+}
+
+/// GET /v1/categories/id/{category_id}
+async fn get_category<S: CategoryAPI>(
+    category_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let category = stack.get_category(&user, &category_id).await?;
+    Ok(HttpResponse::Ok().json(&category))
+}
+
+/// POST /v1/categories
+async fn create_category<S: CategoryAPI + MemberAPI>(
+    create_category: Json<CreateCategory>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Id(&create_category.course_id),
+        Role::Editor,
+    )
+    .await?;
+    let category = stack.create_category(&user, &create_category).await?;
+    Ok(HttpResponse::Ok().json(&category))
+}
+
+/// PUT /v1/categories
+async fn update_category<S: CategoryAPI + MemberAPI>(
+    update_category: Json<UpdateCategory>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Category(&update_category.id),
+        Role::Editor,
+    )
+    .await?;
+    let category = stack.update_category(&user, &update_category).await?;
+    Ok(HttpResponse::Ok().json(&category))
+}
+
+/// DELETE /v1/categories/id/{category_id}
+///
+/// Refuses while the category has cards outside the trash. Those in the trash are deleted for
+/// good along with it, as are the quizzes on it, whoever started them.
+async fn delete_category<S: CategoryAPI + MemberAPI>(
+    category_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Category(&category_id),
+        Role::Editor,
+    )
+    .await?;
+    let category = stack.delete_category(&user, &category_id).await?;
+    Ok(HttpResponse::Ok().json(&category))
+}
+
+/// PUT /v1/categories/course/{course_id}/order
+async fn reorder_categories<S: CategoryAPI + MemberAPI>(
+    course_id: Path<Uuid>,
+    order: Json<Vec<Uuid>>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Id(&course_id), Role::Editor).await?;
+    let categories = stack.reorder_categories(&user, &course_id, &order).await?;
+    Ok(HttpResponse::Ok().json(&categories))
+}
+
+/// POST /v1/categories/rename/{course_code}
+async fn rename_category<S: CategoryAPI + MemberAPI>(
+    course_code: Path<String>,
+    rename_category: Json<RenameCategory>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Code(&course_code), Role::Editor).await?;
+    stack
+        .rename_category(&user, &course_code, &rename_category)
+        .await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use serde_json::json;
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn categories_with_live_cards_are_kept() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let course = app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+
+        let categories = format!("/v1/categories/course/{}", course["id"].as_str().unwrap());
+        let (status, listed) = app.get(&categories, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["name"], "Basics");
+        let category = format!("/v1/categories/id/{}", listed[0]["id"].as_str().unwrap());
+
+        let (status, error) = app.delete(&category, &alice).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"], "conflict");
+
+        let card = format!("/v1/cards/id/{}", card["id"].as_str().unwrap());
+        app.delete(&card, &alice).await;
+        let (status, _) = app.delete(&category, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get(&card, &alice).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, trash) = app.get("/v1/trash", &alice).await;
+        assert!(trash.as_array().unwrap().is_empty());
+    }
+
+    #[ntex::test]
+    async fn categories_take_their_quizzes_along() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+        let members = format!("/v1/courses/id/{}/members", course["id"].as_str().unwrap());
+        let invite = json!({ "username": "bobby", "role": "viewer" });
+        app.post(&members, &alice, invite).await;
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (status, quiz) = app.post("/v1/quiz", &bob, create).await;
+        assert_eq!(status, StatusCode::OK);
+        let quiz = format!("/v1/quiz/id/{}", quiz["id"].as_str().unwrap());
+
+        let categories = format!("/v1/categories/course/{}", course["id"].as_str().unwrap());
+        let (_, listed) = app.get(&categories, &alice).await;
+        let category = format!("/v1/categories/id/{}", listed[0]["id"].as_str().unwrap());
+        app.delete(
+            &format!("/v1/cards/id/{}", card["id"].as_str().unwrap()),
+            &alice,
+        )
+        .await;
+        let (status, _) = app.delete(&category, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get(&quiz, &bob).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[ntex::test]
+    async fn categories_are_added_and_ordered() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let course = app.course(&alice, "C1").await;
+        let course_id = course["id"].as_str().unwrap();
+
+        let mut ids = Vec::new();
+        for name in ["First", "Second"] {
+            let category = json!({ "course_id": course_id, "name": name });
+            let (status, category) = app.post("/v1/categories", &alice, category).await;
+            assert_eq!(status, StatusCode::OK);
+            ids.push(category["id"].clone());
+        }
+        ids.reverse();
+
+        let order = format!("/v1/categories/course/{course_id}/order");
+        let (status, _) = app.put(&order, &alice, &ids).await;
+        assert!(status.is_success());
+        let (_, listed) = app
+            .get(&format!("/v1/categories/course/{course_id}"), &alice)
+            .await;
+        let names: Vec<&str> = listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Second", "First"]);
+    }
+}
@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{
+    category_in_use, validate_name, validate_order, validate_unlock_after, Category, CategoryAPI,
+    CreateCategory, RenameCategory, UpdateCategory,
+};
+use crate::auth_api::User;
+use crate::memory::{InMemoryKeiko, State};
+use crate::{KeikoError, KeikoResult};
+
+fn name_taken(name: &str) -> KeikoError {
+    KeikoError::Conflict(format!("the course already has a category named {}", name))
+}
+
+/// Fails with not found unless `user` is a member of the category's course.
+fn visible_category<'s>(
+    state: &'s State,
+    user: &User,
+    category_id: &Uuid,
+) -> KeikoResult<&'s Category> {
+    state
+        .categories
+        .iter()
+        .find(|c| {
+            c.id == *category_id
+                && state
+                    .course(&c.course_id)
+                    .is_some_and(|co| state.course_role(co, user).is_some())
+        })
+        .ok_or_else(|| KeikoError::not_found("category"))
+}
+
+/// Renames a category, carrying the new name over to its cards and quizzes, trashed ones
+/// included, as the foreign keys cascade it.
+fn rename(state: &mut State, category_id: &Uuid, name: &str) -> KeikoResult<()> {
+    let Some(category) = state.categories.iter().find(|c| c.id == *category_id) else {
+        return Ok(());
+    };
+    let (course_id, old) = (category.course_id, category.name.clone());
+    if old == name {
+        return Ok(());
+    }
+    if state
+        .categories
+        .iter()
+        .any(|c| c.course_id == course_id && c.name == name)
+    {
+        return Err(name_taken(name));
+    }
+
+    for category in state.categories.iter_mut().filter(|c| c.id == *category_id) {
+        category.name = name.to_owned();
+    }
+    for card in state
+        .cards
+        .iter_mut()
+        .chain(state.trashed_cards.iter_mut().map(|t| &mut t.row))
+        .filter(|c| c.course_id == course_id && c.category == old)
+    {
+        card.category = name.to_owned();
+    }
+    for row in state
+        .quizzes
+        .iter_mut()
+        .chain(state.trashed_quizzes.iter_mut().map(|t| &mut t.row))
+        .filter(|q| q.quiz.course_id == course_id && q.quiz.category == old)
+    {
+        row.quiz.category = name.to_owned();
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl CategoryAPI for InMemoryKeiko {
+    async fn get_categories(&self, user: &User, course_id: &Uuid) -> KeikoResult<Vec<Category>> {
+        let state = self.state();
+        if !state
+            .course(course_id)
+            .is_some_and(|c| state.course_role(c, user).is_some())
+        {
+            return Ok(Vec::new());
+        }
+
+        Ok(state
+            .course_categories(course_id)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Category> {
+        visible_category(&self.state(), user, category_id).cloned()
+    }
+
+    async fn create_category(
+        &self,
+        user: &User,
+        create_category: &CreateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&create_category.name)?;
+
+        let mut state = self.state();
+        state
+            .course(&create_category.course_id)
+            .filter(|c| state.course_role(c, user).is_some())
+            .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let categories: Vec<Category> = state
+            .course_categories(&create_category.course_id)
+            .into_iter()
+            .cloned()
+            .collect();
+        validate_unlock_after(&categories, None, create_category.unlock_after.as_ref())?;
+        if categories.iter().any(|c| c.name == create_category.name) {
+            return Err(name_taken(&create_category.name));
+        }
+
+        let category = Category {
+            id: Uuid::new_v4(),
+            course_id: create_category.course_id,
+            name: create_category.name.clone(),
+            description: create_category.description.clone(),
+            position: categories.last().map_or(0, |c| c.position + 1),
+            unlock_after: create_category.unlock_after,
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+        };
+        state.categories.push(category.clone());
+
+        Ok(category)
+    }
+
+    async fn update_category(
+        &self,
+        user: &User,
+        update_category: &UpdateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&update_category.name)?;
+
+        let mut state = self.state();
+        let course_id = visible_category(&state, user, &update_category.id)?.course_id;
+        let categories: Vec<Category> = state
+            .course_categories(&course_id)
+            .into_iter()
+            .cloned()
+            .collect();
+        validate_unlock_after(
+            &categories,
+            Some(&update_category.id),
+            update_category.unlock_after.as_ref(),
+        )?;
+
+        rename(&mut state, &update_category.id, &update_category.name)?;
+        let category = state
+            .categories
+            .iter_mut()
+            .find(|c| c.id == update_category.id)
+            .ok_or_else(|| KeikoError::not_found("category"))?;
+        category.description = update_category.description.clone();
+        category.unlock_after = update_category.unlock_after;
+        category.updated_at = Some(chrono::Utc::now());
+
+        Ok(category.clone())
+    }
+
+    async fn delete_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut state = self.state();
+        let category = visible_category(&state, user, category_id)?;
+        if state
+            .cards
+            .iter()
+            .any(|c| c.course_id == category.course_id && c.category == category.name)
+        {
+            return Err(category_in_use());
+        }
+        state.delete_category(category_id);
+
+        Ok(*category_id)
+    }
+
+    async fn reorder_categories(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+        order: &[Uuid],
+    ) -> KeikoResult<Vec<Category>> {
+        let mut state = self.state();
+        let categories: Vec<Category> = if state
+            .course(course_id)
+            .is_some_and(|c| state.course_role(c, user).is_some())
+        {
+            state
+                .course_categories(course_id)
+                .into_iter()
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        validate_order(&categories, order)?;
+
+        let now = chrono::Utc::now();
+        for (position, id) in order.iter().enumerate() {
+            for category in state.categories.iter_mut().filter(|c| c.id == *id) {
+                category.position = position as i32;
+                category.updated_at = Some(now);
+            }
+        }
+
+        Ok(state
+            .course_categories(course_id)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    async fn rename_category(
+        &self,
+        user: &User,
+        course_code: &str,
+        rename_category: &RenameCategory,
+    ) -> KeikoResult<()> {
+        validate_name(&rename_category.new)?;
+
+        let mut state = self.state();
+        let course_id = state.visible_course(user, course_code)?.id;
+        let Some(category_id) = state
+            .categories
+            .iter()
+            .find(|c| c.course_id == course_id && c.name == rename_category.old)
+            .map(|c| c.id)
+        else {
+            return Ok(());
+        };
+
+        rename(&mut state, &category_id, &rename_category.new)?;
+        for category in state.categories.iter_mut().filter(|c| c.id == category_id) {
+            category.updated_at = Some(chrono::Utc::now());
+        }
+
+        Ok(())
+    }
+}
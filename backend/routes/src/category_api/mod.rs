@@ -0,0 +1,167 @@
+mod memory;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::{KeikoError, KeikoResult};
+
+/// A lesson of a course. Cards and quizzes refer to it by name, which a rename carries over to.
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct Category {
+    pub id: Uuid,
+    pub course_id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// Categories of a course are listed by this, lowest first.
+    pub position: i32,
+    /// Category a quiz must be completed on before quizzes on this one can be started.
+    pub unlock_after: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct CreateCategory {
+    pub course_id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub unlock_after: Option<Uuid>,
+}
+
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct UpdateCategory {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub unlock_after: Option<Uuid>,
+}
+
+/// Renames a category of a course by name, for clients that predate category ids.
+#[derive(
+    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub struct RenameCategory {
+    pub old: String,
+    pub new: String,
+}
+
+#[async_trait]
+pub trait CategoryAPI: Send + Sync + 'static {
+    /// The categories of a course, in order.
+    async fn get_categories(&self, user: &User, course_id: &Uuid) -> KeikoResult<Vec<Category>>;
+    async fn get_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Category>;
+    /// Adds a category after the last one of its course.
+    async fn create_category(
+        &self,
+        user: &User,
+        create_category: &CreateCategory,
+    ) -> KeikoResult<Category>;
+    async fn update_category(
+        &self,
+        user: &User,
+        update_category: &UpdateCategory,
+    ) -> KeikoResult<Category>;
+    /// Deletes the category along with its quizzes and those of its cards in the trash. Fails
+    /// with conflict while it has any others.
+    async fn delete_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Uuid>;
+    /// Puts the categories of a course in the given order, which must name each of them once.
+    async fn reorder_categories(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+        order: &[Uuid],
+    ) -> KeikoResult<Vec<Category>>;
+    async fn rename_category(
+        &self,
+        user: &User,
+        course_code: &str,
+        rename_category: &RenameCategory,
+    ) -> KeikoResult<()>;
+}
+
+fn category_in_use() -> KeikoError {
+    KeikoError::Conflict("the category still has cards, delete or move them first".to_owned())
+}
+
+pub(crate) fn validate_name(name: &str) -> KeikoResult<()> {
+    if name.trim().is_empty() {
+        return Err(KeikoError::Validation(
+            "category name must not be empty".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that `unlock_after` is another category of the course, given all of its
+/// `categories`, and that following it never leads back to `category_id`, which would leave
+/// both locked for good.
+pub(crate) fn validate_unlock_after(
+    categories: &[Category],
+    category_id: Option<&Uuid>,
+    unlock_after: Option<&Uuid>,
+) -> KeikoResult<()> {
+    let mut next = unlock_after;
+    for _ in 0..=categories.len() {
+        let Some(id) = next else {
+            return Ok(());
+        };
+        if Some(id) == category_id {
+            return Err(KeikoError::Validation(
+                "unlock_after would leave the category locked for good".to_owned(),
+            ));
+        }
+        next = categories
+            .iter()
+            .find(|c| c.id == *id)
+            .ok_or_else(|| {
+                KeikoError::Validation(
+                    "unlock_after must be another category of the same course".to_owned(),
+                )
+            })?
+            .unlock_after
+            .as_ref();
+    }
+
+    Ok(())
+}
+
+/// Checks that `order` names every category of the course exactly once.
+pub(crate) fn validate_order(categories: &[Category], order: &[Uuid]) -> KeikoResult<()> {
+    let mut given = order.to_vec();
+    given.sort_unstable();
+    given.dedup();
+    let mut expected: Vec<Uuid> = categories.iter().map(|c| c.id).collect();
+    expected.sort_unstable();
+
+    if given.len() != order.len() || given != expected {
+        return Err(KeikoError::Validation(
+            "order must list every category of the course once".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn locked(category: &str, unlock_after: &str) -> KeikoError {
+    KeikoError::Conflict(format!(
+        "category {} unlocks once a quiz on {} is completed",
+        category, unlock_after
+    ))
+}
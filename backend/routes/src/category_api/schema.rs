@@ -0,0 +1,265 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::{
+    category_in_use, validate_name, validate_order, validate_unlock_after, Category, CategoryAPI,
+    CreateCategory, RenameCategory, UpdateCategory,
+};
+use crate::access;
+use crate::auth_api::User;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+async fn course_categories(
+    executor: impl sqlx::PgExecutor<'_>,
+    course_id: &Uuid,
+) -> KeikoResult<Vec<Category>> {
+    sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE course_id = $1 ORDER BY position, created_at, id",
+    )
+    .bind(course_id)
+    .fetch_all(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// Fails with not found unless `user` is a member of the category's course.
+async fn visible_category(
+    executor: impl sqlx::PgExecutor<'_>,
+    user: &User,
+    category_id: &Uuid,
+) -> KeikoResult<Category> {
+    sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE id = $1 AND course_role(course_id, $2) IS NOT NULL",
+    )
+    .bind(category_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::not_found("category"))
+}
+
+#[async_trait]
+impl CategoryAPI for KeikoDatabase {
+    /// GET /v1/categories/course/{course_id}
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_categories(&self, user: &User, course_id: &Uuid) -> KeikoResult<Vec<Category>> {
+        sqlx::query_as::<_, Category>(
+            r#"
+            SELECT * FROM categories
+            WHERE course_id = $1 AND course_role(course_id, $2) IS NOT NULL
+            ORDER BY position, created_at, id
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    /// GET /v1/categories/id/{category_id}
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Category> {
+        visible_category(&self.pool, user, category_id).await
+    }
+
+    /// POST /v1/categories
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_category(
+        &self,
+        user: &User,
+        create_category: &CreateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&create_category.name)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM courses WHERE id = $1 AND course_role(id, $2) IS NOT NULL FOR UPDATE",
+        )
+        .bind(create_category.course_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let categories = course_categories(&mut *tx, &create_category.course_id).await?;
+        validate_unlock_after(&categories, None, create_category.unlock_after.as_ref())?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            INSERT INTO categories (course_id, name, description, position, unlock_after)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(create_category.course_id)
+        .bind(&create_category.name)
+        .bind(&create_category.description)
+        .bind(categories.last().map_or(0, |c| c.position + 1))
+        .bind(create_category.unlock_after)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(category)
+    }
+
+    /// PUT /v1/categories
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_category(
+        &self,
+        user: &User,
+        update_category: &UpdateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&update_category.name)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let category = visible_category(&mut *tx, user, &update_category.id).await?;
+        let categories = course_categories(&mut *tx, &category.course_id).await?;
+        validate_unlock_after(
+            &categories,
+            Some(&category.id),
+            update_category.unlock_after.as_ref(),
+        )?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            UPDATE categories
+            SET name = $2, description = $3, unlock_after = $4, updated_at = now()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(update_category.id)
+        .bind(&update_category.name)
+        .bind(&update_category.description)
+        .bind(update_category.unlock_after)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(category)
+    }
+
+    /// DELETE /v1/categories/id/{category_id}
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locks the category, so no card or quiz can be added to it meanwhile.
+        let category = visible_category(&mut *tx, user, category_id).await?;
+        sqlx::query("SELECT 1 FROM categories WHERE id = $1 FOR UPDATE")
+            .bind(category_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let in_use = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM cards
+                WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+            )
+            "#,
+        )
+        .bind(category.course_id)
+        .bind(&category.name)
+        .fetch_one(&mut *tx)
+        .await?;
+        if in_use {
+            return Err(category_in_use());
+        }
+
+        for table in ["cards", "quizzes"] {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE course_id = $1 AND category = $2",
+                table
+            ))
+            .bind(category.course_id)
+            .bind(&category.name)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let id = sqlx::query_scalar::<_, Uuid>("DELETE FROM categories WHERE id = $1 RETURNING id")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("category"))?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    /// PUT /v1/categories/course/{course_id}/order
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn reorder_categories(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+        order: &[Uuid],
+    ) -> KeikoResult<Vec<Category>> {
+        let mut tx = self.pool.begin().await?;
+
+        // Locks the course's categories, so one added meanwhile cannot be left out.
+        let categories = sqlx::query_as::<_, Category>(
+            r#"
+            SELECT * FROM categories
+            WHERE course_id = $1 AND course_role(course_id, $2) IS NOT NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_all(&mut *tx)
+        .await?;
+        validate_order(&categories, order)?;
+
+        sqlx::query(
+            r#"
+            UPDATE categories c
+            SET position = o.position - 1, updated_at = now()
+            FROM UNNEST($1::uuid[]) WITH ORDINALITY AS o (id, position)
+            WHERE c.id = o.id
+            "#,
+        )
+        .bind(order)
+        .execute(&mut *tx)
+        .await?;
+
+        let categories = course_categories(&mut *tx, course_id).await?;
+        tx.commit().await?;
+
+        Ok(categories)
+    }
+
+    /// POST /v1/categories/rename/{course_code}
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn rename_category(
+        &self,
+        user: &User,
+        course_code: &str,
+        rename_category: &RenameCategory,
+    ) -> KeikoResult<()> {
+        validate_name(&rename_category.new)?;
+        let course_id = access::course(&self.pool, user, course_code).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE categories SET name = $3, updated_at = now()
+            WHERE course_id = $1 AND name = $2
+            "#,
+        )
+        .bind(course_id)
+        .bind(&rename_category.old)
+        .bind(&rename_category.new)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
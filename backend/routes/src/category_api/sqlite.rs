@@ -0,0 +1,259 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::{
+    category_in_use, validate_name, validate_order, validate_unlock_after, Category, CategoryAPI,
+    CreateCategory, RenameCategory, UpdateCategory,
+};
+use crate::auth_api::User;
+use crate::sqlite::{visible_course, KeikoSqlite};
+use crate::{KeikoError, KeikoResult};
+
+async fn course_categories(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    course_id: &Uuid,
+) -> KeikoResult<Vec<Category>> {
+    sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE course_id = $1 ORDER BY position, created_at, id",
+    )
+    .bind(course_id)
+    .fetch_all(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// Fails with not found unless `user` is a member of the category's course.
+async fn visible_category(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    user: &User,
+    category_id: &Uuid,
+) -> KeikoResult<Category> {
+    sqlx::query_as::<_, Category>(
+        r#"
+        SELECT * FROM categories
+        WHERE id = $1 AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+        "#,
+    )
+    .bind(category_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::not_found("category"))
+}
+
+#[async_trait]
+impl CategoryAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_categories(&self, user: &User, course_id: &Uuid) -> KeikoResult<Vec<Category>> {
+        sqlx::query_as::<_, Category>(
+            r#"
+            SELECT * FROM categories
+            WHERE course_id = $1
+            AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            ORDER BY position, created_at, id
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Category> {
+        visible_category(&self.pool, user, category_id).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_category(
+        &self,
+        user: &User,
+        create_category: &CreateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&create_category.name)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM courses
+            WHERE id = $1 AND id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            "#,
+        )
+        .bind(create_category.course_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let categories = course_categories(&mut *tx, &create_category.course_id).await?;
+        validate_unlock_after(&categories, None, create_category.unlock_after.as_ref())?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            INSERT INTO categories (id, course_id, name, description, position, unlock_after)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(create_category.course_id)
+        .bind(&create_category.name)
+        .bind(&create_category.description)
+        .bind(categories.last().map_or(0, |c| c.position + 1))
+        .bind(create_category.unlock_after)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(category)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_category(
+        &self,
+        user: &User,
+        update_category: &UpdateCategory,
+    ) -> KeikoResult<Category> {
+        validate_name(&update_category.name)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let category = visible_category(&mut *tx, user, &update_category.id).await?;
+        let categories = course_categories(&mut *tx, &category.course_id).await?;
+        validate_unlock_after(
+            &categories,
+            Some(&category.id),
+            update_category.unlock_after.as_ref(),
+        )?;
+
+        let category = sqlx::query_as::<_, Category>(
+            r#"
+            UPDATE categories
+            SET name = $2, description = $3, unlock_after = $4, updated_at = $5
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(update_category.id)
+        .bind(&update_category.name)
+        .bind(&update_category.description)
+        .bind(update_category.unlock_after)
+        .bind(chrono::Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(category)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_category(&self, user: &User, category_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut tx = self.pool.begin().await?;
+        let category = visible_category(&mut *tx, user, category_id).await?;
+
+        let in_use = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM cards
+                WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+            )
+            "#,
+        )
+        .bind(category.course_id)
+        .bind(&category.name)
+        .fetch_one(&mut *tx)
+        .await?;
+        if in_use {
+            return Err(category_in_use());
+        }
+
+        for table in ["cards", "quizzes"] {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE course_id = $1 AND category = $2",
+                table
+            ))
+            .bind(category.course_id)
+            .bind(&category.name)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let id = sqlx::query_scalar::<_, Uuid>("DELETE FROM categories WHERE id = $1 RETURNING id")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("category"))?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn reorder_categories(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+        order: &[Uuid],
+    ) -> KeikoResult<Vec<Category>> {
+        let mut tx = self.pool.begin().await?;
+
+        let categories = sqlx::query_as::<_, Category>(
+            r#"
+            SELECT * FROM categories
+            WHERE course_id = $1
+            AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_all(&mut *tx)
+        .await?;
+        validate_order(&categories, order)?;
+
+        let now = chrono::Utc::now();
+        for (position, id) in order.iter().enumerate() {
+            sqlx::query("UPDATE categories SET position = $2, updated_at = $3 WHERE id = $1")
+                .bind(id)
+                .bind(position as i32)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let categories = course_categories(&mut *tx, course_id).await?;
+        tx.commit().await?;
+
+        Ok(categories)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn rename_category(
+        &self,
+        user: &User,
+        course_code: &str,
+        rename_category: &RenameCategory,
+    ) -> KeikoResult<()> {
+        validate_name(&rename_category.new)?;
+        let course_id = visible_course(&self.pool, user, course_code).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE categories SET name = $3, updated_at = $4
+            WHERE course_id = $1 AND name = $2
+            "#,
+        )
+        .bind(course_id)
+        .bind(&rename_category.old)
+        .bind(&rename_category.new)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
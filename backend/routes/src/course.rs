@@ -1,98 +1,307 @@
+#[cfg(feature = "anki")]
+use crate::anki;
+use crate::auth_api::User;
+use crate::card_api::CardAPI;
 use crate::course_api::{CourseAPI, CreateCourse, UpdateCourse};
-use ntex::web::{
-    self,
-    types::{Json, Path, State},
-    HttpResponse, ServiceConfig,
-};
+use crate::extract::{Json, Path};
+use crate::list::ListQuery;
+use crate::member_api::{self, CourseRef, Invite, MemberAPI, Role};
+use crate::KeikoResult;
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
 use uuid::Uuid;
 
-pub fn service<S: CourseAPI>(cfg: &mut ServiceConfig) {
-    cfg.service(
-        web::scope("/v1/courses")
-            .route("", web::get().to(get_courses::<S>))
-            .route("/id/{course_id}", web::get().to(get_course::<S>))
-            .route(
-                "/code/{course_code}",
-                web::get().to(get_course_from_course_code::<S>),
-            )
-            .route(
-                "/id/{course_id}/categories",
-                web::get().to(get_categories_for_course::<S>),
-            )
-            .route("", web::post().to(create_course::<S>))
-            .route("", web::put().to(update_course::<S>))
-            .route("/id/{course_id}", web::delete().to(delete_course::<S>)),
+pub fn service<S: CourseAPI + CardAPI + MemberAPI>(cfg: &mut ServiceConfig) {
+    let scope = web::scope("/v1/courses")
+        .route("", web::get().to(get_courses::<S>))
+        .route("/id/{course_id}", web::get().to(get_course::<S>))
+        .route(
+            "/code/{course_code}",
+            web::get().to(get_course_from_course_code::<S>),
+        )
+        .route(
+            "/id/{course_id}/categories",
+            web::get().to(get_categories_for_course::<S>),
+        )
+        .route("", web::post().to(create_course::<S>))
+        .route("", web::put().to(update_course::<S>))
+        .route("/id/{course_id}", web::delete().to(delete_course::<S>))
+        .route("/id/{course_id}/members", web::get().to(get_members::<S>))
+        .route("/id/{course_id}/members", web::post().to(invite::<S>))
+        .route(
+            "/id/{course_id}/members/{user_id}",
+            web::delete().to(revoke::<S>),
+        );
+    #[cfg(feature = "anki")]
+    let scope = scope.route(
+        "/id/{course_id}/export.apkg",
+        web::get().to(export_anki::<S>),
     );
+    cfg.service(scope);
 }
 
 /// GET /v1/courses
-async fn get_courses<S: CourseAPI>(stack: State<S>) -> HttpResponse {
-    match stack.get_courses().await {
-        Ok(courses) => HttpResponse::Ok().json(&courses),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn get_courses<S: CourseAPI>(
+    query: ListQuery,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let courses = stack.get_courses(&user, &query).await?;
+    Ok(courses.respond())
 }
 
 /// GET /v1/courses/id/{course_id}
-async fn get_course<S: CourseAPI>(course_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.get_course(&course_id).await {
-        Ok(course) => HttpResponse::Ok().json(&course),
-        Err(e) => HttpResponse::NotFound().body(format!("Course not found: {:?}", e)),
-    }
+async fn get_course<S: CourseAPI>(
+    course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let course = stack.get_course(&user, &course_id).await?;
+    Ok(HttpResponse::Ok().json(&course))
 }
 
 /// GET /v1/courses/code/{course_code}
 async fn get_course_from_course_code<S: CourseAPI>(
     course_code: Path<String>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.get_course_from_course_code(&course_code).await {
-        Ok(course) => HttpResponse::Ok().json(&course),
-        Err(e) => HttpResponse::NotFound().body(format!("Course not found: {:?}", e)),
-    }
+) -> KeikoResult<HttpResponse> {
+    let course = stack
+        .get_course_from_course_code(&user, &course_code)
+        .await?;
+    Ok(HttpResponse::Ok().json(&course))
 }
 
 /// GET /v1/courses/id/{course_id}/categories
 async fn get_categories_for_course<S: CourseAPI>(
     course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let categories = stack.get_categories_for_course(&user, &course_id).await?;
+    Ok(HttpResponse::Ok().json(&categories))
+}
+
+/// GET /v1/courses/id/{course_id}/export.apkg
+#[cfg(feature = "anki")]
+async fn export_anki<S: CourseAPI + CardAPI>(
+    course_id: Path<Uuid>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.get_categories_for_course(&course_id).await {
-        Ok(categories) => HttpResponse::Ok().json(&categories),
-        Err(e) => HttpResponse::NotFound().body(format!("Course not found: {:?}", e)),
+) -> KeikoResult<HttpResponse> {
+    let course = stack.get_course(&user, &course_id).await?;
+    let mut query = ListQuery {
+        course_code: Some(course.course_code.clone()),
+        limit: Some(crate::list::MAX_PAGE_SIZE),
+        ..Default::default()
+    };
+    let mut cards = Vec::new();
+    loop {
+        let page = stack.get_cards(&user, &query).await?;
+        cards.extend(page.items);
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
     }
+    let package = anki::write_package(&course, &cards).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/apkg")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.apkg\"", course.course_code),
+        )
+        .body(package))
 }
 
 /// POST /v1/courses
 async fn create_course<S: CourseAPI>(
     create_course: Json<CreateCourse>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.create_course(&create_course).await {
-        Ok(course) => HttpResponse::Ok().json(&course),
-        Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Internal server error: {:?}", e))
-        }
-    }
+) -> KeikoResult<HttpResponse> {
+    let course = stack.create_course(&user, &create_course).await?;
+    Ok(HttpResponse::Ok().json(&course))
 }
 
 /// PUT /v1/courses
-async fn update_course<S: CourseAPI>(
+async fn update_course<S: CourseAPI + MemberAPI>(
     update_course: Json<UpdateCourse>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.update_course(&update_course).await {
-        Ok(course) => HttpResponse::Ok().json(&course),
-        Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Internal server error: {:?}", e))
-        }
-    }
+) -> KeikoResult<HttpResponse> {
+    member_api::require(
+        &*stack,
+        &user,
+        CourseRef::Id(&update_course.id),
+        Role::Editor,
+    )
+    .await?;
+    let course = stack.update_course(&user, &update_course).await?;
+    Ok(HttpResponse::Ok().json(&course))
 }
 
 /// DELETE /v1/courses/id/{course_id}
-async fn delete_course<S: CourseAPI>(course_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.delete_course(&course_id).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => HttpResponse::NotFound().body(format!("Not found: {:?}", e)),
+async fn delete_course<S: CourseAPI + MemberAPI>(
+    course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Id(&course_id), Role::Owner).await?;
+    stack.delete_course(&user, &course_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// GET /v1/courses/id/{course_id}/members
+async fn get_members<S: MemberAPI>(
+    course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Id(&course_id), Role::Viewer).await?;
+    let members = stack.get_members(&course_id).await?;
+    Ok(HttpResponse::Ok().json(&members))
+}
+
+/// POST /v1/courses/id/{course_id}/members
+async fn invite<S: MemberAPI>(
+    course_id: Path<Uuid>,
+    invite: Json<Invite>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Id(&course_id), Role::Owner).await?;
+    let member = stack.invite(&course_id, &invite).await?;
+    Ok(HttpResponse::Ok().json(&member))
+}
+
+/// DELETE /v1/courses/id/{course_id}/members/{user_id}
+///
+/// Members may also remove themselves.
+async fn revoke<S: MemberAPI>(
+    path: Path<(Uuid, Uuid)>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let (course_id, member_id) = path.into_inner();
+    let required = if member_id == user.id {
+        Role::Viewer
+    } else {
+        Role::Owner
+    };
+    member_api::require(&*stack, &user, CourseRef::Id(&course_id), required).await?;
+    stack.revoke(&course_id, &member_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+    use serde_json::json;
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn courses_are_listed_for_their_members() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        app.course(&bob, "C2").await;
+
+        let (status, courses) = app.get("/v1/courses", &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(courses.as_array().unwrap().len(), 1);
+        assert_eq!(courses[0]["course_code"], "C1");
+
+        let path = format!("/v1/courses/id/{}", course["id"].as_str().unwrap());
+        let (status, _) = app.get(&path, &bob).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[ntex::test]
+    async fn course_codes_are_unique() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+
+        let course = json!({ "name": "Again", "course_code": "C1", "description": "" });
+        let (status, error) = app.post("/v1/courses", &alice, course).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"], "conflict");
+    }
+
+    #[ntex::test]
+    async fn members_share_a_course_by_role() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        let members = format!("/v1/courses/id/{}/members", course["id"].as_str().unwrap());
+
+        let invite = json!({ "username": "bobby", "role": "viewer" });
+        let (status, _) = app.post(&members, &alice, invite).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, listed) = app.get(&members, &bob).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        let (status, _) = app.get("/v1/courses/code/C1", &bob).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let invite = json!({ "username": "alice", "role": "viewer" });
+        let (status, _) = app.post(&members, &bob, invite).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app
+            .delete(
+                &format!("/v1/courses/id/{}", course["id"].as_str().unwrap()),
+                &bob,
+            )
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[ntex::test]
+    async fn progress_counts_only_the_members_own_quizzes() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        let course_id = course["id"].as_str().unwrap();
+        app.card(&alice, "C1", "Basics", 1).await;
+        let invite = json!({ "username": "bobby", "role": "viewer" });
+        app.post(
+            &format!("/v1/courses/id/{course_id}/members"),
+            &alice,
+            invite,
+        )
+        .await;
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (_, quiz) = app.post("/v1/quiz", &alice, &create).await;
+        let quiz_id = quiz["id"].as_str().unwrap();
+        let (_, question) = app
+            .get(&format!("/v1/quiz/id/{quiz_id}/question"), &alice)
+            .await;
+        let grade = json!({
+            "card_id": question["card_id"],
+            "given_answer": "Answer 1",
+            "elapsed_ms": 1000,
+        });
+        let (status, graded) = app
+            .post(&format!("/v1/quiz/id/{quiz_id}/grade"), &alice, grade)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(graded["quiz"]["is_completed"], true);
+        let (status, _) = app.post("/v1/quiz", &bob, &create).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let path = format!("/v1/courses/id/{course_id}");
+        let (_, seen_by_alice) = app.get(&path, &alice).await;
+        assert_eq!(seen_by_alice["progress"], 100);
+        let (_, seen_by_bob) = app.get(&path, &bob).await;
+        assert_eq!(seen_by_bob["progress"], 0);
+        let (_, listed_for_bob) = app.get("/v1/courses", &bob).await;
+        assert_eq!(listed_for_bob[0]["progress"], 0);
     }
 }
@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::schema::COURSES;
+use super::{Course, CourseAPI, CourseCategory, CourseView, CreateCourse, UpdateCourse};
+use crate::auth_api::User;
+use crate::list::{Columns, FilterValue, ListQuery, Page, SortValue};
+use crate::memory::{round, InMemoryKeiko, State, Trashed};
+use crate::{KeikoError, KeikoResult};
+
+/// The row of `courses_view` for `user`.
+fn course_view(state: &State, course: &Course, user: &User) -> CourseView {
+    let questions = state
+        .cards
+        .iter()
+        .filter(|c| c.course_id == course.id)
+        .count() as i64;
+
+    let quizzes: Vec<_> = state
+        .quizzes
+        .iter()
+        .filter(|q| q.quiz.course_id == course.id && q.quiz.user_id == Some(user.id))
+        .collect();
+    let completed = quizzes.iter().filter(|q| q.quiz.is_completed).count();
+    let progress = if quizzes.is_empty() {
+        0
+    } else {
+        round(completed as f64 / quizzes.len() as f64 * 100.0)
+    };
+
+    let categories = state
+        .course_categories(&course.id)
+        .into_iter()
+        .map(|c| c.name.clone())
+        .collect();
+
+    CourseView {
+        id: course.id,
+        user_id: course.user_id,
+        name: course.name.clone(),
+        course_code: course.course_code.clone(),
+        description: course.description.clone(),
+        created_at: course.created_at,
+        updated_at: course.updated_at,
+        questions,
+        progress,
+        categories,
+    }
+}
+
+fn code_taken(course_code: &str) -> KeikoError {
+    KeikoError::Conflict(format!("course code {} is taken", course_code))
+}
+
+impl Columns for CourseView {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "created_at" => Some(SortValue::Time(self.created_at)),
+            "name" => Some(SortValue::Text(self.name.clone())),
+            "course_code" => Some(SortValue::Text(self.course_code.clone())),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, filter: &str, value: &FilterValue<'_>) -> bool {
+        match (filter, value) {
+            ("course_code", FilterValue::Text(v)) => self.course_code == *v,
+            ("category", FilterValue::Text(v)) => self.categories.iter().any(|c| c == v),
+            ("created_after", FilterValue::Time(v)) => self.created_at > *v,
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl CourseAPI for InMemoryKeiko {
+    async fn get_courses(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<CourseView>> {
+        let state = self.state();
+        let courses = state
+            .courses
+            .iter()
+            .filter(|c| state.course_role(c, user).is_some())
+            .map(|c| course_view(&state, c, user))
+            .collect();
+
+        COURSES.page(courses, query)
+    }
+
+    async fn get_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<CourseView> {
+        let state = self.state();
+        state
+            .courses
+            .iter()
+            .find(|c| c.id == *course_id && state.course_role(c, user).is_some())
+            .map(|c| course_view(&state, c, user))
+            .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    async fn get_course_from_course_code(
+        &self,
+        user: &User,
+        course_code: &str,
+    ) -> KeikoResult<CourseView> {
+        let state = self.state();
+        let course = state.visible_course(user, course_code)?;
+        Ok(course_view(&state, course, user))
+    }
+
+    async fn get_categories_for_course(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+    ) -> KeikoResult<Vec<CourseCategory>> {
+        let state = self.state();
+        let Some(course) = state
+            .courses
+            .iter()
+            .find(|c| c.id == *course_id && state.course_role(c, user).is_some())
+        else {
+            return Ok(Vec::new());
+        };
+
+        Ok(state
+            .course_categories(&course.id)
+            .into_iter()
+            .map(|c| CourseCategory {
+                category: c.name.clone(),
+            })
+            .collect())
+    }
+
+    async fn create_course(
+        &self,
+        user: &User,
+        create_course: &CreateCourse,
+    ) -> KeikoResult<Course> {
+        let mut state = self.state();
+        if state
+            .course_code_holder(&create_course.course_code)
+            .is_some()
+        {
+            return Err(code_taken(&create_course.course_code));
+        }
+
+        let course = Course {
+            id: Uuid::new_v4(),
+            user_id: Some(user.id),
+            name: create_course.name.clone(),
+            course_code: create_course.course_code.clone(),
+            description: create_course.description.clone(),
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+        };
+        state.courses.push(course.clone());
+
+        Ok(course)
+    }
+
+    async fn update_course(
+        &self,
+        user: &User,
+        update_course: &UpdateCourse,
+    ) -> KeikoResult<Course> {
+        let mut state = self.state();
+        if state
+            .course_code_holder(&update_course.course_code)
+            .is_some_and(|id| id != update_course.id)
+        {
+            return Err(code_taken(&update_course.course_code));
+        }
+
+        let index = state
+            .courses
+            .iter()
+            .position(|c| c.id == update_course.id && state.course_role(c, user).is_some())
+            .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let course = &mut state.courses[index];
+        course.name = update_course.name.clone();
+        course.course_code = update_course.course_code.clone();
+        course.description = update_course.description.clone();
+        course.updated_at = Some(chrono::Utc::now());
+        let course = course.clone();
+
+        // The new code follows the course onto its cards and quizzes, trashed ones included, as
+        // the foreign keys cascade it.
+        let State {
+            cards,
+            quizzes,
+            trashed_cards,
+            trashed_quizzes,
+            ..
+        } = &mut *state;
+        for card in cards
+            .iter_mut()
+            .chain(trashed_cards.iter_mut().map(|t| &mut t.row))
+            .filter(|c| c.course_id == course.id)
+        {
+            card.course_code = course.course_code.clone();
+        }
+        for row in quizzes
+            .iter_mut()
+            .chain(trashed_quizzes.iter_mut().map(|t| &mut t.row))
+            .filter(|q| q.quiz.course_id == course.id)
+        {
+            row.quiz.course_code = course.course_code.clone();
+        }
+
+        Ok(course)
+    }
+
+    /// Moves the course to the trash, as `delete_course()` does.
+    async fn delete_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut state = self.state();
+        let index = state
+            .courses
+            .iter()
+            .position(|c| c.id == *course_id && state.course_role(c, user).is_some())
+            .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let row = state.courses.remove(index);
+        state.trashed_courses.push(Trashed {
+            row,
+            deleted_at: chrono::Utc::now(),
+        });
+
+        Ok(*course_id)
+    }
+}
@@ -1,10 +1,15 @@
+mod memory;
 mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::auth_api::User;
+use crate::list::{ListQuery, Page};
 use crate::KeikoResult;
 
 #[derive(
@@ -12,12 +17,16 @@ use crate::KeikoResult;
 )]
 pub struct CourseView {
     pub id: Uuid,
+    /// `None` for courses created before user accounts until the first user registers and is
+    /// given them; no one else can see them.
+    pub user_id: Option<Uuid>,
     pub name: String,
     pub course_code: String,
     pub description: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
     pub questions: i64,
+    /// Percentage of the requesting user's quizzes in the course that are completed.
     pub progress: i32,
     pub categories: Vec<String>,
 }
@@ -27,6 +36,7 @@ pub struct CourseView {
 )]
 pub struct Course {
     pub id: Uuid,
+    pub user_id: Option<Uuid>,
     pub name: String,
     pub course_code: String,
     pub description: String,
@@ -62,12 +72,21 @@ pub struct CourseCategory {
 
 #[async_trait]
 pub trait CourseAPI: Send + Sync + 'static {
-    async fn get_courses(&self) -> KeikoResult<Vec<CourseView>>;
-    async fn get_course(&self, course_id: &Uuid) -> KeikoResult<CourseView>;
-    async fn get_course_from_course_code(&self, course_code: &str) -> KeikoResult<CourseView>;
-    async fn get_categories_for_course(&self, course_id: &Uuid)
-        -> KeikoResult<Vec<CourseCategory>>;
-    async fn create_course(&self, create_course: &CreateCourse) -> KeikoResult<Course>;
-    async fn update_course(&self, update_course: &UpdateCourse) -> KeikoResult<Course>;
-    async fn delete_course(&self, course_id: &Uuid) -> KeikoResult<Uuid>;
+    async fn get_courses(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<CourseView>>;
+    async fn get_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<CourseView>;
+    async fn get_course_from_course_code(
+        &self,
+        user: &User,
+        course_code: &str,
+    ) -> KeikoResult<CourseView>;
+    async fn get_categories_for_course(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+    ) -> KeikoResult<Vec<CourseCategory>>;
+    async fn create_course(&self, user: &User, create_course: &CreateCourse)
+        -> KeikoResult<Course>;
+    async fn update_course(&self, user: &User, update_course: &UpdateCourse)
+        -> KeikoResult<Course>;
+    async fn delete_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Uuid>;
 }
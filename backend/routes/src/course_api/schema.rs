@@ -1,80 +1,146 @@
 use super::{Course, CourseAPI, CourseCategory, CourseView, CreateCourse, UpdateCourse};
-use crate::{KeikoDatabase, KeikoResult};
+use crate::auth_api::User;
+use crate::list::{Filter, ListQuery, Listing, Page, SortField};
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
 use async_trait::async_trait;
+use tracing::instrument;
 use uuid::Uuid;
 
+pub(super) const COURSES: Listing = Listing {
+    name: "courses",
+    from: "courses_view",
+    visible_to: "member_id = $?",
+    sort_fields: &[
+        SortField {
+            column: "created_at",
+            sql_type: "timestamptz",
+        },
+        SortField {
+            column: "name",
+            sql_type: "text",
+        },
+        SortField {
+            column: "course_code",
+            sql_type: "text",
+        },
+    ],
+    filters: &[
+        Filter {
+            name: "course_code",
+            condition: "course_code = $?",
+        },
+        Filter {
+            name: "category",
+            condition: "$? = ANY(categories)",
+        },
+        Filter {
+            name: "created_after",
+            condition: "created_at > $?",
+        },
+    ],
+};
+
 #[async_trait]
 impl CourseAPI for KeikoDatabase {
     /// GET /v1/courses
-    async fn get_courses(&self) -> KeikoResult<Vec<CourseView>> {
-        sqlx::query_as::<_, CourseView>("SELECT * FROM courses_view")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_courses(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<CourseView>> {
+        COURSES.fetch(&self.pool, user, query).await
     }
 
     /// GET /v1/courses/id/{course_id}
-    async fn get_course(&self, course_id: &Uuid) -> KeikoResult<CourseView> {
-        sqlx::query_as::<_, CourseView>("SELECT * FROM courses_view WHERE id = $1")
-            .bind(course_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<CourseView> {
+        sqlx::query_as::<_, CourseView>(
+            "SELECT * FROM courses_view WHERE id = $1 AND member_id = $2",
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("course"))
     }
 
     /// GET /v1/courses/code/{course_code}
-    async fn get_course_from_course_code(&self, course_code: &str) -> KeikoResult<CourseView> {
-        sqlx::query_as::<_, CourseView>("SELECT * FROM courses_view WHERE course_code = $1")
-            .bind(course_code)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_course_from_course_code(
+        &self,
+        user: &User,
+        course_code: &str,
+    ) -> KeikoResult<CourseView> {
+        sqlx::query_as::<_, CourseView>(
+            r#"
+            SELECT * FROM courses_view
+            WHERE course_code = $1 AND member_id = $2
+            "#,
+        )
+        .bind(course_code)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("course"))
     }
 
     /// GET /v1/courses/id/{course_id}/categories
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
     async fn get_categories_for_course(
         &self,
+        user: &User,
         course_id: &Uuid,
     ) -> KeikoResult<Vec<CourseCategory>> {
         sqlx::query_as::<_, CourseCategory>(
             r#"
-            SELECT DISTINCT cards.category
-            FROM courses_view
-            JOIN cards ON courses_view.course_code = cards.course_code
-            WHERE courses_view.id = $1
-            ORDER BY cards.category;
+            SELECT name AS category
+            FROM categories
+            WHERE course_id = $1
+            AND course_role(course_id, $2) IS NOT NULL
+            ORDER BY position, created_at, id;
             "#,
         )
         .bind(course_id)
+        .bind(user.id)
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(KeikoError::from)
     }
 
     /// POST /v1/courses
-    async fn create_course(&self, create_course: &CreateCourse) -> KeikoResult<Course> {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_course(
+        &self,
+        user: &User,
+        create_course: &CreateCourse,
+    ) -> KeikoResult<Course> {
         sqlx::query_as::<_, Course>(
             r#"
-            INSERT INTO courses (name, course_code, description)
-            VALUES ($1, $2, $3)
+            INSERT INTO courses (name, course_code, description, user_id)
+            VALUES ($1, $2, $3, $4)
             RETURNING *
             "#,
         )
         .bind(&create_course.name)
         .bind(&create_course.course_code)
         .bind(&create_course.description)
+        .bind(user.id)
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(KeikoError::from)
     }
 
     /// PUT /v1/courses
-    async fn update_course(&self, update_course: &UpdateCourse) -> KeikoResult<Course> {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_course(
+        &self,
+        user: &User,
+        update_course: &UpdateCourse,
+    ) -> KeikoResult<Course> {
         sqlx::query_as::<_, Course>(
             r#"
             UPDATE courses
             SET name = $2, course_code = $3, description = $4, updated_at = now()
-            WHERE id = $1
+            WHERE id = $1 AND course_role(id, $5) IS NOT NULL
             RETURNING *
             "#,
         )
@@ -82,17 +148,28 @@ impl CourseAPI for KeikoDatabase {
         .bind(&update_course.name)
         .bind(&update_course.course_code)
         .bind(&update_course.description)
-        .fetch_one(&self.pool)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("course"))
     }
 
     /// DELETE /v1/courses/id/{course_id}
-    async fn delete_course(&self, course_id: &Uuid) -> KeikoResult<Uuid> {
-        sqlx::query_scalar::<_, Uuid>("SELECT delete_course($1)")
-            .bind(course_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Uuid> {
+        sqlx::query_scalar::<_, Option<Uuid>>(
+            r#"
+            SELECT delete_course(id) FROM courses
+            WHERE id = $1 AND course_role(id, $2) IS NOT NULL
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .flatten()
+        .ok_or_else(|| KeikoError::not_found("course"))
     }
 }
@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::schema::COURSES;
+use super::{Course, CourseAPI, CourseCategory, CourseView, CreateCourse, UpdateCourse};
+use crate::auth_api::User;
+use crate::list::{ListQuery, Page};
+use crate::sqlite::{self, KeikoSqlite};
+use crate::{KeikoError, KeikoResult};
+
+/// A row of `courses_view` for one member, which has its categories as a JSON array.
+#[derive(FromRow)]
+struct CourseViewRow {
+    id: Uuid,
+    user_id: Option<Uuid>,
+    name: String,
+    course_code: String,
+    description: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    questions: i64,
+    progress: i32,
+    categories: Json<Vec<String>>,
+}
+
+impl From<CourseViewRow> for CourseView {
+    fn from(row: CourseViewRow) -> Self {
+        CourseView {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            course_code: row.course_code,
+            description: row.description,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            questions: row.questions,
+            progress: row.progress,
+            categories: row.categories.0,
+        }
+    }
+}
+
+#[async_trait]
+impl CourseAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_courses(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<CourseView>> {
+        let courses = sqlx::query_as::<_, CourseViewRow>(
+            r#"
+            SELECT * FROM courses_view WHERE member_id = $1
+            "#,
+        )
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        COURSES.page(courses.into_iter().map(CourseView::from).collect(), query)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<CourseView> {
+        sqlx::query_as::<_, CourseViewRow>(
+            r#"
+            SELECT * FROM courses_view WHERE id = $1 AND member_id = $2
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(CourseView::from)
+        .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_course_from_course_code(
+        &self,
+        user: &User,
+        course_code: &str,
+    ) -> KeikoResult<CourseView> {
+        sqlx::query_as::<_, CourseViewRow>(
+            r#"
+            SELECT * FROM courses_view WHERE course_code = $1 AND member_id = $2
+            "#,
+        )
+        .bind(course_code)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(CourseView::from)
+        .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_categories_for_course(
+        &self,
+        user: &User,
+        course_id: &Uuid,
+    ) -> KeikoResult<Vec<CourseCategory>> {
+        sqlx::query_as::<_, CourseCategory>(
+            r#"
+            SELECT name AS category
+            FROM categories
+            WHERE course_id = $1
+            AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            ORDER BY position, created_at, id
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_course(
+        &self,
+        user: &User,
+        create_course: &CreateCourse,
+    ) -> KeikoResult<Course> {
+        sqlx::query_as::<_, Course>(
+            r#"
+            INSERT INTO courses (id, name, course_code, description, user_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(&create_course.name)
+        .bind(&create_course.course_code)
+        .bind(&create_course.description)
+        .bind(user.id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn update_course(
+        &self,
+        user: &User,
+        update_course: &UpdateCourse,
+    ) -> KeikoResult<Course> {
+        sqlx::query_as::<_, Course>(
+            r#"
+            UPDATE courses
+            SET name = $2, course_code = $3, description = $4, updated_at = $6
+            WHERE id = $1 AND id IN (SELECT course_id FROM course_roles WHERE user_id = $5)
+            RETURNING *
+            "#,
+        )
+        .bind(update_course.id)
+        .bind(&update_course.name)
+        .bind(&update_course.course_code)
+        .bind(&update_course.description)
+        .bind(user.id)
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Uuid> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id FROM courses
+            WHERE id = $1 AND id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        sqlite::delete_course(&self.pool, course_id)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("course"))
+    }
+}
@@ -0,0 +1,351 @@
+//! Bulk card import from CSV and TSV files.
+
+use std::collections::{HashMap, HashSet};
+
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+
+use crate::auth_api::User;
+use crate::card_api::CreateCard;
+use crate::import_api::{ImportAPI, ImportBatch, ImportReport, RowIssue};
+use crate::{KeikoError, KeikoResult};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DelimitedFormat {
+    #[default]
+    Csv,
+    Tsv,
+}
+
+impl DelimitedFormat {
+    fn delimiter(self) -> u8 {
+        match self {
+            DelimitedFormat::Csv => b',',
+            DelimitedFormat::Tsv => b'\t',
+        }
+    }
+}
+
+/// How to read a file. Each column is picked by header name or by zero-based index, and
+/// defaults to the header of the same name, or to the order below when there are no headers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct DelimitedImportOptions {
+    pub format: DelimitedFormat,
+    pub has_headers: bool,
+    /// Validate and report without writing anything.
+    pub dry_run: bool,
+    pub question: Option<String>,
+    pub answer: Option<String>,
+    pub category: Option<String>,
+    pub course_code: Option<String>,
+    /// Optional, cards are imported without a hint when the column is missing.
+    pub hint: Option<String>,
+}
+
+impl Default for DelimitedImportOptions {
+    fn default() -> Self {
+        Self {
+            format: DelimitedFormat::default(),
+            has_headers: true,
+            dry_run: false,
+            question: None,
+            answer: None,
+            category: None,
+            course_code: None,
+            hint: None,
+        }
+    }
+}
+
+struct Columns {
+    question: usize,
+    answer: usize,
+    category: usize,
+    course_code: usize,
+    hint: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(
+        headers: Option<&StringRecord>,
+        options: &DelimitedImportOptions,
+    ) -> KeikoResult<Self> {
+        let find = |selector: &Option<String>, name: &str, position: usize| {
+            let selector = match (selector, headers) {
+                (Some(selector), _) => selector.trim(),
+                (None, Some(_)) => name,
+                (None, None) => return Some(position),
+            };
+
+            selector.parse::<usize>().ok().or_else(|| {
+                headers?
+                    .iter()
+                    .position(|h| h.trim().eq_ignore_ascii_case(selector))
+            })
+        };
+        let require = |selector: &Option<String>, name: &str, position: usize| {
+            find(selector, name, position)
+                .ok_or_else(|| KeikoError::Validation(format!("no {} column", name)))
+        };
+
+        Ok(Self {
+            question: require(&options.question, "question", 0)?,
+            answer: require(&options.answer, "answer", 1)?,
+            category: require(&options.category, "category", 2)?,
+            course_code: require(&options.course_code, "course_code", 3)?,
+            hint: find(&options.hint, "hint", 4),
+        })
+    }
+
+    fn card(&self, record: &StringRecord) -> Result<CreateCard, String> {
+        let field = |i: usize| record.get(i).unwrap_or_default().trim().to_owned();
+        let card = CreateCard {
+            question: field(self.question),
+            answer: field(self.answer),
+            category: field(self.category),
+            course_code: field(self.course_code),
+            hint: self.hint.map(field).filter(|h| !h.is_empty()),
+        };
+
+        let missing: Vec<&str> = [
+            ("question", &card.question),
+            ("answer", &card.answer),
+            ("category", &card.category),
+            ("course_code", &card.course_code),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(name, _)| name)
+        .collect();
+
+        if missing.is_empty() {
+            Ok(card)
+        } else {
+            Err(format!("missing {}", missing.join(", ")))
+        }
+    }
+}
+
+/// The rows of a file, before they are checked against the database.
+pub struct ParsedRows {
+    /// Valid cards with their row numbers.
+    pub cards: Vec<(u64, CreateCard)>,
+    pub errors: Vec<RowIssue>,
+}
+
+pub fn parse(data: &[u8], options: &DelimitedImportOptions) -> KeikoResult<ParsedRows> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.format.delimiter())
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = if options.has_headers {
+        Some(
+            reader
+                .headers()
+                .map_err(|e| KeikoError::Validation(e.to_string()))?
+                .clone(),
+        )
+    } else {
+        None
+    };
+    let columns = Columns::resolve(headers.as_ref(), options)?;
+
+    let mut cards = Vec::new();
+    let mut errors = Vec::new();
+
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let row = record.position().map(|p| p.line()).unwrap_or_default();
+                match columns.card(&record) {
+                    Ok(card) => cards.push((row, card)),
+                    Err(message) => errors.push(RowIssue { row, message }),
+                }
+            }
+            Err(e) => errors.push(RowIssue {
+                row: e.position().map(|p| p.line()).unwrap_or_default(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(ParsedRows { cards, errors })
+}
+
+/// Validates a file against the database and, unless it is a dry run or a row is invalid,
+/// writes its cards in one transaction. Duplicate cards are reported and skipped.
+pub async fn import<S: ImportAPI>(
+    stack: &S,
+    user: &User,
+    data: &[u8],
+    options: &DelimitedImportOptions,
+) -> KeikoResult<ImportReport> {
+    let ParsedRows {
+        cards: rows,
+        mut errors,
+    } = parse(data, options)?;
+    let row_count = (rows.len() + errors.len()) as u64;
+    let batch = ImportBatch {
+        courses: Vec::new(),
+        cards: rows.iter().map(|(_, card)| card.clone()).collect(),
+    };
+    let check = stack.check_batch(user, &batch).await?;
+    let missing_courses: HashSet<&str> = check.missing_courses.iter().map(String::as_str).collect();
+    let existing_cards: HashSet<usize> = check.existing_cards.into_iter().collect();
+
+    let mut duplicates = Vec::new();
+    let mut seen: HashMap<(&str, &str), u64> = HashMap::new();
+    let mut cards = Vec::new();
+
+    for (i, (row, card)) in rows.iter().enumerate() {
+        if missing_courses.contains(card.course_code.as_str()) {
+            errors.push(RowIssue {
+                row: *row,
+                message: format!(
+                    "course {} does not exist or you cannot edit it",
+                    card.course_code
+                ),
+            });
+        } else if let Some(first) = seen.get(&(card.course_code.as_str(), card.question.as_str())) {
+            duplicates.push(RowIssue {
+                row: *row,
+                message: format!("same question as row {}", first),
+            });
+        } else if existing_cards.contains(&i) {
+            duplicates.push(RowIssue {
+                row: *row,
+                message: format!("card exists in {} already", card.course_code),
+            });
+        } else {
+            seen.insert((&card.course_code, &card.question), *row);
+            cards.push(card.clone());
+        }
+    }
+    errors.sort();
+
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        rows: row_count,
+        errors,
+        duplicates,
+        summary: None,
+    };
+
+    if report.dry_run || !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    let batch = ImportBatch {
+        courses: Vec::new(),
+        cards,
+    };
+    report.summary = Some(stack.import_batch(user, &batch).await?);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(question: &str, answer: &str, category: &str, hint: Option<&str>) -> CreateCard {
+        CreateCard {
+            question: question.to_owned(),
+            answer: answer.to_owned(),
+            category: category.to_owned(),
+            course_code: "BIO-101".to_owned(),
+            hint: hint.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn reads_columns_by_header_name_in_any_order() {
+        let data = b"Course_Code,answer,question,category,hint\n\
+                     BIO-101,Mitochondria,Powerhouse of the cell?,cells,\n\
+                     BIO-101, Ribosome ,\"Makes proteins, mostly?\",cells,small\n";
+        let rows = parse(data, &DelimitedImportOptions::default()).unwrap();
+
+        assert!(rows.errors.is_empty());
+        assert_eq!(
+            rows.cards,
+            vec![
+                (
+                    2,
+                    card("Powerhouse of the cell?", "Mitochondria", "cells", None)
+                ),
+                (
+                    3,
+                    card(
+                        "Makes proteins, mostly?",
+                        "Ribosome",
+                        "cells",
+                        Some("small")
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_columns_by_position_without_headers() {
+        let data = b"Front\tBack\tcells\tBIO-101\n";
+        let options = DelimitedImportOptions {
+            format: DelimitedFormat::Tsv,
+            has_headers: false,
+            ..DelimitedImportOptions::default()
+        };
+        let rows = parse(data, &options).unwrap();
+        assert_eq!(rows.cards, vec![(1, card("Front", "Back", "cells", None))]);
+    }
+
+    #[test]
+    fn columns_can_be_picked_by_name_or_index() {
+        let data = b"Front,Back,Deck,Course\nq,a,cells,BIO-101\n";
+        let options = DelimitedImportOptions {
+            question: Some("front".to_owned()),
+            answer: Some("1".to_owned()),
+            category: Some("Deck".to_owned()),
+            course_code: Some("3".to_owned()),
+            ..DelimitedImportOptions::default()
+        };
+        let rows = parse(data, &options).unwrap();
+        assert_eq!(rows.cards, vec![(2, card("q", "a", "cells", None))]);
+    }
+
+    #[test]
+    fn a_missing_column_fails_the_whole_file() {
+        let data = b"question,answer,course_code\nq,a,BIO-101\n";
+        assert!(matches!(
+            parse(data, &DelimitedImportOptions::default()),
+            Err(KeikoError::Validation(message)) if message == "no category column"
+        ));
+    }
+
+    #[test]
+    fn rows_with_empty_fields_are_reported_by_line() {
+        let data = b"question,answer,category,course_code\n\
+                     q,a,cells,BIO-101\n\
+                     ,a,,BIO-101\n\
+                     q,a\n";
+        let rows = parse(data, &DelimitedImportOptions::default()).unwrap();
+
+        assert_eq!(rows.cards.len(), 1);
+        assert_eq!(
+            rows.errors,
+            vec![
+                RowIssue {
+                    row: 3,
+                    message: "missing question, category".to_owned(),
+                },
+                RowIssue {
+                    row: 4,
+                    message: "missing category, course_code".to_owned(),
+                },
+            ]
+        );
+    }
+}
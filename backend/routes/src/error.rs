@@ -0,0 +1,128 @@
+use std::fmt;
+
+use ntex::http::StatusCode;
+use ntex::web::{HttpRequest, HttpResponse, WebResponseError};
+use serde::Serialize;
+
+use crate::metrics::METRICS;
+
+#[derive(Debug)]
+pub enum KeikoError {
+    /// The requested row does not exist.
+    NotFound(String),
+    /// The request collides with existing data, e.g. a duplicate course code.
+    Conflict(String),
+    /// The request was understood but its contents are invalid.
+    Validation(String),
+    /// The request lacks a valid session, or the credentials are wrong.
+    Unauthorized(String),
+    /// The user is known but their role does not allow the request.
+    Forbidden(String),
+    /// The database could not be reached (pool exhausted, connection lost).
+    Unavailable(String),
+    /// The backend in use cannot do what was asked, such as search on SQLite.
+    Unsupported(String),
+    /// Any other database failure.
+    Database(String),
+}
+
+/// JSON body returned for every error response.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ErrorBody {
+    pub error: &'static str,
+    pub message: String,
+}
+
+impl KeikoError {
+    pub fn not_found(what: impl fmt::Display) -> Self {
+        Self::NotFound(format!("{} not found", what))
+    }
+
+    /// Stable machine readable identifier of the variant.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::Validation(_) => "validation",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::Unavailable(_) => "unavailable",
+            Self::Unsupported(_) => "unsupported",
+            Self::Database(_) => "database",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(m)
+            | Self::Conflict(m)
+            | Self::Validation(m)
+            | Self::Unauthorized(m)
+            | Self::Forbidden(m)
+            | Self::Unavailable(m)
+            | Self::Unsupported(m)
+            | Self::Database(m) => m,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            error: self.code(),
+            message: self.message().to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for KeikoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for KeikoError {}
+
+impl From<sqlx::Error> for KeikoError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => Self::NotFound("row not found".to_owned()),
+            sqlx::Error::PoolTimedOut => {
+                METRICS.pool_acquire_timeouts.inc();
+                Self::Unavailable(e.to_string())
+            }
+            sqlx::Error::PoolClosed | sqlx::Error::Io(_) | sqlx::Error::Tls(_) => {
+                Self::Unavailable(e.to_string())
+            }
+            sqlx::Error::Database(ref db) => {
+                let message = db.message().to_owned();
+
+                match db.kind() {
+                    sqlx::error::ErrorKind::UniqueViolation => Self::Conflict(message),
+                    sqlx::error::ErrorKind::ForeignKeyViolation
+                    | sqlx::error::ErrorKind::NotNullViolation
+                    | sqlx::error::ErrorKind::CheckViolation => Self::Validation(message),
+                    _ => Self::Database(message),
+                }
+            }
+            e => Self::Database(e.to_string()),
+        }
+    }
+}
+
+impl WebResponseError for KeikoError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(&self.body())
+    }
+}
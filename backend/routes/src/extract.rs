@@ -0,0 +1,85 @@
+//! `Json`, `Path` and `Query` extractors that fail with a `KeikoError`, so a malformed body or
+//! an unparsable path or query answers with the same JSON error body as everything else rather
+//! than ntex's plain text.
+
+use std::ops::{Deref, DerefMut};
+
+use ntex::http::Payload;
+use ntex::web::{types, ErrorRenderer, FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+
+use crate::KeikoError;
+
+macro_rules! extractor {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    };
+}
+
+extractor!(
+    /// A JSON request body. A body that does not parse is a `Validation` error.
+    Json
+);
+extractor!(
+    /// Path parameters. Parameters that do not parse, such as an id that is not a UUID, name
+    /// nothing that exists, so they are `NotFound`.
+    Path
+);
+extractor!(
+    /// Query parameters. Parameters that do not parse are a `Validation` error.
+    Query
+);
+
+impl<T: DeserializeOwned + 'static, Err: ErrorRenderer> FromRequest<Err> for Json<T> {
+    type Error = KeikoError;
+
+    async fn from_request(req: &HttpRequest, payload: &mut Payload) -> Result<Self, Self::Error> {
+        <types::Json<T> as FromRequest<Err>>::from_request(req, payload)
+            .await
+            .map(|json| Json(json.into_inner()))
+            .map_err(|e| KeikoError::Validation(e.to_string()))
+    }
+}
+
+impl<T: DeserializeOwned, Err: ErrorRenderer> FromRequest<Err> for Path<T> {
+    type Error = KeikoError;
+
+    async fn from_request(req: &HttpRequest, payload: &mut Payload) -> Result<Self, Self::Error> {
+        <types::Path<T> as FromRequest<Err>>::from_request(req, payload)
+            .await
+            .map(|path| Path(path.into_inner()))
+            .map_err(|_| KeikoError::not_found(req.path()))
+    }
+}
+
+impl<T: DeserializeOwned, Err: ErrorRenderer> FromRequest<Err> for Query<T> {
+    type Error = KeikoError;
+
+    async fn from_request(req: &HttpRequest, _: &mut Payload) -> Result<Self, Self::Error> {
+        types::Query::<T>::from_query(req.query_string())
+            .map(|query| Query(query.into_inner()))
+            .map_err(|e| KeikoError::Validation(e.to_string()))
+    }
+}
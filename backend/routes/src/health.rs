@@ -1,12 +1,75 @@
-use ntex::web::{self, HttpResponse, ServiceConfig};
+use ntex::http::StatusCode;
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
+use serde::Serialize;
 
-pub fn service(cfg: &mut ServiceConfig) {
-    cfg.route("/health", web::get().to(health));
+use crate::health_api::HealthAPI;
+
+/// What `/version` reports, filled in by `build.rs`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// Short hash of the commit built from, `unknown` outside a git checkout.
+    pub commit: &'static str,
+    /// When the server was built, RFC 3339.
+    pub built_at: &'static str,
+}
+
+pub const BUILD_INFO: BuildInfo = BuildInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    commit: env!("KEIKO_GIT_COMMIT"),
+    built_at: env!("KEIKO_BUILD_TIME"),
+};
+
+pub fn service<S: HealthAPI>(cfg: &mut ServiceConfig) {
+    cfg.route("/health", web::get().to(live))
+        .route("/health/live", web::get().to(live))
+        .route("/health/ready", web::get().to(ready::<S>))
+        .route("/version", web::get().to(version));
 }
 
-async fn health() -> HttpResponse {
+/// GET /health/live
+///
+/// Answers as long as the server does, without touching the database.
+async fn live() -> HttpResponse {
     HttpResponse::Ok()
-        .set_header("version", "v0.0.1")
+        .set_header("version", BUILD_INFO.version)
         .set_header("status", "alive")
         .finish()
 }
+
+/// GET /health/ready
+///
+/// 503 unless the database answers and its schema is up to date.
+async fn ready<S: HealthAPI>(stack: State<S>) -> HttpResponse {
+    let readiness = stack.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    HttpResponse::build(status).json(&readiness)
+}
+
+/// GET /version
+async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(&BUILD_INFO)
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::{Method, StatusCode};
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn health_answers_without_a_token() {
+        let app = testing::app().await;
+
+        let (status, _) = app.send(Method::GET, "/health/live", None, ()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, readiness) = app.send(Method::GET, "/health/ready", None, ()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(readiness["ready"], true);
+    }
+}
@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+
+use super::{DatabaseCheck, HealthAPI, PoolStatus, Readiness};
+use crate::memory::InMemoryKeiko;
+
+#[async_trait]
+impl HealthAPI for InMemoryKeiko {
+    async fn readiness(&self) -> Readiness {
+        let database = DatabaseCheck {
+            reachable: true,
+            latency_ms: None,
+            error: None,
+        };
+
+        Readiness::new(database, None, None)
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+}
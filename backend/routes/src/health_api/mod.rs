@@ -0,0 +1,188 @@
+mod memory;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::migrate::{AppliedMigration, Migrator};
+use sqlx::{Database, Executor, FromRow, IntoArguments, Pool};
+
+use crate::migrate::{self, MigrationState, MigrationStatus};
+
+/// How long the readiness probe waits for the database. Well under the pool's acquire timeout,
+/// so a saturated pool or a dead connection fails the probe instead of hanging it.
+pub const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The migrations a database has applied. Only reads, unlike `migrate::status()`, which
+/// creates the table when missing.
+const APPLIED_MIGRATIONS: &str =
+    "SELECT version, checksum FROM _sqlx_migrations WHERE success ORDER BY version";
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Readiness {
+    /// Whether the instance should be sent traffic: the database answers and its schema is
+    /// up to date.
+    pub ready: bool,
+    pub database: DatabaseCheck,
+    /// `None` for backends without a connection pool.
+    pub pool: Option<PoolStatus>,
+    /// `None` for backends without migrations, or when the database could not be reached.
+    pub migrations: Option<MigrationCheck>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DatabaseCheck {
+    pub reachable: bool,
+    /// Round trip of a `SELECT 1`.
+    pub latency_ms: Option<f64>,
+    /// Which check failed. The probe needs no token, so the database's own error is only
+    /// logged.
+    pub error: Option<&'static str>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PoolStatus {
+    /// Open connections, idle or not.
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+    /// `in_use` over `max_connections`, 1 when every connection is taken.
+    pub saturation: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationCheck {
+    /// Versions not applied yet.
+    pub pending: Vec<i64>,
+    /// Versions applied from a file that has been edited since.
+    pub modified: Vec<i64>,
+}
+
+#[async_trait]
+pub trait HealthAPI: Send + Sync + 'static {
+    async fn readiness(&self) -> Readiness;
+    /// `None` for backends without a connection pool.
+    fn pool_status(&self) -> Option<PoolStatus>;
+}
+
+impl Readiness {
+    pub fn new(
+        database: DatabaseCheck,
+        pool: Option<PoolStatus>,
+        migrations: Option<MigrationCheck>,
+    ) -> Self {
+        let ready = database.reachable
+            && migrations
+                .as_ref()
+                .is_none_or(|m| m.pending.is_empty() && m.modified.is_empty());
+
+        Self {
+            ready,
+            database,
+            pool,
+            migrations,
+        }
+    }
+
+    /// Runs `SELECT 1` on the pool and reads which migrations of `migrator` it has applied,
+    /// giving up on both after `PING_TIMEOUT`.
+    pub async fn check<DB>(pool: &Pool<DB>, migrator: &Migrator) -> Self
+    where
+        DB: Database,
+        for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+        for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+        for<'r> (i64, Vec<u8>): FromRow<'r, DB::Row>,
+    {
+        let status = PoolStatus::of(pool);
+        let start = Instant::now();
+        let mut latency_ms = None;
+        let result = ntex::time::timeout(PING_TIMEOUT, async {
+            sqlx::query("SELECT 1")
+                .execute(pool)
+                .await
+                .map_err(|e| ("database did not answer", e))?;
+            latency_ms = Some(start.elapsed().as_secs_f64() * 1000.0);
+
+            sqlx::query_as::<_, (i64, Vec<u8>)>(APPLIED_MIGRATIONS)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ("could not read the applied migrations", e))
+        })
+        .await;
+
+        let applied = match result {
+            Ok(Ok(applied)) => applied,
+            Ok(Err((error, e))) => {
+                tracing::warn!("Readiness check failed, {}: {}", error, e);
+                return Self::new(DatabaseCheck::failed(error), Some(status), None);
+            }
+            Err(()) => {
+                let error = "database did not answer in time";
+                return Self::new(DatabaseCheck::failed(error), Some(status), None);
+            }
+        };
+        let applied: Vec<AppliedMigration> = applied
+            .into_iter()
+            .map(|(version, checksum)| AppliedMigration {
+                version,
+                checksum: checksum.into(),
+            })
+            .collect();
+        let database = DatabaseCheck {
+            reachable: true,
+            latency_ms,
+            error: None,
+        };
+        let migrations = MigrationCheck::new(&migrate::compare(migrator, &applied));
+
+        Self::new(database, Some(status), Some(migrations))
+    }
+}
+
+impl DatabaseCheck {
+    pub fn failed(error: &'static str) -> Self {
+        Self {
+            reachable: false,
+            latency_ms: None,
+            error: Some(error),
+        }
+    }
+}
+
+impl PoolStatus {
+    pub fn of<DB: Database>(pool: &Pool<DB>) -> Self {
+        let size = pool.size();
+        let idle = pool.num_idle().min(size as usize) as u32;
+        let in_use = size - idle;
+        let max_connections = pool.options().get_max_connections();
+
+        Self {
+            size,
+            idle,
+            in_use,
+            max_connections,
+            saturation: f64::from(in_use) / f64::from(max_connections.max(1)),
+        }
+    }
+}
+
+impl MigrationCheck {
+    pub fn new(status: &[MigrationStatus]) -> Self {
+        let versions = |state: MigrationState| {
+            status
+                .iter()
+                .filter(|m| m.state == state)
+                .map(|m| m.version)
+                .collect()
+        };
+
+        Self {
+            pending: versions(MigrationState::Pending),
+            modified: versions(MigrationState::ChecksumMismatch),
+        }
+    }
+}
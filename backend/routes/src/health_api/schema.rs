@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use super::{HealthAPI, PoolStatus, Readiness};
+use crate::migrate::MIGRATOR;
+use crate::KeikoDatabase;
+
+#[async_trait]
+impl HealthAPI for KeikoDatabase {
+    async fn readiness(&self) -> Readiness {
+        Readiness::check(&self.pool, &MIGRATOR).await
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus::of(&self.pool))
+    }
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+use super::{HealthAPI, PoolStatus, Readiness};
+use crate::sqlite::{KeikoSqlite, MIGRATOR};
+
+#[async_trait]
+impl HealthAPI for KeikoSqlite {
+    async fn readiness(&self) -> Readiness {
+        Readiness::check(&self.pool, &MIGRATOR).await
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(PoolStatus::of(&self.pool))
+    }
+}
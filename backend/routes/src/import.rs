@@ -0,0 +1,104 @@
+use ntex::util::Bytes;
+use ntex::web::{
+    self,
+    types::{PayloadConfig, State},
+    HttpResponse, ServiceConfig,
+};
+
+#[cfg(feature = "anki")]
+use crate::anki::{self, AnkiImportOptions};
+use crate::auth_api::User;
+use crate::delimited::{self, DelimitedImportOptions};
+use crate::extract::Query;
+use crate::import_api::ImportAPI;
+use crate::KeikoResult;
+
+/// Imports carry whole decks, so they are allowed to be much larger than JSON bodies.
+const MAX_UPLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+pub fn service<S: ImportAPI>(cfg: &mut ServiceConfig) {
+    let scope = web::scope("/v1/import")
+        .state(PayloadConfig::new(MAX_UPLOAD_SIZE))
+        .route("/cards", web::post().to(import_cards::<S>));
+    #[cfg(feature = "anki")]
+    let scope = scope.route("/anki", web::post().to(import_anki::<S>));
+    cfg.service(scope);
+}
+
+/// POST /v1/import/anki
+#[cfg(feature = "anki")]
+async fn import_anki<S: ImportAPI>(
+    package: Bytes,
+    options: Query<AnkiImportOptions>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let summary = anki::import(&*stack, &user, package, &options).await?;
+    Ok(HttpResponse::Ok().json(&summary))
+}
+
+/// POST /v1/import/cards
+async fn import_cards<S: ImportAPI>(
+    file: Bytes,
+    options: Query<DelimitedImportOptions>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let report = delimited::import(&*stack, &user, &file, &options).await?;
+    if report.dry_run || report.errors.is_empty() {
+        Ok(HttpResponse::Ok().json(&report))
+    } else {
+        Ok(HttpResponse::UnprocessableEntity().json(&report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+
+    use crate::testing;
+
+    const CARDS: &str = "question,answer,category,course_code\n\
+                         Q1,A1,Basics,C1\n\
+                         Q2,A2,Basics,C1\n";
+
+    #[ntex::test]
+    async fn cards_are_imported_once() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+
+        let (status, report) = app
+            .upload("/v1/import/cards?dry_run=true", &alice, CARDS)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["rows"], 2);
+        assert!(report["summary"].is_null());
+        let (_, cards) = app.get("/v1/cards", &alice).await;
+        assert!(cards.as_array().unwrap().is_empty());
+
+        let (status, report) = app.upload("/v1/import/cards", &alice, CARDS).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["summary"]["cards_created"], 2);
+
+        let (status, report) = app.upload("/v1/import/cards", &alice, CARDS).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["duplicates"].as_array().unwrap().len(), 2);
+        let (_, cards) = app.get("/v1/cards", &alice).await;
+        assert_eq!(cards.as_array().unwrap().len(), 2);
+    }
+
+    #[ntex::test]
+    async fn cards_need_a_course_the_user_can_edit() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        app.course(&alice, "C1").await;
+
+        let (status, report) = app.upload("/v1/import/cards", &bob, CARDS).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(report["errors"].as_array().unwrap().len(), 2);
+        let (_, cards) = app.get("/v1/cards", &alice).await;
+        assert!(cards.as_array().unwrap().is_empty());
+    }
+}
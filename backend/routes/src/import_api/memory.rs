@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{BatchCheck, ImportAPI, ImportBatch, ImportSummary};
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::course_api::Course;
+use crate::member_api::Role;
+use crate::memory::{InMemoryKeiko, State};
+use crate::metrics::METRICS;
+use crate::{KeikoError, KeikoResult};
+
+/// The id of the course, if `user` may edit it.
+fn editable_course(state: &State, user: &User, course_code: &str) -> Option<Uuid> {
+    state
+        .course_by_code(course_code)
+        .filter(|c| state.course_role(c, user) >= Some(Role::Editor))
+        .map(|c| c.id)
+}
+
+#[async_trait]
+impl ImportAPI for InMemoryKeiko {
+    async fn import_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<ImportSummary> {
+        let mut state = self.state();
+        let now = chrono::Utc::now();
+
+        let mut new_courses: Vec<Course> = Vec::new();
+        for course in &batch.courses {
+            let code = course.course_code.as_str();
+            if state.course_code_holder(code).is_none()
+                && !new_courses.iter().any(|c| c.course_code == code)
+            {
+                new_courses.push(Course {
+                    id: Uuid::new_v4(),
+                    user_id: Some(user.id),
+                    name: course.name.clone(),
+                    course_code: course.course_code.clone(),
+                    description: course.description.clone(),
+                    created_at: now,
+                    updated_at: None,
+                });
+            }
+        }
+
+        // Checked before anything is written, as the whole batch is one transaction.
+        let mut course_ids = Vec::with_capacity(batch.cards.len());
+        for card in &batch.cards {
+            let course_id = new_courses
+                .iter()
+                .find(|c| c.course_code == card.course_code)
+                .map(|c| c.id)
+                .or_else(|| editable_course(&state, user, &card.course_code))
+                .ok_or_else(|| {
+                    KeikoError::Validation(format!(
+                        "course {} does not exist or you cannot edit it",
+                        card.course_code
+                    ))
+                })?;
+            course_ids.push(course_id);
+        }
+
+        let courses_created = new_courses.len() as u64;
+        state.courses.extend(new_courses);
+
+        for (card, course_id) in batch.cards.iter().zip(course_ids) {
+            state.ensure_category(&course_id, &card.category);
+
+            let card = Card {
+                id: Uuid::new_v4(),
+                question: card.question.clone(),
+                answer: card.answer.clone(),
+                course_id,
+                course_code: card.course_code.clone(),
+                category: card.category.clone(),
+                hint: card.hint.clone().filter(|h| !h.is_empty()),
+                created_at: now,
+                updated_at: None,
+            };
+            state.cards.push(card.clone());
+            state.add_card_revision(&card, user);
+        }
+
+        let cards_created = batch.cards.len() as u64;
+        METRICS.cards_created.inc_by(cards_created);
+
+        Ok(ImportSummary {
+            courses_created,
+            cards_created,
+            duplicates_skipped: 0,
+        })
+    }
+
+    async fn check_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<BatchCheck> {
+        let state = self.state();
+
+        let mut missing_courses: Vec<String> = batch
+            .cards
+            .iter()
+            .map(|c| c.course_code.as_str())
+            .filter(|code| !batch.courses.iter().any(|c| c.course_code == *code))
+            .filter(|code| editable_course(&state, user, code).is_none())
+            .map(str::to_owned)
+            .collect();
+        missing_courses.sort_unstable();
+        missing_courses.dedup();
+
+        let existing: HashSet<(&str, &str)> = state
+            .cards
+            .iter()
+            .map(|c| (c.course_code.as_str(), c.question.as_str()))
+            .collect();
+        let existing_cards = batch
+            .cards
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| existing.contains(&(c.course_code.as_str(), c.question.as_str())))
+            .map(|(i, _)| i)
+            .collect();
+
+        Ok(BatchCheck {
+            missing_courses,
+            existing_cards,
+        })
+    }
+}
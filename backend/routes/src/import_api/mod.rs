@@ -0,0 +1,65 @@
+mod memory;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::auth_api::User;
+use crate::card_api::CreateCard;
+use crate::course_api::CreateCourse;
+use crate::KeikoResult;
+
+/// Courses and cards written together by an importer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct ImportBatch {
+    /// Courses to create. Ones whose `course_code` already exists are left untouched.
+    pub courses: Vec<CreateCourse>,
+    pub cards: Vec<CreateCard>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct ImportSummary {
+    pub courses_created: u64,
+    pub cards_created: u64,
+    /// Cards left out because their course has the question already or the import repeats it.
+    pub duplicates_skipped: u64,
+}
+
+/// A problem with one row of an import, numbered as in the source file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct RowIssue {
+    pub row: u64,
+    pub message: String,
+}
+
+/// Outcome of a row-based import. Nothing is written when there are errors or on a dry run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: u64,
+    pub errors: Vec<RowIssue>,
+    /// Rows skipped because the card exists already or appears earlier in the file.
+    pub duplicates: Vec<RowIssue>,
+    /// Set once the cards have been written.
+    pub summary: Option<ImportSummary>,
+}
+
+/// What the database already holds for a batch, used to validate it before writing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct BatchCheck {
+    /// Course codes referenced by cards that the user cannot edit and that are not part of the
+    /// batch.
+    pub missing_courses: Vec<String>,
+    /// Indexes into `cards` of cards whose question exists in the course already.
+    pub existing_cards: Vec<usize>,
+}
+
+#[async_trait]
+pub trait ImportAPI: Send + Sync + 'static {
+    /// Writes the whole batch in one transaction, with `user` owning the new courses. Every
+    /// card must belong to a course the user can edit or that is part of the batch.
+    async fn import_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<ImportSummary>;
+    async fn check_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<BatchCheck>;
+}
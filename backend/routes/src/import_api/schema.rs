@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+
+use super::{BatchCheck, ImportAPI, ImportBatch, ImportSummary};
+use crate::auth_api::User;
+use crate::metrics::METRICS;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+#[async_trait]
+impl ImportAPI for KeikoDatabase {
+    async fn import_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<ImportSummary> {
+        let mut tx = self.pool.begin().await?;
+        let mut courses_created = 0;
+
+        for course in &batch.courses {
+            courses_created += sqlx::query(
+                r#"
+                INSERT INTO courses (name, course_code, description, user_id)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (course_code) DO NOTHING
+                "#,
+            )
+            .bind(&course.name)
+            .bind(&course.course_code)
+            .bind(&course.description)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        let mut course_codes: Vec<&str> =
+            batch.cards.iter().map(|c| c.course_code.as_str()).collect();
+        course_codes.sort_unstable();
+        course_codes.dedup();
+
+        let known = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT course_code FROM courses
+            WHERE course_code = ANY($1) AND course_role(id, $2) IN ('owner', 'editor')
+            "#,
+        )
+        .bind(&course_codes)
+        .bind(user.id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if let Some(missing) = course_codes
+            .iter()
+            .find(|code| !known.iter().any(|k| k == *code))
+        {
+            return Err(KeikoError::Validation(format!(
+                "course {} does not exist or you cannot edit it",
+                missing
+            )));
+        }
+
+        // Categories new to a course are added in the order the batch first names them.
+        let mut seen = HashSet::new();
+        let categories: Vec<(&str, &str)> = batch
+            .cards
+            .iter()
+            .map(|c| (c.course_code.as_str(), c.category.as_str()))
+            .filter(|pair| seen.insert(*pair))
+            .collect();
+        sqlx::query(
+            r#"
+            SELECT ensure_category(c.id, t.category)
+            FROM UNNEST($1::text[], $2::text[]) WITH ORDINALITY AS t (course_code, category, n)
+            JOIN courses c ON c.course_code = t.course_code
+            ORDER BY t.n
+            "#,
+        )
+        .bind(categories.iter().map(|c| c.0).collect::<Vec<_>>())
+        .bind(categories.iter().map(|c| c.1).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+
+        let cards_created = sqlx::query(
+            r#"
+            WITH inserted AS (
+                INSERT INTO cards (question, answer, course_id, course_code, category, hint)
+                SELECT t.question, t.answer, c.id, t.course_code, t.category, NULLIF(t.hint, '')
+                FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[])
+                    AS t (question, answer, course_code, category, hint)
+                JOIN courses c ON c.course_code = t.course_code
+                RETURNING *
+            )
+            INSERT INTO card_revisions
+                (card_id, revision, question, answer, category, hint, user_id)
+            SELECT id, 1, question, answer, category, hint, $6
+            FROM inserted
+            "#,
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.question.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.answer.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.course_code.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.category.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.hint.as_deref())
+                .collect::<Vec<_>>(),
+        )
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        METRICS.cards_created.inc_by(cards_created);
+
+        Ok(ImportSummary {
+            courses_created,
+            cards_created,
+            duplicates_skipped: 0,
+        })
+    }
+
+    async fn check_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<BatchCheck> {
+        let mut course_codes: Vec<&str> = batch
+            .cards
+            .iter()
+            .map(|c| c.course_code.as_str())
+            .filter(|code| !batch.courses.iter().any(|c| c.course_code == *code))
+            .collect();
+        course_codes.sort_unstable();
+        course_codes.dedup();
+
+        let known = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT course_code FROM courses
+            WHERE course_code = ANY($1) AND course_role(id, $2) IN ('owner', 'editor')
+            "#,
+        )
+        .bind(&course_codes)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let missing_courses = course_codes
+            .into_iter()
+            .filter(|code| !known.iter().any(|k| k == code))
+            .map(str::to_owned)
+            .collect();
+
+        let existing_cards = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT t.i - 1
+            FROM UNNEST($1::text[], $2::text[]) WITH ORDINALITY AS t (question, course_code, i)
+            WHERE EXISTS (
+                SELECT 1 FROM cards c
+                WHERE c.course_code = t.course_code AND c.question = t.question
+                AND c.deleted_at IS NULL
+            )
+            ORDER BY t.i
+            "#,
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.question.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .bind(
+            batch
+                .cards
+                .iter()
+                .map(|c| c.course_code.as_str())
+                .collect::<Vec<_>>(),
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|i| i as usize)
+        .collect();
+
+        Ok(BatchCheck {
+            missing_courses,
+            existing_cards,
+        })
+    }
+}
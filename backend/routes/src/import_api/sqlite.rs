@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::{BatchCheck, ImportAPI, ImportBatch, ImportSummary};
+use crate::auth_api::User;
+use crate::metrics::METRICS;
+use crate::sqlite::{add_card_revision, ensure_category, KeikoSqlite};
+use crate::{KeikoError, KeikoResult};
+
+/// The id of the course, if `user` may edit it.
+async fn editable_course<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    user: &User,
+    course_code: &str,
+) -> KeikoResult<Option<Uuid>> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM courses
+        WHERE course_code = $1 AND id IN (
+            SELECT course_id FROM course_roles
+            WHERE user_id = $2 AND role IN ('owner', 'editor')
+        )
+        "#,
+    )
+    .bind(course_code)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+#[async_trait]
+impl ImportAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn import_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<ImportSummary> {
+        let mut tx = self.pool.begin().await?;
+        let mut courses_created = 0;
+
+        for course in &batch.courses {
+            courses_created += sqlx::query(
+                r#"
+                INSERT INTO courses (id, name, course_code, description, user_id)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (course_code) DO NOTHING
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(&course.name)
+            .bind(&course.course_code)
+            .bind(&course.description)
+            .bind(user.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        let mut course_ids = HashMap::new();
+        for card in &batch.cards {
+            let code = card.course_code.as_str();
+            if !course_ids.contains_key(code) {
+                let course_id = editable_course(&mut *tx, user, code)
+                    .await?
+                    .ok_or_else(|| {
+                        KeikoError::Validation(format!(
+                            "course {} does not exist or you cannot edit it",
+                            code
+                        ))
+                    })?;
+                course_ids.insert(code, course_id);
+            }
+        }
+
+        // Categories new to a course are added in the order the batch first names them.
+        let mut cards_created = 0;
+        for card in &batch.cards {
+            let course_id = course_ids[card.course_code.as_str()];
+            ensure_category(&mut *tx, &course_id, &card.category).await?;
+
+            let card_id = Uuid::new_v4();
+            sqlx::query(
+                r#"
+                INSERT INTO cards (id, question, answer, course_id, course_code, category, hint)
+                VALUES ($1, $2, $3, $4, $5, $6, NULLIF($7, ''))
+                "#,
+            )
+            .bind(card_id)
+            .bind(&card.question)
+            .bind(&card.answer)
+            .bind(course_id)
+            .bind(&card.course_code)
+            .bind(&card.category)
+            .bind(&card.hint)
+            .execute(&mut *tx)
+            .await?;
+            add_card_revision(&mut *tx, user, &card_id).await?;
+            cards_created += 1;
+        }
+
+        tx.commit().await?;
+        METRICS.cards_created.inc_by(cards_created);
+
+        Ok(ImportSummary {
+            courses_created,
+            cards_created,
+            duplicates_skipped: 0,
+        })
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn check_batch(&self, user: &User, batch: &ImportBatch) -> KeikoResult<BatchCheck> {
+        let mut conn = self.pool.acquire().await?;
+
+        let mut course_codes: Vec<&str> = batch
+            .cards
+            .iter()
+            .map(|c| c.course_code.as_str())
+            .filter(|code| !batch.courses.iter().any(|c| c.course_code == *code))
+            .collect();
+        course_codes.sort_unstable();
+        course_codes.dedup();
+
+        let mut missing_courses = Vec::new();
+        for code in course_codes {
+            if editable_course(&mut *conn, user, code).await?.is_none() {
+                missing_courses.push(code.to_owned());
+            }
+        }
+
+        let mut questions: HashMap<&str, HashSet<String>> = HashMap::new();
+        for card in &batch.cards {
+            let code = card.course_code.as_str();
+            if !questions.contains_key(code) {
+                let existing = sqlx::query_scalar::<_, String>(
+                    "SELECT question FROM cards WHERE course_code = $1 AND deleted_at IS NULL",
+                )
+                .bind(code)
+                .fetch_all(&mut *conn)
+                .await?;
+                questions.insert(code, existing.into_iter().collect());
+            }
+        }
+
+        let existing_cards = batch
+            .cards
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| questions[c.course_code.as_str()].contains(&c.question))
+            .map(|(i, _)| i)
+            .collect();
+
+        Ok(BatchCheck {
+            missing_courses,
+            existing_cards,
+        })
+    }
+}
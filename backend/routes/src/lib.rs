@@ -1,11 +1,42 @@
+pub mod access;
+#[cfg(feature = "anki")]
+pub mod anki;
+pub mod auth;
+pub mod auth_api;
 pub mod card;
 pub mod card_api;
+pub mod category;
+pub mod category_api;
 pub mod course;
 pub mod course_api;
+pub mod delimited;
+pub mod error;
+pub mod extract;
 pub mod health;
+pub mod health_api;
+pub mod import;
+pub mod import_api;
+pub mod list;
+pub mod member_api;
+pub mod memory;
+pub mod metrics;
+pub mod migrate;
 pub mod quiz;
 pub mod quiz_api;
+pub mod request_id;
+pub mod review;
+pub mod review_api;
+pub mod search;
+pub mod search_api;
+pub mod session;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(test)]
+mod testing;
+pub mod trash;
+pub mod trash_api;
 
+#[derive(Clone)]
 pub struct KeikoDatabase {
     pool: sqlx::PgPool,
 }
@@ -16,6 +47,9 @@ impl KeikoDatabase {
     }
 }
 
-pub type KeikoResult<T> = Result<T, String>;
+pub use error::KeikoError;
+pub use memory::InMemoryKeiko;
+#[cfg(feature = "sqlite")]
+pub use sqlite::KeikoSqlite;
 
-pub const SCHEMA: &str = include_str!("schema.sql");
+pub type KeikoResult<T> = Result<T, KeikoError>;
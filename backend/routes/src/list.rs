@@ -0,0 +1,671 @@
+//! Pagination, sorting and filtering shared by the list endpoints.
+
+use ntex::http::Payload;
+use ntex::web::{types::Query, FromRequest, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::{KeikoError, KeikoResult};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Query parameters accepted by every list endpoint. Filters a list does not support are
+/// rejected rather than ignored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// `next_cursor` of the previous page. Takes precedence over `offset`.
+    pub cursor: Option<String>,
+    /// Field to sort by, prefixed with `-` for descending order.
+    pub sort: Option<String>,
+    pub course_code: Option<String>,
+    pub category: Option<String>,
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub is_completed: Option<bool>,
+}
+
+impl ListQuery {
+    fn validate(&self) -> KeikoResult<()> {
+        if self
+            .limit
+            .is_some_and(|l| !(1..=MAX_PAGE_SIZE).contains(&l))
+        {
+            return Err(KeikoError::Validation(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+        if self.offset.is_some_and(|o| o < 0) {
+            return Err(KeikoError::Validation(
+                "offset must not be negative".to_owned(),
+            ));
+        }
+        if let Some(cursor) = &self.cursor {
+            Cursor::decode(cursor)?;
+        }
+
+        Ok(())
+    }
+
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    fn filters(&self) -> Vec<(&'static str, FilterValue<'_>)> {
+        let mut filters = Vec::new();
+        if let Some(v) = &self.course_code {
+            filters.push(("course_code", FilterValue::Text(v)));
+        }
+        if let Some(v) = &self.category {
+            filters.push(("category", FilterValue::Text(v)));
+        }
+        if let Some(v) = self.created_after {
+            filters.push(("created_after", FilterValue::Time(v)));
+        }
+        if let Some(v) = self.is_completed {
+            filters.push(("is_completed", FilterValue::Bool(v)));
+        }
+
+        filters
+    }
+}
+
+impl<Err> FromRequest<Err> for ListQuery {
+    type Error = KeikoError;
+
+    async fn from_request(req: &HttpRequest, _: &mut Payload) -> Result<Self, Self::Error> {
+        let query = Query::<ListQuery>::from_query(req.query_string())
+            .map_err(|e| KeikoError::Validation(e.to_string()))?
+            .into_inner();
+        query.validate()?;
+
+        Ok(query)
+    }
+}
+
+/// One page of a list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Set when there are more items after this page.
+    pub next_cursor: Option<String>,
+    /// Number of items matching the filters, on all pages.
+    pub total: i64,
+}
+
+impl<T: Serialize> Page<T> {
+    /// Responds with the items as a plain JSON array, as the list endpoints always have, and
+    /// puts `total` and `next_cursor` in headers.
+    pub fn respond(&self) -> HttpResponse {
+        let mut response = HttpResponse::Ok();
+        response.header(TOTAL_COUNT_HEADER, self.total.to_string());
+        if let Some(cursor) = &self.next_cursor {
+            response.header(NEXT_CURSOR_HEADER, cursor.as_str());
+        }
+
+        response.json(&self.items)
+    }
+}
+
+/// Position after the last item of a page: the sort field, its value and the item id.
+struct Cursor {
+    sort: String,
+    id: Uuid,
+    value: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        format!("{}\n{}\n{}", self.sort, self.id, self.value)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn decode(cursor: &str) -> KeikoResult<Self> {
+        let invalid = || KeikoError::Validation("invalid cursor".to_owned());
+
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+
+        let mut parts = text.splitn(3, '\n');
+        let (Some(sort), Some(id), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+
+        Ok(Self {
+            sort: sort.to_owned(),
+            id: id.parse().map_err(|_| invalid())?,
+            value: value.to_owned(),
+        })
+    }
+}
+
+pub enum FilterValue<'a> {
+    Text(&'a str),
+    Time(chrono::DateTime<chrono::Utc>),
+    Bool(bool),
+}
+
+/// Value of a sort field of a row listed from memory.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SortValue {
+    Text(String),
+    Time(chrono::DateTime<chrono::Utc>),
+    Integer(i64),
+}
+
+impl SortValue {
+    fn encode(&self) -> String {
+        match self {
+            SortValue::Text(v) => v.clone(),
+            SortValue::Time(v) => v.to_rfc3339(),
+            SortValue::Integer(v) => v.to_string(),
+        }
+    }
+
+    fn decode(field: &SortField, value: &str) -> KeikoResult<Self> {
+        let invalid = || KeikoError::Validation("invalid cursor".to_owned());
+        match field.sql_type {
+            "timestamptz" => chrono::DateTime::parse_from_rfc3339(value)
+                .map(|v| SortValue::Time(v.to_utc()))
+                .map_err(|_| invalid()),
+            "integer" => value.parse().map(SortValue::Integer).map_err(|_| invalid()),
+            _ => Ok(SortValue::Text(value.to_owned())),
+        }
+    }
+}
+
+/// A row listed from memory rather than with SQL, the counterpart of a `Listing`'s columns
+/// and filter conditions.
+pub trait Columns {
+    fn id(&self) -> Uuid;
+    /// Value of one of the listing's sort fields.
+    fn sort_value(&self, column: &str) -> Option<SortValue>;
+    /// Whether the row passes one of the listing's filters.
+    fn matches(&self, filter: &str, value: &FilterValue<'_>) -> bool;
+}
+
+/// A non-null column a list can be sorted by.
+pub struct SortField {
+    pub column: &'static str,
+    /// Postgres type the column is compared as when resuming from a cursor.
+    pub sql_type: &'static str,
+}
+
+pub struct Filter {
+    /// Name of the `ListQuery` filter.
+    pub name: &'static str,
+    /// SQL condition, in which `$?` stands for the filter value.
+    pub condition: &'static str,
+}
+
+/// How a table or view is listed. The first sort field is the default.
+pub struct Listing {
+    pub name: &'static str,
+    pub from: &'static str,
+    /// SQL condition limiting rows to those the user can see, `$?` stands for the user id.
+    pub visible_to: &'static str,
+    pub sort_fields: &'static [SortField],
+    pub filters: &'static [Filter],
+}
+
+impl Listing {
+    fn push_filters<'a>(
+        &self,
+        builder: &mut QueryBuilder<'a, Postgres>,
+        user: &User,
+        filters: &[(&'static str, FilterValue<'a>)],
+    ) {
+        let (before, after) = self
+            .visible_to
+            .split_once("$?")
+            .unwrap_or((self.visible_to, ""));
+        builder
+            .push(" WHERE ")
+            .push(before)
+            .push_bind(user.id)
+            .push(after);
+
+        for (name, value) in filters {
+            let Some(filter) = self.filters.iter().find(|f| f.name == *name) else {
+                continue;
+            };
+            let (before, after) = filter
+                .condition
+                .split_once("$?")
+                .unwrap_or((filter.condition, ""));
+
+            builder.push(" AND ").push(before);
+            match value {
+                FilterValue::Text(v) => builder.push_bind(*v),
+                FilterValue::Time(v) => builder.push_bind(*v),
+                FilterValue::Bool(v) => builder.push_bind(*v),
+            };
+            builder.push(after);
+        }
+    }
+
+    /// Checks `query` against what the listing supports and works out its sort order.
+    fn plan<'q>(&self, query: &'q ListQuery) -> KeikoResult<Plan<'q>> {
+        let filters = query.filters();
+        if let Some((name, _)) = filters
+            .iter()
+            .find(|(name, _)| !self.filters.iter().any(|f| f.name == *name))
+        {
+            return Err(KeikoError::Validation(format!(
+                "{} cannot be filtered by {}",
+                self.name, name
+            )));
+        }
+
+        let sort = query.sort.as_deref().unwrap_or(self.sort_fields[0].column);
+        let (column, descending) = match sort.strip_prefix('-') {
+            Some(column) => (column, true),
+            None => (sort, false),
+        };
+        let field = self
+            .sort_fields
+            .iter()
+            .find(|f| f.column == column)
+            .ok_or_else(|| {
+                KeikoError::Validation(format!("{} cannot be sorted by {}", self.name, column))
+            })?;
+
+        let cursor = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+        if cursor.as_ref().is_some_and(|c| c.sort != sort) {
+            return Err(KeikoError::Validation(
+                "cursor was created with a different sort".to_owned(),
+            ));
+        }
+
+        Ok(Plan {
+            filters,
+            sort,
+            field,
+            descending,
+            cursor,
+        })
+    }
+
+    /// Fetches one page of rows matching `query`, along with the total count.
+    pub async fn fetch<T>(
+        &self,
+        pool: &PgPool,
+        user: &User,
+        query: &ListQuery,
+    ) -> KeikoResult<Page<T>>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let Plan {
+            filters,
+            sort,
+            field,
+            descending,
+            cursor,
+        } = self.plan(query)?;
+
+        let mut count = QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", self.from));
+        self.push_filters(&mut count, user, &filters);
+        let total: i64 = count.build_query_scalar().fetch_one(pool).await?;
+
+        let mut select = QueryBuilder::new(format!(
+            "SELECT *, {}::text AS sort_value FROM {}",
+            field.column, self.from
+        ));
+        self.push_filters(&mut select, user, &filters);
+
+        let (order, comparison) = if descending {
+            ("DESC", "<")
+        } else {
+            ("ASC", ">")
+        };
+
+        if let Some(cursor) = &cursor {
+            select
+                .push(format!(" AND ({}, id) {} (", field.column, comparison))
+                .push_bind(cursor.value.clone())
+                .push(format!("::{}, ", field.sql_type))
+                .push_bind(cursor.id)
+                .push(")");
+        }
+
+        select.push(format!(
+            " ORDER BY {} {}, id {} LIMIT ",
+            field.column, order, order
+        ));
+        select.push_bind(query.limit() + 1);
+        if cursor.is_none() {
+            select.push(" OFFSET ").push_bind(query.offset.unwrap_or(0));
+        }
+
+        let mut rows = select.build().fetch_all(pool).await?;
+
+        let mut next_cursor = None;
+        if rows.len() as i64 > query.limit() {
+            rows.truncate(query.limit() as usize);
+            if let Some(last) = rows.last() {
+                let cursor = Cursor {
+                    sort: sort.to_owned(),
+                    id: last.try_get("id")?,
+                    value: last.try_get("sort_value")?,
+                };
+                next_cursor = Some(cursor.encode());
+            }
+        }
+
+        let items = rows
+            .iter()
+            .map(T::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page {
+            items,
+            next_cursor,
+            total,
+        })
+    }
+
+    /// Picks one page of `rows` matching `query`, in the same way `fetch` does in SQL. The
+    /// rows must already be limited to those the user can see.
+    pub fn page<T: Columns>(&self, rows: Vec<T>, query: &ListQuery) -> KeikoResult<Page<T>> {
+        let Plan {
+            filters,
+            sort,
+            field,
+            descending,
+            cursor,
+        } = self.plan(query)?;
+
+        let mut rows: Vec<(SortValue, T)> = rows
+            .into_iter()
+            .filter(|row| filters.iter().all(|(name, v)| row.matches(name, v)))
+            .filter_map(|row| Some((row.sort_value(field.column)?, row)))
+            .collect();
+        let total = rows.len() as i64;
+
+        rows.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.id().cmp(&y.id())));
+        if descending {
+            rows.reverse();
+        }
+
+        let start = match &cursor {
+            Some(cursor) => {
+                let after = (SortValue::decode(field, &cursor.value)?, cursor.id);
+                rows.iter()
+                    .position(|(value, row)| {
+                        let key = (value.clone(), row.id());
+                        if descending {
+                            key < after
+                        } else {
+                            key > after
+                        }
+                    })
+                    .unwrap_or(rows.len())
+            }
+            None => query.offset.unwrap_or(0).min(total) as usize,
+        };
+
+        let mut rows = rows.split_off(start);
+        let mut next_cursor = None;
+        if rows.len() as i64 > query.limit() {
+            rows.truncate(query.limit() as usize);
+            if let Some((value, last)) = rows.last() {
+                let cursor = Cursor {
+                    sort: sort.to_owned(),
+                    id: last.id(),
+                    value: value.encode(),
+                };
+                next_cursor = Some(cursor.encode());
+            }
+        }
+
+        Ok(Page {
+            items: rows.into_iter().map(|(_, row)| row).collect(),
+            next_cursor,
+            total,
+        })
+    }
+}
+
+/// A `ListQuery` checked against a `Listing`.
+struct Plan<'q> {
+    filters: Vec<(&'static str, FilterValue<'q>)>,
+    sort: &'q str,
+    field: &'static SortField,
+    descending: bool,
+    cursor: Option<Cursor>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Row {
+        id: Uuid,
+        name: String,
+        count: i64,
+    }
+
+    impl Columns for Row {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+
+        fn sort_value(&self, column: &str) -> Option<SortValue> {
+            match column {
+                "name" => Some(SortValue::Text(self.name.clone())),
+                "count" => Some(SortValue::Integer(self.count)),
+                _ => None,
+            }
+        }
+
+        fn matches(&self, filter: &str, value: &FilterValue<'_>) -> bool {
+            match (filter, value) {
+                ("category", FilterValue::Text(v)) => self.name.starts_with(v),
+                _ => false,
+            }
+        }
+    }
+
+    const ROWS: Listing = Listing {
+        name: "rows",
+        from: "rows",
+        visible_to: "TRUE",
+        sort_fields: &[
+            SortField {
+                column: "name",
+                sql_type: "text",
+            },
+            SortField {
+                column: "count",
+                sql_type: "integer",
+            },
+        ],
+        filters: &[Filter {
+            name: "category",
+            condition: "name LIKE $? || '%'",
+        }],
+    };
+
+    /// Five rows, two of which share a name so that the id breaks the tie.
+    fn rows() -> Vec<Row> {
+        ["b", "a", "c", "b", "d"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Row {
+                id: Uuid::from_u128(i as u128 + 1),
+                name: name.to_owned(),
+                count: 10 - i as i64,
+            })
+            .collect()
+    }
+
+    /// Follows `next_cursor` from the first page to the last, collecting the row ids.
+    fn walk(query: ListQuery) -> Vec<u128> {
+        let mut query = query;
+        let mut ids = Vec::new();
+        loop {
+            let page = ROWS.page(rows(), &query).unwrap();
+            assert_eq!(page.total, 5);
+            ids.extend(page.items.iter().map(|r| r.id.as_u128()));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return ids,
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = Cursor {
+            sort: "-created_at".to_owned(),
+            id: Uuid::from_u128(42),
+            value: "line one\nline two, ünïcode".to_owned(),
+        };
+        let encoded = cursor.encode();
+        assert!(encoded.bytes().all(|b| b.is_ascii_hexdigit()));
+
+        let decoded = Cursor::decode(&encoded).unwrap();
+        assert_eq!(decoded.sort, cursor.sort);
+        assert_eq!(decoded.id, cursor.id);
+        assert_eq!(decoded.value, cursor.value);
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let valid = Cursor {
+            sort: "name".to_owned(),
+            id: Uuid::from_u128(1),
+            value: "a".to_owned(),
+        }
+        .encode();
+        let hex = |text: &str| text.bytes().map(|b| format!("{:02x}", b)).collect();
+
+        for cursor in [
+            valid[..valid.len() - 1].to_owned(),
+            format!("zz{}", &valid[2..]),
+            "é".repeat(4),
+            hex("name\nnot-a-uuid\na"),
+            hex("name only"),
+            "ff".to_owned(),
+        ] {
+            assert!(
+                matches!(Cursor::decode(&cursor), Err(KeikoError::Validation(_))),
+                "{} should not decode",
+                cursor
+            );
+        }
+    }
+
+    #[test]
+    fn query_limits_are_validated() {
+        let query = |limit, offset| ListQuery {
+            limit,
+            offset,
+            ..ListQuery::default()
+        };
+        assert!(query(None, None).validate().is_ok());
+        assert!(query(Some(1), Some(0)).validate().is_ok());
+        assert!(query(Some(MAX_PAGE_SIZE), None).validate().is_ok());
+        assert!(query(Some(0), None).validate().is_err());
+        assert!(query(Some(MAX_PAGE_SIZE + 1), None).validate().is_err());
+        assert!(query(None, Some(-1)).validate().is_err());
+
+        let cursor = ListQuery {
+            cursor: Some("nope".to_owned()),
+            ..ListQuery::default()
+        };
+        assert!(cursor.validate().is_err());
+    }
+
+    #[test]
+    fn cursors_visit_every_row_once_in_order() {
+        for limit in 1..=5 {
+            let query = ListQuery {
+                limit: Some(limit),
+                ..ListQuery::default()
+            };
+            assert_eq!(walk(query), vec![2, 1, 4, 3, 5]);
+
+            let query = ListQuery {
+                limit: Some(limit),
+                sort: Some("-count".to_owned()),
+                ..ListQuery::default()
+            };
+            assert_eq!(walk(query), vec![1, 2, 3, 4, 5]);
+        }
+    }
+
+    #[test]
+    fn offset_and_filters_narrow_the_page() {
+        let query = ListQuery {
+            limit: Some(2),
+            offset: Some(3),
+            ..ListQuery::default()
+        };
+        let page = ROWS.page(rows(), &query).unwrap();
+        assert_eq!(
+            page.items
+                .iter()
+                .map(|r| r.id.as_u128())
+                .collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+        assert_eq!(page.next_cursor, None);
+
+        let query = ListQuery {
+            category: Some("b".to_owned()),
+            ..ListQuery::default()
+        };
+        let page = ROWS.page(rows(), &query).unwrap();
+        assert_eq!(page.total, 2);
+    }
+
+    #[test]
+    fn unsupported_sorts_filters_and_foreign_cursors_are_rejected() {
+        let sorted_by = |sort: &str| ListQuery {
+            sort: Some(sort.to_owned()),
+            ..ListQuery::default()
+        };
+        assert!(ROWS.page(rows(), &sorted_by("id")).is_err());
+
+        let filtered = ListQuery {
+            is_completed: Some(true),
+            ..ListQuery::default()
+        };
+        assert!(ROWS.page(rows(), &filtered).is_err());
+
+        let first = ROWS
+            .page(
+                rows(),
+                &ListQuery {
+                    limit: Some(1),
+                    ..ListQuery::default()
+                },
+            )
+            .unwrap();
+        let query = ListQuery {
+            cursor: first.next_cursor,
+            ..sorted_by("-name")
+        };
+        assert!(ROWS.page(rows(), &query).is_err());
+    }
+}
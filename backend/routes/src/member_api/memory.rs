@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{CourseMember, CourseRef, Invite, MemberAPI, Role};
+use crate::auth_api::User;
+use crate::memory::{InMemoryKeiko, MemberRow};
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl MemberAPI for InMemoryKeiko {
+    async fn get_role(&self, user: &User, course: CourseRef<'_>) -> KeikoResult<Option<Role>> {
+        let state = self.state();
+        let course = match course {
+            CourseRef::Id(id) => state.course(id),
+            CourseRef::Code(code) => state.course_by_code(code),
+            CourseRef::Card(card_id) => state
+                .cards
+                .iter()
+                .chain(state.trashed_cards.iter().map(|t| &t.row))
+                .find(|c| c.id == *card_id)
+                .and_then(|card| state.course(&card.course_id)),
+            CourseRef::Category(category_id) => state
+                .categories
+                .iter()
+                .find(|c| c.id == *category_id)
+                .and_then(|category| state.course(&category.course_id)),
+        };
+
+        Ok(course.and_then(|c| state.course_role(c, user)))
+    }
+
+    async fn get_members(&self, course_id: &Uuid) -> KeikoResult<Vec<CourseMember>> {
+        let state = self.state();
+        let mut members: Vec<CourseMember> = state
+            .courses
+            .iter()
+            .filter(|c| c.id == *course_id)
+            .filter_map(|c| {
+                let owner = state.user(&c.user_id?)?;
+                Some(CourseMember {
+                    course_id: c.id,
+                    user_id: owner.id,
+                    username: owner.username.clone(),
+                    role: Role::Owner,
+                    created_at: c.created_at,
+                })
+            })
+            .collect();
+
+        members.extend(
+            state
+                .members
+                .iter()
+                .filter(|m| m.course_id == *course_id)
+                .filter_map(|m| {
+                    Some(CourseMember {
+                        course_id: m.course_id,
+                        user_id: m.user_id,
+                        username: state.user(&m.user_id)?.username.clone(),
+                        role: m.role,
+                        created_at: m.created_at,
+                    })
+                }),
+        );
+        members.sort_by_key(|m| m.created_at);
+
+        Ok(members)
+    }
+
+    async fn invite(&self, course_id: &Uuid, invite: &Invite) -> KeikoResult<CourseMember> {
+        if invite.role == Role::Owner {
+            return Err(KeikoError::Validation(
+                "a course has a single owner, invite as viewer or editor".to_owned(),
+            ));
+        }
+
+        let mut state = self.state();
+        let owner_id = state
+            .courses
+            .iter()
+            .find(|c| c.id == *course_id)
+            .map(|c| c.user_id)
+            .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let user = state
+            .users
+            .iter()
+            .map(|u| u.user.clone())
+            .find(|u| u.username == invite.username)
+            .ok_or_else(|| KeikoError::not_found("user"))?;
+
+        if owner_id == Some(user.id) {
+            return Err(KeikoError::Validation(
+                "the owner is already a member".to_owned(),
+            ));
+        }
+
+        let created_at = match state
+            .members
+            .iter_mut()
+            .find(|m| m.course_id == *course_id && m.user_id == user.id)
+        {
+            Some(member) => {
+                member.role = invite.role;
+                member.created_at
+            }
+            None => {
+                let created_at = chrono::Utc::now();
+                state.members.push(MemberRow {
+                    course_id: *course_id,
+                    user_id: user.id,
+                    role: invite.role,
+                    created_at,
+                });
+                created_at
+            }
+        };
+
+        Ok(CourseMember {
+            course_id: *course_id,
+            user_id: user.id,
+            username: user.username,
+            role: invite.role,
+            created_at,
+        })
+    }
+
+    async fn revoke(&self, course_id: &Uuid, user_id: &Uuid) -> KeikoResult<()> {
+        let mut state = self.state();
+        let before = state.members.len();
+        state
+            .members
+            .retain(|m| !(m.course_id == *course_id && m.user_id == *user_id));
+
+        if state.members.len() == before {
+            return Err(KeikoError::not_found("member"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+mod memory;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::{KeikoError, KeikoResult};
+
+/// What a user may do in a course. Each role includes the ones before it.
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum Role {
+    /// Can see the course and its cards, and take quizzes on them.
+    Viewer,
+    /// Can also add, edit and delete cards, and edit the course.
+    Editor,
+    /// Can also delete the course and manage its members.
+    Owner,
+}
+
+/// The course a permission check is about, by any of the ways routes refer to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseRef<'a> {
+    Id(&'a Uuid),
+    Code(&'a str),
+    /// The course the card belongs to.
+    Card(&'a Uuid),
+    /// The course the category belongs to.
+    Category(&'a Uuid),
+}
+
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CourseMember {
+    pub course_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: Role,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Invite {
+    pub username: String,
+    /// `viewer` or `editor`, a course has a single owner.
+    pub role: Role,
+}
+
+#[async_trait]
+pub trait MemberAPI: Send + Sync + 'static {
+    /// The user's role in the course, `None` if it is not shared with them or does not exist.
+    async fn get_role(&self, user: &User, course: CourseRef<'_>) -> KeikoResult<Option<Role>>;
+    async fn get_members(&self, course_id: &Uuid) -> KeikoResult<Vec<CourseMember>>;
+    /// Adds the user to the course, or changes the role of an existing member.
+    async fn invite(&self, course_id: &Uuid, invite: &Invite) -> KeikoResult<CourseMember>;
+    async fn revoke(&self, course_id: &Uuid, user_id: &Uuid) -> KeikoResult<()>;
+}
+
+/// Fails unless `user` holds at least `required` in the course. Courses the user is not a
+/// member of are reported as not found, so their existence does not leak.
+pub async fn require<S: MemberAPI>(
+    stack: &S,
+    user: &User,
+    course: CourseRef<'_>,
+    required: Role,
+) -> KeikoResult<Role> {
+    match stack.get_role(user, course).await? {
+        Some(role) if role >= required => Ok(role),
+        Some(_) => Err(KeikoError::Forbidden(format!(
+            "this requires the {} role in the course",
+            required.name()
+        ))),
+        None => Err(KeikoError::not_found(match course {
+            CourseRef::Card(_) => "card",
+            CourseRef::Category(_) => "category",
+            _ => "course",
+        })),
+    }
+}
+
+/// Something that shows the answer of a card, which viewers of its course only get to see by
+/// completing a quiz.
+pub trait Answered {
+    fn course_code(&self) -> &str;
+    /// Blanks the answer and anything giving it away, such as the hint.
+    fn hide_answer(&mut self);
+}
+
+/// Hides the answers of `items` in courses where `user` cannot edit the cards, so a student
+/// cannot read them ahead of a quiz.
+pub async fn hide_answers<S: MemberAPI, T: Answered>(
+    stack: &S,
+    user: &User,
+    items: &mut [T],
+) -> KeikoResult<()> {
+    let mut hidden: HashMap<String, bool> = HashMap::new();
+    for item in items.iter_mut() {
+        let hide = match hidden.get(item.course_code()) {
+            Some(hide) => *hide,
+            None => {
+                let role = stack
+                    .get_role(user, CourseRef::Code(item.course_code()))
+                    .await?;
+                let hide = role < Some(Role::Editor);
+                hidden.insert(item.course_code().to_owned(), hide);
+                hide
+            }
+        };
+        if hide {
+            item.hide_answer();
+        }
+    }
+
+    Ok(())
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{CourseMember, CourseRef, Invite, MemberAPI, Role};
+use crate::auth_api::User;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+#[async_trait]
+impl MemberAPI for KeikoDatabase {
+    async fn get_role(&self, user: &User, course: CourseRef<'_>) -> KeikoResult<Option<Role>> {
+        let query = match course {
+            CourseRef::Id(id) => {
+                sqlx::query_scalar::<_, Option<Role>>("SELECT course_role($1, $2)").bind(*id)
+            }
+            CourseRef::Code(code) => sqlx::query_scalar::<_, Option<Role>>(
+                "SELECT course_role(id, $2) FROM courses WHERE course_code = $1",
+            )
+            .bind(code),
+            CourseRef::Card(card_id) => sqlx::query_scalar::<_, Option<Role>>(
+                r#"
+                SELECT course_role(co.id, $2)
+                FROM cards c
+                JOIN courses co ON co.id = c.course_id
+                WHERE c.id = $1
+                "#,
+            )
+            .bind(*card_id),
+            CourseRef::Category(category_id) => sqlx::query_scalar::<_, Option<Role>>(
+                "SELECT course_role(course_id, $2) FROM categories WHERE id = $1",
+            )
+            .bind(*category_id),
+        };
+
+        Ok(query
+            .bind(user.id)
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten())
+    }
+
+    /// GET /v1/courses/id/{course_id}/members
+    async fn get_members(&self, course_id: &Uuid) -> KeikoResult<Vec<CourseMember>> {
+        sqlx::query_as::<_, CourseMember>(
+            r#"
+            SELECT c.id AS course_id, u.id AS user_id, u.username, 'owner' AS role, c.created_at
+            FROM courses c
+            JOIN users u ON u.id = c.user_id
+            WHERE c.id = $1
+            UNION ALL
+            SELECT m.course_id, m.user_id, u.username, m.role, m.created_at
+            FROM course_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.course_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(course_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    /// POST /v1/courses/id/{course_id}/members
+    async fn invite(&self, course_id: &Uuid, invite: &Invite) -> KeikoResult<CourseMember> {
+        if invite.role == Role::Owner {
+            return Err(KeikoError::Validation(
+                "a course has a single owner, invite as viewer or editor".to_owned(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let owner_id =
+            sqlx::query_scalar::<_, Option<Uuid>>("SELECT user_id FROM courses WHERE id = $1")
+                .bind(course_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let user_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+            .bind(&invite.username)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("user"))?;
+
+        if owner_id == Some(user_id) {
+            return Err(KeikoError::Validation(
+                "the owner is already a member".to_owned(),
+            ));
+        }
+
+        let member = sqlx::query_as::<_, CourseMember>(
+            r#"
+            WITH m AS (
+                INSERT INTO course_members (course_id, user_id, role)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (course_id, user_id) DO UPDATE SET role = EXCLUDED.role
+                RETURNING *
+            )
+            SELECT m.course_id, m.user_id, u.username, m.role, m.created_at
+            FROM m
+            JOIN users u ON u.id = m.user_id
+            "#,
+        )
+        .bind(course_id)
+        .bind(user_id)
+        .bind(invite.role)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(member)
+    }
+
+    /// DELETE /v1/courses/id/{course_id}/members/{user_id}
+    async fn revoke(&self, course_id: &Uuid, user_id: &Uuid) -> KeikoResult<()> {
+        let result =
+            sqlx::query("DELETE FROM course_members WHERE course_id = $1 AND user_id = $2")
+                .bind(course_id)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(KeikoError::not_found("member"));
+        }
+
+        Ok(())
+    }
+}
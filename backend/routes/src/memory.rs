@@ -0,0 +1,331 @@
+//! State of `InMemoryKeiko`, and the visibility rules the Postgres views and `access` apply,
+//! over plain vectors. Trashed rows are moved to vectors of their own rather than tombstoned,
+//! so nothing reading the live ones has to skip them.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use uuid::Uuid;
+
+use crate::auth_api::{ApiToken, User};
+use crate::card_api::revision::CardRevision;
+use crate::card_api::Card;
+use crate::category_api::Category;
+use crate::course_api::Course;
+use crate::member_api::Role;
+use crate::quiz_api::hint::HintUsage;
+use crate::quiz_api::{Quiz, QuizAnswer};
+use crate::{KeikoError, KeikoResult};
+
+/// A backend that keeps everything in memory, for tests and demos without a database. Nothing
+/// survives a restart. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct InMemoryKeiko {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryKeiko {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A quiz with the columns of `quizzes` that `Quiz` leaves out.
+#[derive(Debug, Clone)]
+pub(crate) struct QuizRow {
+    pub quiz: Quiz,
+    pub seed: i64,
+    pub distractor_count: i32,
+    pub hint_budget: i32,
+    pub hint_penalty: f64,
+}
+
+/// A row in the trash, and when it was put there.
+#[derive(Debug, Clone)]
+pub(crate) struct Trashed<T> {
+    pub row: T,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MemberRow {
+    pub course_id: Uuid,
+    pub user_id: Uuid,
+    pub role: Role,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UserRow {
+    pub user: User,
+    pub password_hash: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SessionRow {
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ApiTokenRow {
+    pub api_token: ApiToken,
+    pub user_id: Uuid,
+    pub token_hash: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    pub users: Vec<UserRow>,
+    pub sessions: Vec<SessionRow>,
+    pub api_tokens: Vec<ApiTokenRow>,
+    pub courses: Vec<Course>,
+    pub categories: Vec<Category>,
+    pub cards: Vec<Card>,
+    /// Their `username` is left empty and looked up when read.
+    pub card_revisions: Vec<CardRevision>,
+    pub quizzes: Vec<QuizRow>,
+    pub answers: Vec<QuizAnswer>,
+    pub hints: Vec<HintUsage>,
+    pub members: Vec<MemberRow>,
+    /// The cards and quizzes of a trashed course stay in `cards` and `quizzes`, and are hidden
+    /// by the course being gone from `courses`.
+    pub trashed_courses: Vec<Trashed<Course>>,
+    pub trashed_cards: Vec<Trashed<Card>>,
+    pub trashed_quizzes: Vec<Trashed<QuizRow>>,
+}
+
+impl State {
+    pub fn user(&self, user_id: &Uuid) -> Option<&User> {
+        self.users
+            .iter()
+            .map(|u| &u.user)
+            .find(|u| u.id == *user_id)
+    }
+
+    /// The user's role in a course, as `course_role()` works it out.
+    pub fn course_role(&self, course: &Course, user: &User) -> Option<Role> {
+        if course.user_id == Some(user.id) {
+            return Some(Role::Owner);
+        }
+
+        self.members
+            .iter()
+            .find(|m| m.course_id == course.id && m.user_id == user.id)
+            .map(|m| m.role)
+    }
+
+    pub fn course(&self, course_id: &Uuid) -> Option<&Course> {
+        self.courses.iter().find(|c| c.id == *course_id)
+    }
+
+    pub fn course_by_code(&self, course_code: &str) -> Option<&Course> {
+        self.courses.iter().find(|c| c.course_code == course_code)
+    }
+
+    /// Id of the course that has the code, counting trashed ones as the unique index does.
+    pub fn course_code_holder(&self, course_code: &str) -> Option<Uuid> {
+        self.course_by_code(course_code).map(|c| c.id).or_else(|| {
+            self.trashed_courses
+                .iter()
+                .find(|t| t.row.course_code == course_code)
+                .map(|t| t.row.id)
+        })
+    }
+
+    /// Fails with not found unless `user` is a member of the course.
+    pub fn visible_course(&self, user: &User, course_code: &str) -> KeikoResult<&Course> {
+        self.course_by_code(course_code)
+            .filter(|c| self.course_role(c, user).is_some())
+            .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    pub fn card_is_visible(&self, user: &User, card: &Card) -> bool {
+        self.course(&card.course_id)
+            .is_some_and(|c| self.course_role(c, user).is_some())
+    }
+
+    /// Fails with not found unless `user` is a member of the card's course.
+    pub fn visible_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<&Card> {
+        self.cards
+            .iter()
+            .find(|c| c.id == *card_id && self.card_is_visible(user, c))
+            .ok_or_else(|| KeikoError::not_found("card"))
+    }
+
+    /// Fails with not found unless `user` owns the quiz and its course is not in the trash.
+    pub fn visible_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<&QuizRow> {
+        self.quizzes
+            .iter()
+            .find(|q| {
+                q.quiz.id == *quiz_id
+                    && quiz_is_visible(&q.quiz, user)
+                    && self.course(&q.quiz.course_id).is_some()
+            })
+            .ok_or_else(|| KeikoError::not_found("quiz"))
+    }
+
+    /// The categories of a course, in order.
+    pub fn course_categories(&self, course_id: &Uuid) -> Vec<&Category> {
+        let mut categories: Vec<&Category> = self
+            .categories
+            .iter()
+            .filter(|c| c.course_id == *course_id)
+            .collect();
+        categories.sort_by_key(|c| (c.position, c.created_at, c.id));
+        categories
+    }
+
+    /// Adds a category to the end of a course unless it already has one by that name, as
+    /// `ensure_category()` does.
+    pub fn ensure_category(&mut self, course_id: &Uuid, name: &str) {
+        if self
+            .categories
+            .iter()
+            .any(|c| c.course_id == *course_id && c.name == name)
+        {
+            return;
+        }
+
+        let position = self
+            .course_categories(course_id)
+            .last()
+            .map_or(0, |c| c.position + 1);
+        self.categories.push(Category {
+            id: Uuid::new_v4(),
+            course_id: *course_id,
+            name: name.to_owned(),
+            description: String::new(),
+            position,
+            unlock_after: None,
+            created_at: chrono::Utc::now(),
+            updated_at: None,
+        });
+    }
+
+    /// Name of the category `user` must still complete a quiz on before starting one on the
+    /// given category, as `category_lock()` works it out.
+    pub fn category_lock(&self, user: &User, course_id: &Uuid, name: &str) -> Option<&str> {
+        let category = self
+            .categories
+            .iter()
+            .find(|c| c.course_id == *course_id && c.name == name)?;
+        let required = self
+            .categories
+            .iter()
+            .find(|c| Some(c.id) == category.unlock_after)?;
+        let completed = self.quizzes.iter().any(|q| {
+            q.quiz.course_id == required.course_id
+                && q.quiz.category == required.name
+                && q.quiz.is_completed
+                && quiz_is_visible(&q.quiz, user)
+        });
+
+        (!completed).then_some(required.name.as_str())
+    }
+
+    /// Deletes a category along with its cards and quizzes, trashed ones included.
+    pub fn delete_category(&mut self, category_id: &Uuid) -> Option<Category> {
+        let index = self.categories.iter().position(|c| c.id == *category_id)?;
+        let category = self.categories.remove(index);
+        for other in &mut self.categories {
+            if other.unlock_after == Some(category.id) {
+                other.unlock_after = None;
+            }
+        }
+        self.delete_cards(|c| c.course_id == category.course_id && c.category == category.name);
+        self.delete_quizzes(|q| q.course_id == category.course_id && q.category == category.name);
+        Some(category)
+    }
+
+    /// Cards of a category, in the order quizzes go through them.
+    pub fn quiz_cards(&self, course_id: &Uuid, category: &str) -> Vec<&Card> {
+        let mut cards: Vec<&Card> = self
+            .cards
+            .iter()
+            .filter(|c| c.course_id == *course_id && c.category == category)
+            .collect();
+        cards.sort_by_key(|c| (c.created_at, c.id));
+        cards
+    }
+
+    /// Records what `card` says now as its next revision, by `user`, as `add_card_revision()`
+    /// does.
+    pub fn add_card_revision(&mut self, card: &Card, user: &User) {
+        let revision = self
+            .card_revisions
+            .iter()
+            .filter(|r| r.card_id == card.id)
+            .map(|r| r.revision)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.card_revisions.push(CardRevision {
+            card_id: card.id,
+            revision,
+            question: card.question.clone(),
+            answer: card.answer.clone(),
+            category: card.category.clone(),
+            hint: card.hint.clone(),
+            user_id: Some(user.id),
+            username: None,
+            created_at: chrono::Utc::now(),
+        });
+    }
+
+    /// Deletes cards for good, trashed ones included, along with their revisions and the
+    /// answers and hints given for them.
+    pub fn delete_cards(&mut self, delete: impl Fn(&Card) -> bool) {
+        let ids: Vec<Uuid> = self
+            .cards
+            .iter()
+            .chain(self.trashed_cards.iter().map(|t| &t.row))
+            .filter(|c| delete(c))
+            .map(|c| c.id)
+            .collect();
+        self.cards.retain(|c| !ids.contains(&c.id));
+        self.trashed_cards.retain(|t| !ids.contains(&t.row.id));
+        self.card_revisions.retain(|r| !ids.contains(&r.card_id));
+        self.answers.retain(|a| !ids.contains(&a.card_id));
+        self.hints.retain(|h| !ids.contains(&h.card_id));
+    }
+
+    /// Deletes quizzes for good, trashed ones included, along with their answers and hints.
+    pub fn delete_quizzes(&mut self, delete: impl Fn(&Quiz) -> bool) {
+        let ids: Vec<Uuid> = self
+            .quizzes
+            .iter()
+            .chain(self.trashed_quizzes.iter().map(|t| &t.row))
+            .filter(|q| delete(&q.quiz))
+            .map(|q| q.quiz.id)
+            .collect();
+        self.quizzes.retain(|q| !ids.contains(&q.quiz.id));
+        self.trashed_quizzes
+            .retain(|t| !ids.contains(&t.row.quiz.id));
+        self.answers.retain(|a| !ids.contains(&a.quiz_id));
+        self.hints.retain(|h| !ids.contains(&h.quiz_id));
+    }
+
+    /// Deletes a course for good, trashed or not, along with everything in it.
+    pub fn delete_course(&mut self, course_id: &Uuid) {
+        self.delete_quizzes(|q| q.course_id == *course_id);
+        self.delete_cards(|c| c.course_id == *course_id);
+        self.categories.retain(|c| c.course_id != *course_id);
+        self.courses.retain(|c| c.id != *course_id);
+        self.trashed_courses.retain(|t| t.row.id != *course_id);
+        self.members.retain(|m| m.course_id != *course_id);
+    }
+}
+
+pub(crate) fn quiz_is_visible(quiz: &Quiz, user: &User) -> bool {
+    quiz.user_id == Some(user.id)
+}
+
+/// `ROUND()` of a double precision value, which Postgres breaks ties for towards even.
+pub(crate) fn round(value: f64) -> i32 {
+    value.round_ties_even() as i32
+}
@@ -0,0 +1,213 @@
+//! Prometheus metrics: HTTP traffic recorded by the `RequestMetrics` middleware, connection
+//! pool gauges read when scraped, and domain counters the backends bump as things happen.
+
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use ntex::http::header::CONTENT_TYPE;
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{self, types::State, DefaultError, HttpRequest, HttpResponse, ServiceConfig};
+use ntex::web::{WebRequest, WebResponse};
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+    TEXT_FORMAT,
+};
+
+use crate::health_api::HealthAPI;
+use crate::quiz_api::Quiz;
+
+/// Route label of requests that matched no route, so stray paths do not each get a series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub pool_connections: IntGauge,
+    pub pool_idle: IntGauge,
+    pub pool_in_use: IntGauge,
+    pub pool_max_connections: IntGauge,
+    /// sqlx keeps its queue of waiting requests to itself, so this counts the ones that gave
+    /// up waiting instead.
+    pub pool_acquire_timeouts: IntCounter,
+    pub quizzes_started: IntCounter,
+    pub quizzes_completed: IntCounter,
+    pub cards_created: IntCounter,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("keiko".to_owned()), None).expect("metric prefix is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests answered"),
+            &["method", "route", "status"],
+        )
+        .expect("metric is valid");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to answer HTTP requests",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("metric is valid");
+
+        let gauge = |name: &str, help: &str| IntGauge::new(name, help).expect("metric is valid");
+        let counter =
+            |name: &str, help: &str| IntCounter::new(name, help).expect("metric is valid");
+
+        let metrics = Self {
+            http_requests,
+            http_request_duration,
+            pool_connections: gauge("db_pool_connections", "Open database connections"),
+            pool_idle: gauge("db_pool_idle", "Open database connections not in use"),
+            pool_in_use: gauge("db_pool_in_use", "Database connections in use"),
+            pool_max_connections: gauge(
+                "db_pool_max_connections",
+                "Most database connections the pool opens",
+            ),
+            pool_acquire_timeouts: counter(
+                "db_pool_acquire_timeouts_total",
+                "Requests that gave up waiting for a database connection",
+            ),
+            quizzes_started: counter("quizzes_started_total", "Quizzes created"),
+            quizzes_completed: counter(
+                "quizzes_completed_total",
+                "Quizzes finished by answering their last question or marked completed",
+            ),
+            cards_created: counter("cards_created_total", "Cards created, including imports"),
+            registry,
+        };
+
+        metrics.register();
+        metrics
+    }
+
+    fn register(&self) {
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(self.http_requests.clone()),
+            Box::new(self.http_request_duration.clone()),
+            Box::new(self.pool_connections.clone()),
+            Box::new(self.pool_idle.clone()),
+            Box::new(self.pool_in_use.clone()),
+            Box::new(self.pool_max_connections.clone()),
+            Box::new(self.pool_acquire_timeouts.clone()),
+            Box::new(self.quizzes_started.clone()),
+            Box::new(self.quizzes_completed.clone()),
+            Box::new(self.cards_created.clone()),
+        ];
+
+        for collector in collectors {
+            self.registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+    }
+
+    /// Counts a completed quiz, given a quiz that was just saved.
+    pub fn count_completion(&self, quiz: &Quiz) {
+        if quiz.is_completed {
+            self.quizzes_completed.inc();
+        }
+    }
+
+    /// Everything in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+}
+
+pub fn service<S: HealthAPI>(cfg: &mut ServiceConfig) {
+    cfg.route("/metrics", web::get().to(metrics::<S>));
+}
+
+/// GET /metrics
+async fn metrics<S: HealthAPI>(stack: State<S>) -> HttpResponse {
+    if let Some(pool) = stack.pool_status() {
+        METRICS.pool_connections.set(pool.size.into());
+        METRICS.pool_idle.set(pool.idle.into());
+        METRICS.pool_in_use.set(pool.in_use.into());
+        METRICS
+            .pool_max_connections
+            .set(pool.max_connections.into());
+    }
+
+    HttpResponse::Ok()
+        .set_header(CONTENT_TYPE, TEXT_FORMAT)
+        .body(METRICS.encode())
+}
+
+/// The route a request matched, with its parameters in place of their values, such as
+/// `/api/v1/cards/id/{card_id}`.
+fn route(req: &HttpRequest) -> String {
+    // Routing consumes the path as it matches, leaving nothing once a route has matched.
+    let params = req.match_info();
+    if !params.path().is_empty() {
+        return UNMATCHED_ROUTE.to_owned();
+    }
+
+    req.path()
+        .split('/')
+        .map(|segment| {
+            params
+                .iter()
+                .find(|(_, value)| *value == segment)
+                .map_or_else(|| segment.to_owned(), |(name, _)| format!("{{{}}}", name))
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Middleware that counts requests and times them, by method, route and status.
+pub struct RequestMetrics;
+
+impl<Svc> Middleware<Svc> for RequestMetrics {
+    type Service = RequestMetricsMiddleware<Svc>;
+
+    fn create(&self, service: Svc) -> Self::Service {
+        RequestMetricsMiddleware { service }
+    }
+}
+
+pub struct RequestMetricsMiddleware<Svc> {
+    service: Svc,
+}
+
+impl<Svc> Service<WebRequest<DefaultError>> for RequestMetricsMiddleware<Svc>
+where
+    Svc: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = Svc::Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let method = req.method().clone();
+
+        let res = ctx.call(&self.service, req).await?;
+
+        let route = route(res.request());
+        let status = res.status();
+        let labels = [method.as_str(), &route, status.as_str()];
+        METRICS.http_requests.with_label_values(&labels).inc();
+        METRICS
+            .http_request_duration
+            .with_label_values(&labels)
+            .observe(start.elapsed().as_secs_f64());
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,139 @@
+use serde::Serialize;
+use sqlx::migrate::{AppliedMigration, Migrate, MigrateError, Migrator};
+
+use crate::{KeikoError, KeikoResult};
+
+/// Versioned schema migrations embedded from `routes/migrations`.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file has been edited since.
+    ChecksumMismatch,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+impl From<MigrateError> for KeikoError {
+    fn from(e: MigrateError) -> Self {
+        match e {
+            MigrateError::Execute(e) => e.into(),
+            e => Self::Database(e.to_string()),
+        }
+    }
+}
+
+/// Applies every pending migration.
+pub async fn up(pool: &sqlx::PgPool) -> KeikoResult<()> {
+    MIGRATOR.run(pool).await.map_err(KeikoError::from)
+}
+
+/// Reverts the latest applied migration, returning its version.
+pub async fn down(pool: &sqlx::PgPool) -> KeikoResult<Option<i64>> {
+    let mut applied = applied_versions(pool).await?;
+    let Some(latest) = applied.pop() else {
+        return Ok(None);
+    };
+    let target = applied.pop().unwrap_or(0);
+
+    MIGRATOR.undo(pool, target).await?;
+
+    Ok(Some(latest))
+}
+
+/// Lists every known migration alongside whether it has been applied.
+pub async fn status(pool: &sqlx::PgPool) -> KeikoResult<Vec<MigrationStatus>> {
+    let mut conn = pool.acquire().await?;
+    migrator_status(&MIGRATOR, &mut *conn).await
+}
+
+/// `status` of any migrator, such as the SQLite one.
+pub(crate) async fn migrator_status(
+    migrator: &Migrator,
+    conn: &mut impl Migrate,
+) -> KeikoResult<Vec<MigrationStatus>> {
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+
+    Ok(compare(migrator, &applied))
+}
+
+/// Matches the migrations of `migrator` against those `applied` to a database.
+pub(crate) fn compare(migrator: &Migrator, applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    migrator
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| {
+            let state = match applied.iter().find(|a| a.version == m.version) {
+                Some(a) if a.checksum == m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::ChecksumMismatch,
+                None => MigrationState::Pending,
+            };
+
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                state,
+            }
+        })
+        .collect()
+}
+
+async fn applied_versions(pool: &sqlx::PgPool) -> KeikoResult<Vec<i64>> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    let mut versions: Vec<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect();
+    versions.sort_unstable();
+
+    Ok(versions)
+}
+
+/// Checks that every migration of `migrator` has both an up and a down script, and that they
+/// are numbered from 1 without gaps. Only the files are looked at: whether each down undoes
+/// its up is for a round trip on a database, as `sqlite::tests` does.
+#[cfg(test)]
+pub(crate) fn assert_paired(migrator: &Migrator) {
+    use sqlx::migrate::MigrationType;
+
+    let versions = |kind: MigrationType| -> Vec<i64> {
+        migrator
+            .iter()
+            .filter(|m| m.migration_type == kind)
+            .map(|m| m.version)
+            .collect()
+    };
+    let up = versions(MigrationType::ReversibleUp);
+    let down = versions(MigrationType::ReversibleDown);
+
+    assert_eq!(
+        up.len(),
+        migrator.iter().len() / 2,
+        "some migrations have no down"
+    );
+    assert_eq!(up, (1..=up.len() as i64).collect::<Vec<_>>());
+    assert_eq!(down, up);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_come_in_numbered_pairs() {
+        assert_paired(&MIGRATOR);
+    }
+}
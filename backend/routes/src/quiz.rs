@@ -1,13 +1,12 @@
-use ntex::web::{
-    self,
-    types::{Json, Path, State},
-    HttpResponse, ServiceConfig,
-};
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
 use uuid::Uuid;
 
-use crate::quiz_api::{
-    CreateQuiz, Quiz, QuizAPI, QuizCompletion, QuizCorrectCount, QuizHint, QuizIndex, RenameQuiz,
-};
+use crate::auth_api::User;
+use crate::extract::{Json, Path};
+use crate::list::ListQuery;
+use crate::quiz_api::hint::RequestHint;
+use crate::quiz_api::{CreateQuiz, GradeAnswer, QuizAPI};
+use crate::KeikoResult;
 
 pub fn service<S: QuizAPI>(cfg: &mut ServiceConfig) {
     cfg.service(
@@ -17,133 +16,313 @@ pub fn service<S: QuizAPI>(cfg: &mut ServiceConfig) {
             .route("/ongoing", web::get().to(get_ongoing_quizzes::<S>))
             .route("/completed", web::get().to(get_completed_quizzes::<S>))
             .route("", web::post().to(create_quiz::<S>))
-            .route("", web::put().to(update_quiz::<S>))
             .route("/id/{quiz_id}", web::delete().to(delete_quiz::<S>))
-            .route("", web::patch().to(set_quiz_completion::<S>))
-            .route(
-                "/id/{quiz_id}/index",
-                web::patch().to(set_current_index::<S>),
-            )
-            .route(
-                "/id/{quiz_id}/correct",
-                web::patch().to(set_correct_count::<S>),
-            )
-            .route("/id/{quiz_id}/hint", web::patch().to(set_hint_used::<S>))
-            .route("/rename/{course_code}", web::post().to(rename_quiz::<S>)),
+            .route("/id/{quiz_id}/retake", web::post().to(retake_quiz::<S>))
+            .route("/id/{quiz_id}/attempts", web::get().to(get_attempts::<S>))
+            .route("/id/{quiz_id}/answers", web::get().to(get_answers::<S>))
+            .route("/id/{quiz_id}/question", web::get().to(get_question::<S>))
+            .route("/id/{quiz_id}/grade", web::post().to(grade_answer::<S>))
+            .route("/id/{quiz_id}/hints", web::get().to(get_hints::<S>))
+            .route("/id/{quiz_id}/hints", web::post().to(use_hint::<S>)),
     );
 }
 
 /// GET /v1/quiz
-async fn get_quizzes<S: QuizAPI>(stack: State<S>) -> HttpResponse {
-    match stack.get_quizzes().await {
-        Ok(quizzes) => HttpResponse::Ok().json(&quizzes),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn get_quizzes<S: QuizAPI>(
+    query: ListQuery,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let quizzes = stack.get_quizzes(&user, &query).await?;
+    Ok(quizzes.respond())
 }
 
 /// GET /v1/quiz/id/{quiz_id}
-async fn get_quiz<S: QuizAPI>(quiz_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.get_quiz(&quiz_id).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Quiz not found: {:?}", e)),
-    }
+async fn get_quiz<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let quiz = stack.get_quiz(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&quiz))
 }
 
 /// GET /v1/quiz/ongoing
-async fn get_ongoing_quizzes<S: QuizAPI>(stack: State<S>) -> HttpResponse {
-    match stack.get_ongoing_quizzes().await {
-        Ok(quizzes) => HttpResponse::Ok().json(&quizzes),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn get_ongoing_quizzes<S: QuizAPI>(
+    query: ListQuery,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let query = ListQuery {
+        is_completed: Some(false),
+        ..query
+    };
+    let quizzes = stack.get_quizzes(&user, &query).await?;
+    Ok(quizzes.respond())
 }
 
 /// GET /v1/quiz/completed
-async fn get_completed_quizzes<S: QuizAPI>(stack: State<S>) -> HttpResponse {
-    match stack.get_completed_quizzes().await {
-        Ok(quizzes) => HttpResponse::Ok().json(&quizzes),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn get_completed_quizzes<S: QuizAPI>(
+    query: ListQuery,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let query = ListQuery {
+        is_completed: Some(true),
+        ..query
+    };
+    let quizzes = stack.get_quizzes(&user, &query).await?;
+    Ok(quizzes.respond())
 }
 
 /// POST /v1/quiz
-async fn create_quiz<S: QuizAPI>(create_quiz: Json<CreateQuiz>, stack: State<S>) -> HttpResponse {
-    match stack.create_quiz(&create_quiz).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+async fn create_quiz<S: QuizAPI>(
+    create_quiz: Json<CreateQuiz>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let quiz = stack.create_quiz(&user, &create_quiz).await?;
+    Ok(HttpResponse::Ok().json(&quiz))
 }
 
-/// PUT /v1/quiz
-async fn update_quiz<S: QuizAPI>(quiz: Json<Quiz>, stack: State<S>) -> HttpResponse {
-    match stack.update_quiz(&quiz).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+/// DELETE /v1/quiz/id/{quiz_id}
+async fn delete_quiz<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    stack.delete_quiz(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().finish())
 }
 
-/// DELETE /v1/quiz/id/{quiz_id}
-async fn delete_quiz<S: QuizAPI>(quiz_id: Path<Uuid>, stack: State<S>) -> HttpResponse {
-    match stack.delete_quiz(&quiz_id).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => HttpResponse::NotFound().body(format!("Quiz not found: {:?}", e)),
-    }
+/// POST /v1/quiz/id/{quiz_id}/retake
+async fn retake_quiz<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let quiz = stack.retake_quiz(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&quiz))
 }
 
-/// PATCH /v1/quiz
-async fn set_quiz_completion<S: QuizAPI>(
-    quiz_completion: Json<QuizCompletion>,
+/// GET /v1/quiz/id/{quiz_id}/attempts
+async fn get_attempts<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.set_quiz_completion(&quiz_completion).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+) -> KeikoResult<HttpResponse> {
+    let attempts = stack.get_attempts(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&attempts))
 }
 
-/// PATCH /v1/quiz/id/{quiz_id}/index
-async fn set_current_index<S: QuizAPI>(
+/// GET /v1/quiz/id/{quiz_id}/answers
+async fn get_answers<S: QuizAPI>(
     quiz_id: Path<Uuid>,
-    quiz_index: Json<QuizIndex>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.set_current_index(&quiz_id, &quiz_index).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+) -> KeikoResult<HttpResponse> {
+    let answers = stack.get_answers(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&answers))
 }
 
-/// PATCH /v1/quiz/id/{quiz_id}/correct
-async fn set_correct_count<S: QuizAPI>(
+/// GET /v1/quiz/id/{quiz_id}/question
+async fn get_question<S: QuizAPI>(
     quiz_id: Path<Uuid>,
-    quiz_correct: Json<QuizCorrectCount>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.set_correct_count(&quiz_id, &quiz_correct).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+) -> KeikoResult<HttpResponse> {
+    let question = stack.get_question(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&question))
 }
 
-/// PATCH /v1/quiz/id/{quiz_id}/hint
-async fn set_hint_used<S: QuizAPI>(
+/// POST /v1/quiz/id/{quiz_id}/grade
+async fn grade_answer<S: QuizAPI>(
     quiz_id: Path<Uuid>,
-    quiz_hint: Json<QuizHint>,
+    answer: Json<GradeAnswer>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.set_hint_used(&quiz_id, &quiz_hint).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
-    }
+) -> KeikoResult<HttpResponse> {
+    let graded = stack.grade_answer(&user, &quiz_id, &answer).await?;
+    Ok(HttpResponse::Ok().json(&graded))
 }
 
-/// POST /v1/quiz/rename/{course_code}
-async fn rename_quiz<S: QuizAPI>(
-    course_code: Path<String>,
-    rename_quiz: Json<RenameQuiz>,
+/// POST /v1/quiz/id/{quiz_id}/hints
+async fn use_hint<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    hint: Json<RequestHint>,
+    user: User,
     stack: State<S>,
-) -> HttpResponse {
-    match stack.rename_quiz(&course_code, &rename_quiz).await {
-        Ok(quiz) => HttpResponse::Ok().json(&quiz),
-        Err(e) => HttpResponse::NotFound().body(format!("Internal server error: {:?}", e)),
+) -> KeikoResult<HttpResponse> {
+    let hint = stack.use_hint(&user, &quiz_id, &hint).await?;
+    Ok(HttpResponse::Ok().json(&hint))
+}
+
+/// GET /v1/quiz/id/{quiz_id}/hints
+async fn get_hints<S: QuizAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let hints = stack.get_hints(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&hints))
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::testing;
+
+    #[ntex::test]
+    async fn quizzes_hide_their_cards_until_completed() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        for n in 1..=4 {
+            app.card(&alice, "C1", "Basics", n).await;
+        }
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (status, quiz) = app.post("/v1/quiz", &alice, create).await;
+        assert_eq!(status, StatusCode::OK);
+        let quiz_id = quiz["id"].as_str().unwrap();
+        let cards = format!("/v1/cards/quiz/{quiz_id}");
+        let (status, _) = app.get(&cards, &alice).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let mut graded = json!(null);
+        for _ in 0..4 {
+            let (status, question) = app
+                .get(&format!("/v1/quiz/id/{quiz_id}/question"), &alice)
+                .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(question["options"].as_array().unwrap().len(), 4);
+            let answer = question["question"]
+                .as_str()
+                .unwrap()
+                .replace("Question", "Answer");
+            assert!(question["options"]
+                .as_array()
+                .unwrap()
+                .contains(&json!(answer)));
+
+            let grade = json!({
+                "card_id": question["card_id"],
+                "given_answer": answer,
+                "elapsed_ms": 1000,
+            });
+            let status;
+            (status, graded) = app
+                .post(&format!("/v1/quiz/id/{quiz_id}/grade"), &alice, grade)
+                .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(graded["correct"], true);
+        }
+        assert_eq!(graded["quiz"]["is_completed"], true);
+
+        let (status, cards) = app.get(&cards, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cards.as_array().unwrap().len(), 4);
+    }
+
+    #[ntex::test]
+    async fn answers_must_be_for_the_current_question() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        let first = app.card(&alice, "C1", "Basics", 1).await;
+        let second = app.card(&alice, "C1", "Basics", 2).await;
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (_, quiz) = app.post("/v1/quiz", &alice, create).await;
+        let quiz_id = quiz["id"].as_str().unwrap();
+        let (_, question) = app
+            .get(&format!("/v1/quiz/id/{quiz_id}/question"), &alice)
+            .await;
+        let other = if question["card_id"] == first["id"] {
+            &second
+        } else {
+            &first
+        };
+
+        let grade = json!({ "card_id": other["id"], "given_answer": "x", "elapsed_ms": 10 });
+        let (status, _) = app
+            .post(&format!("/v1/quiz/id/{quiz_id}/grade"), &alice, grade)
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[ntex::test]
+    async fn hints_are_only_counted_from_the_log() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        app.card(&alice, "C1", "Basics", 1).await;
+        app.card(&alice, "C1", "Basics", 2).await;
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (_, quiz) = app.post("/v1/quiz", &alice, create).await;
+        let quiz_id = quiz["id"].as_str().unwrap();
+        let (status, _) = app
+            .send(
+                Method::PATCH,
+                &format!("/v1/quiz/id/{quiz_id}/hint"),
+                Some(&alice),
+                json!({ "hint_used": true }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        for hinted in [false, true] {
+            let (_, question) = app
+                .get(&format!("/v1/quiz/id/{quiz_id}/question"), &alice)
+                .await;
+            if hinted {
+                let hint = json!({ "kind": "first_letter" });
+                let (status, _) = app
+                    .post(&format!("/v1/quiz/id/{quiz_id}/hints"), &alice, hint)
+                    .await;
+                assert_eq!(status, StatusCode::OK);
+            }
+            let grade = json!({
+                "card_id": question["card_id"],
+                "given_answer": "x",
+                "hint_used": !hinted,
+                "elapsed_ms": 10,
+            });
+            let (status, _) = app
+                .post(&format!("/v1/quiz/id/{quiz_id}/grade"), &alice, grade)
+                .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let (_, answers) = app
+            .get(&format!("/v1/quiz/id/{quiz_id}/answers"), &alice)
+            .await;
+        let hinted: Vec<_> = answers
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["hint_used"].as_bool().unwrap())
+            .collect();
+        assert_eq!(hinted, [false, true]);
+    }
+
+    #[ntex::test]
+    async fn quizzes_belong_to_whoever_started_them() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        app.course(&alice, "C1").await;
+        app.card(&alice, "C1", "Basics", 1).await;
+
+        let create = json!({ "course_code": "C1", "category": "Basics" });
+        let (_, quiz) = app.post("/v1/quiz", &alice, create).await;
+        let path = format!("/v1/quiz/id/{}", quiz["id"].as_str().unwrap());
+        let (status, _) = app.get(&path, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get(&path, &bob).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
     }
 }
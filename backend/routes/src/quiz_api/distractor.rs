@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use sqlx::FromRow;
+use uuid::Uuid;
+
+pub const DEFAULT_DISTRACTOR_COUNT: i32 = 3;
+
+/// Only the best `count * POOL_FACTOR` candidates are eligible, so the seed varies which of
+/// the closest answers appear without reaching for unrelated ones.
+const POOL_FACTOR: usize = 2;
+
+/// A candidate wrong answer and whether it comes from the card's own category.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub answer: String,
+    pub same_category: bool,
+}
+
+/// SplitMix64, chosen over an external RNG so options stay stable across dependency upgrades.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+fn bigrams(s: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = s.to_lowercase().chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Sørensen–Dice coefficient over character bigrams.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let mut remaining = b.clone();
+    let shared = a
+        .iter()
+        .filter(|x| {
+            remaining
+                .iter()
+                .position(|y| y == *x)
+                .map(|i| remaining.swap_remove(i))
+                .is_some()
+        })
+        .count();
+
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+fn length_closeness(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.chars().count() as f64, b.chars().count() as f64);
+    if a.max(b) == 0.0 {
+        return 1.0;
+    }
+
+    1.0 - (a - b).abs() / a.max(b)
+}
+
+fn score(correct: &str, candidate: &str) -> f64 {
+    0.7 * similarity(correct, candidate) + 0.3 * length_closeness(correct, candidate)
+}
+
+/// Per-card seed, so every card of a quiz gets its own but reproducible arrangement.
+pub fn card_seed(quiz_seed: i64, card_id: &Uuid) -> u64 {
+    let (hi, lo) = card_id.as_u64_pair();
+    quiz_seed as u64 ^ hi ^ lo.rotate_left(32)
+}
+
+/// Builds the answer options for a card: up to `count` distractors plus the correct answer,
+/// in an order determined by `seed`.
+///
+/// Candidates from the same category rank ahead of the rest of the course; within each group
+/// they are ranked by similarity to the correct answer and closeness in length.
+pub fn answer_options(
+    correct: &str,
+    candidates: Vec<Candidate>,
+    count: usize,
+    seed: u64,
+) -> Vec<String> {
+    let mut seen = HashSet::from([correct.trim().to_lowercase()]);
+    let mut ranked: Vec<(bool, f64, String)> = Vec::new();
+
+    let mut candidates = candidates;
+    candidates.sort_by_key(|c| !c.same_category);
+
+    for c in candidates {
+        if seen.insert(c.answer.trim().to_lowercase()) {
+            ranked.push((c.same_category, score(correct, &c.answer), c.answer));
+        }
+    }
+
+    ranked.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.total_cmp(&a.1))
+            .then_with(|| a.2.cmp(&b.2))
+    });
+    ranked.truncate(count * POOL_FACTOR);
+
+    let mut rng = SplitMix64(seed);
+    rng.shuffle(&mut ranked);
+    ranked.sort_by_key(|c| !c.0);
+
+    let mut options: Vec<String> = ranked
+        .into_iter()
+        .take(count)
+        .map(|(_, _, answer)| answer)
+        .collect();
+    options.push(correct.to_owned());
+    rng.shuffle(&mut options);
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(same: &[&str], other: &[&str]) -> Vec<Candidate> {
+        let candidate = |same_category| {
+            move |answer: &&str| Candidate {
+                answer: answer.to_string(),
+                same_category,
+            }
+        };
+        same.iter()
+            .map(candidate(true))
+            .chain(other.iter().map(candidate(false)))
+            .collect()
+    }
+
+    fn deck() -> Vec<Candidate> {
+        candidates(
+            &[
+                "Berlin", "Madrid", "Rome", "Lisbon", "Vienna", "Prague", "Parris",
+            ],
+            &["Tokyo", "Ottawa", "Canberra"],
+        )
+    }
+
+    #[test]
+    fn same_seed_gives_same_options() {
+        for seed in [0, 1, 42, u64::MAX] {
+            assert_eq!(
+                answer_options("Paris", deck(), 3, seed),
+                answer_options("Paris", deck(), 3, seed),
+            );
+        }
+
+        let arrangements: HashSet<Vec<String>> = (0..32)
+            .map(|seed| answer_options("Paris", deck(), 3, seed))
+            .collect();
+        assert!(arrangements.len() > 1);
+    }
+
+    #[test]
+    fn correct_answer_is_offered_once_and_never_as_a_distractor() {
+        let mut deck = deck();
+        deck.extend(candidates(&["Paris", " paris ", "PARIS"], &["Paris"]));
+
+        for seed in 0..64 {
+            let options = answer_options("Paris", deck.clone(), 3, seed);
+            assert_eq!(options.len(), 4);
+            assert_eq!(
+                options
+                    .iter()
+                    .filter(|o| o.trim().eq_ignore_ascii_case("paris"))
+                    .count(),
+                1
+            );
+            let distinct: HashSet<String> = options.iter().map(|o| o.to_lowercase()).collect();
+            assert_eq!(distinct.len(), options.len());
+        }
+    }
+
+    #[test]
+    fn small_decks_offer_what_they_have() {
+        assert_eq!(answer_options("Paris", Vec::new(), 3, 7), vec!["Paris"]);
+
+        let options = answer_options("Paris", candidates(&["Rome", "rome"], &[]), 3, 7);
+        assert_eq!(options.len(), 2);
+        assert!(options.contains(&"Rome".to_owned()));
+        assert!(options.contains(&"Paris".to_owned()));
+
+        assert_eq!(answer_options("Paris", deck(), 0, 7), vec!["Paris"]);
+    }
+
+    #[test]
+    fn same_category_ranks_ahead_of_the_course() {
+        for seed in 0..64 {
+            let options = answer_options("Paris", deck(), 3, seed);
+            assert!(options
+                .iter()
+                .all(|o| !["Tokyo", "Ottawa", "Canberra"].contains(&o.as_str())));
+        }
+
+        let options = answer_options("Paris", candidates(&["Rome"], &["Tokyo", "Ottawa"]), 3, 7);
+        assert_eq!(options.len(), 4);
+    }
+
+    #[test]
+    fn closer_answers_are_preferred() {
+        assert!(score("Paris", "Parris") > score("Paris", "Canberra"));
+        assert_eq!(similarity("night", "night"), 1.0);
+        assert_eq!(similarity("a", "b"), 0.0);
+        assert_eq!(length_closeness("", ""), 1.0);
+    }
+
+    #[test]
+    fn card_seeds_differ_per_card() {
+        let (a, b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        assert_eq!(card_seed(5, &a), card_seed(5, &a));
+        assert_ne!(card_seed(5, &a), card_seed(5, &b));
+        assert_ne!(card_seed(5, &a), card_seed(6, &a));
+    }
+}
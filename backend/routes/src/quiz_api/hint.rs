@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::distractor::card_seed;
+use crate::card_api::Card;
+use crate::{KeikoError, KeikoResult};
+
+pub const DEFAULT_HINT_BUDGET: i32 = 3;
+pub const DEFAULT_HINT_PENALTY: f64 = 0.5;
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum HintKind {
+    /// Removes one wrong answer from the options.
+    EliminateOption,
+    /// Reveals the first letter of the answer.
+    FirstLetter,
+    /// Shows the hint text written for the card.
+    CardHint,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestHint {
+    pub kind: HintKind,
+}
+
+/// A hint consumed during a quiz.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct HintUsage {
+    pub id: Uuid,
+    pub quiz_id: Uuid,
+    pub card_id: Uuid,
+    pub kind: HintKind,
+    /// The eliminated option, the first letter or the hint text, depending on `kind`.
+    pub revealed: String,
+    pub used_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RevealedHint {
+    #[serde(flatten)]
+    pub usage: HintUsage,
+    pub hints_remaining: i32,
+}
+
+/// Works out what a hint of `kind` reveals for `card`, given the options currently on offer.
+pub fn reveal(kind: HintKind, card: &Card, options: &[String], seed: i64) -> KeikoResult<String> {
+    match kind {
+        HintKind::EliminateOption => {
+            let wrong: Vec<&String> = options.iter().filter(|o| **o != card.answer).collect();
+            if wrong.is_empty() {
+                return Err(KeikoError::Conflict(
+                    "no wrong options left to eliminate".to_owned(),
+                ));
+            }
+
+            let pick = card_seed(seed, &card.id) % wrong.len() as u64;
+            Ok(wrong[pick as usize].clone())
+        }
+        HintKind::FirstLetter => card
+            .answer
+            .trim()
+            .chars()
+            .next()
+            .map(String::from)
+            .ok_or_else(|| KeikoError::Validation("card has an empty answer".to_owned())),
+        HintKind::CardHint => card
+            .hint
+            .clone()
+            .filter(|h| !h.trim().is_empty())
+            .ok_or_else(|| KeikoError::Validation("card has no hint".to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(answer: &str, hint: Option<&str>) -> Card {
+        Card {
+            id: Uuid::from_u128(7),
+            question: "What is the capital of France?".to_owned(),
+            answer: answer.to_owned(),
+            hint: hint.map(str::to_owned),
+            ..Card::default()
+        }
+    }
+
+    fn options(answers: &[&str]) -> Vec<String> {
+        answers.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn eliminates_a_wrong_option_the_same_way_each_time() {
+        let card = card("Paris", None);
+        let options = options(&["Rome", "Paris", "Berlin", "Madrid"]);
+
+        for seed in 0..32 {
+            let revealed = reveal(HintKind::EliminateOption, &card, &options, seed).unwrap();
+            assert_ne!(revealed, "Paris");
+            assert!(options.contains(&revealed));
+            assert_eq!(
+                reveal(HintKind::EliminateOption, &card, &options, seed).unwrap(),
+                revealed
+            );
+        }
+    }
+
+    #[test]
+    fn nothing_left_to_eliminate_is_a_conflict() {
+        let card = card("Paris", None);
+        for options in [options(&[]), options(&["Paris"])] {
+            assert!(matches!(
+                reveal(HintKind::EliminateOption, &card, &options, 1),
+                Err(KeikoError::Conflict(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn first_letter_skips_leading_whitespace() {
+        assert_eq!(
+            reveal(HintKind::FirstLetter, &card("  Éire", None), &[], 1).unwrap(),
+            "É"
+        );
+        assert!(matches!(
+            reveal(HintKind::FirstLetter, &card("   ", None), &[], 1),
+            Err(KeikoError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn card_hint_needs_a_written_hint() {
+        assert_eq!(
+            reveal(
+                HintKind::CardHint,
+                &card("Paris", Some("city of light")),
+                &[],
+                1
+            )
+            .unwrap(),
+            "city of light"
+        );
+        for hint in [None, Some(""), Some("  ")] {
+            assert!(matches!(
+                reveal(HintKind::CardHint, &card("Paris", hint), &[], 1),
+                Err(KeikoError::Validation(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use async_trait::async_trait;
+use rand::Rng;
+use uuid::Uuid;
+
+use super::distractor::{answer_options, card_seed, Candidate, DEFAULT_DISTRACTOR_COUNT};
+use super::hint::{
+    reveal, HintKind, HintUsage, RequestHint, RevealedHint, DEFAULT_HINT_BUDGET,
+    DEFAULT_HINT_PENALTY,
+};
+use super::schema::{validate_elapsed_ms, QUIZZES};
+use super::{
+    CreateQuiz, GradeAnswer, GradedAnswer, Quiz, QuizAPI, QuizAnswer, QuizAnswerView, QuizQuestion,
+    QuizView,
+};
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::category_api::locked;
+use crate::list::{Columns, FilterValue, ListQuery, Page, SortValue};
+use crate::memory::{quiz_is_visible, round, InMemoryKeiko, QuizRow, State, Trashed};
+use crate::metrics::METRICS;
+use crate::{KeikoError, KeikoResult};
+
+/// Largest seed the `quizzes.seed` column default produces.
+const MAX_SEED: i64 = 9_007_199_254_740_991;
+
+/// A row of `quizzes_view`.
+fn quiz_view(state: &State, row: &QuizRow) -> QuizView {
+    let quiz = &row.quiz;
+    let card_count = state
+        .cards
+        .iter()
+        .filter(|c| c.course_id == quiz.course_id && c.category == quiz.category)
+        .count() as i64;
+
+    let answers: Vec<&QuizAnswer> = state
+        .answers
+        .iter()
+        .filter(|a| a.quiz_id == quiz.id)
+        .collect();
+    let hints: Vec<&HintUsage> = state
+        .hints
+        .iter()
+        .filter(|h| h.quiz_id == quiz.id)
+        .collect();
+
+    let (correct_count, score) = if answers.is_empty() {
+        (quiz.correct_count, f64::from(quiz.correct_count))
+    } else {
+        let correct: Vec<&&QuizAnswer> = answers.iter().filter(|a| a.correct).collect();
+        let score = correct
+            .iter()
+            .map(|a| {
+                let taken = hints.iter().filter(|h| h.card_id == a.card_id).count();
+                (1.0 - row.hint_penalty * taken as f64).max(0.0)
+            })
+            .sum();
+        (correct.len() as i32, score)
+    };
+
+    let progress = if quiz.is_completed {
+        100
+    } else if card_count == 0 {
+        0
+    } else if !answers.is_empty() {
+        round(answers.len() as f64 / card_count as f64 * 100.0).min(100)
+    } else {
+        round(f64::from(quiz.current_index) / (card_count - 1).max(1) as f64 * 100.0)
+    };
+
+    QuizView {
+        id: quiz.id,
+        user_id: quiz.user_id,
+        course_id: quiz.course_id,
+        course_code: quiz.course_code.clone(),
+        category: quiz.category.clone(),
+        current_index: quiz.current_index,
+        is_completed: quiz.is_completed,
+        hint_used: quiz.hint_used,
+        hint_budget: row.hint_budget,
+        hints_used: hints.len() as i64,
+        score,
+        started_at: quiz.started_at,
+        completed_at: quiz.completed_at,
+        correct_count,
+        card_count,
+        progress,
+    }
+}
+
+fn validate_quiz(quiz: &CreateQuiz) -> KeikoResult<()> {
+    if quiz
+        .distractor_count
+        .is_some_and(|c| !(1..=10).contains(&c))
+    {
+        return Err(KeikoError::Validation(
+            "distractor_count must be between 1 and 10".to_owned(),
+        ));
+    }
+    if quiz.hint_budget.is_some_and(|b| b < 0) {
+        return Err(KeikoError::Validation(
+            "hint_budget must not be negative".to_owned(),
+        ));
+    }
+    if quiz.hint_penalty.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+        return Err(KeikoError::Validation(
+            "hint_penalty must be between 0 and 1".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A visible quiz that is still running, as `quiz_session()` loads it.
+fn quiz_session<'s>(state: &'s State, user: &User, quiz_id: &Uuid) -> KeikoResult<&'s QuizRow> {
+    let row = state.visible_quiz(user, quiz_id)?;
+    if row.quiz.is_completed {
+        return Err(KeikoError::Conflict("quiz is already completed".to_owned()));
+    }
+
+    Ok(row)
+}
+
+fn current_card<'s>(state: &'s State, quiz: &Quiz) -> KeikoResult<&'s Card> {
+    state
+        .quiz_cards(&quiz.course_id, &quiz.category)
+        .get(quiz.current_index.max(0) as usize)
+        .copied()
+        .ok_or_else(|| KeikoError::Conflict("quiz has no remaining questions".to_owned()))
+}
+
+/// Answer options still on offer for `card`, after those eliminated by hints.
+fn remaining_options(state: &State, row: &QuizRow, card: &Card) -> Vec<String> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for c in &state.cards {
+        if c.course_id != row.quiz.course_id || c.answer == card.answer {
+            continue;
+        }
+        let candidate = Candidate {
+            answer: c.answer.clone(),
+            same_category: c.category == row.quiz.category,
+        };
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+
+    let mut options = answer_options(
+        &card.answer,
+        candidates,
+        row.distractor_count as usize,
+        card_seed(row.seed, &card.id),
+    );
+
+    let eliminated: Vec<&String> = state
+        .hints
+        .iter()
+        .filter(|h| {
+            h.quiz_id == row.quiz.id && h.card_id == card.id && h.kind == HintKind::EliminateOption
+        })
+        .map(|h| &h.revealed)
+        .collect();
+    options.retain(|o| !eliminated.contains(&o));
+
+    options
+}
+
+fn quiz_mut<'s>(state: &'s mut State, quiz_id: &Uuid) -> KeikoResult<&'s mut Quiz> {
+    state
+        .quizzes
+        .iter_mut()
+        .map(|q| &mut q.quiz)
+        .find(|q| q.id == *quiz_id)
+        .ok_or_else(|| KeikoError::not_found("quiz"))
+}
+
+impl Columns for QuizView {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "started_at" => Some(SortValue::Time(self.started_at)),
+            "course_code" => Some(SortValue::Text(self.course_code.clone())),
+            "category" => Some(SortValue::Text(self.category.clone())),
+            "progress" => Some(SortValue::Integer(i64::from(self.progress))),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, filter: &str, value: &FilterValue<'_>) -> bool {
+        match (filter, value) {
+            ("course_code", FilterValue::Text(v)) => self.course_code == *v,
+            ("category", FilterValue::Text(v)) => self.category == *v,
+            ("created_after", FilterValue::Time(v)) => self.started_at > *v,
+            ("is_completed", FilterValue::Bool(v)) => self.is_completed == *v,
+            _ => false,
+        }
+    }
+}
+
+#[async_trait]
+impl QuizAPI for InMemoryKeiko {
+    async fn get_quizzes(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<QuizView>> {
+        let state = self.state();
+        let quizzes = state
+            .quizzes
+            .iter()
+            .filter(|q| quiz_is_visible(&q.quiz, user) && state.course(&q.quiz.course_id).is_some())
+            .map(|q| quiz_view(&state, q))
+            .collect();
+
+        QUIZZES.page(quizzes, query)
+    }
+
+    async fn get_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizView> {
+        let state = self.state();
+        let row = state.visible_quiz(user, quiz_id)?;
+        Ok(quiz_view(&state, row))
+    }
+
+    async fn create_quiz(&self, user: &User, quiz: &CreateQuiz) -> KeikoResult<Quiz> {
+        validate_quiz(quiz)?;
+
+        let mut state = self.state();
+        let course_id = state.visible_course(user, &quiz.course_code)?.id;
+        if let Some(unlock_after) = state.category_lock(user, &course_id, &quiz.category) {
+            return Err(locked(&quiz.category, unlock_after));
+        }
+        state.ensure_category(&course_id, &quiz.category);
+
+        let row = QuizRow {
+            quiz: Quiz {
+                id: Uuid::new_v4(),
+                user_id: Some(user.id),
+                course_id,
+                course_code: quiz.course_code.clone(),
+                category: quiz.category.clone(),
+                current_index: 0,
+                correct_count: 0,
+                is_completed: false,
+                hint_used: false,
+                started_at: chrono::Utc::now(),
+                completed_at: None,
+            },
+            seed: rand::thread_rng().gen_range(0..MAX_SEED),
+            distractor_count: quiz.distractor_count.unwrap_or(DEFAULT_DISTRACTOR_COUNT),
+            hint_budget: quiz.hint_budget.unwrap_or(DEFAULT_HINT_BUDGET),
+            hint_penalty: quiz.hint_penalty.unwrap_or(DEFAULT_HINT_PENALTY),
+        };
+        let created = row.quiz.clone();
+        state.quizzes.push(row);
+        METRICS.quizzes_started.inc();
+
+        Ok(created)
+    }
+
+    /// Moves the quiz to the trash, as `delete_quiz()` does.
+    async fn delete_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Uuid> {
+        let mut state = self.state();
+        state.visible_quiz(user, quiz_id)?;
+        let index = state
+            .quizzes
+            .iter()
+            .position(|q| q.quiz.id == *quiz_id)
+            .ok_or_else(|| KeikoError::not_found("quiz"))?;
+        let row = state.quizzes.remove(index);
+        state.trashed_quizzes.push(Trashed {
+            row,
+            deleted_at: chrono::Utc::now(),
+        });
+
+        Ok(*quiz_id)
+    }
+
+    async fn retake_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz> {
+        let retake = {
+            let state = self.state();
+            let row = state.visible_quiz(user, quiz_id)?;
+            CreateQuiz {
+                course_code: row.quiz.course_code.clone(),
+                category: row.quiz.category.clone(),
+                distractor_count: Some(row.distractor_count),
+                hint_budget: Some(row.hint_budget),
+                hint_penalty: Some(row.hint_penalty),
+            }
+        };
+
+        self.create_quiz(user, &retake).await
+    }
+
+    async fn get_attempts(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizView>> {
+        let state = self.state();
+        let quiz = &state.visible_quiz(user, quiz_id)?.quiz;
+
+        let mut attempts: Vec<QuizView> = state
+            .quizzes
+            .iter()
+            .filter(|q| {
+                q.quiz.course_id == quiz.course_id
+                    && q.quiz.category == quiz.category
+                    && quiz_is_visible(&q.quiz, user)
+            })
+            .map(|q| quiz_view(&state, q))
+            .collect();
+        attempts.sort_by_key(|q| (q.started_at, q.id));
+
+        Ok(attempts)
+    }
+
+    async fn get_answers(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizAnswerView>> {
+        let state = self.state();
+        state.visible_quiz(user, quiz_id)?;
+
+        let mut answers: Vec<QuizAnswerView> = state
+            .answers
+            .iter()
+            .filter(|a| a.quiz_id == *quiz_id)
+            .filter_map(|a| {
+                let card = state
+                    .cards
+                    .iter()
+                    .chain(state.trashed_cards.iter().map(|t| &t.row))
+                    .find(|c| c.id == a.card_id)?;
+                Some(QuizAnswerView {
+                    id: a.id,
+                    quiz_id: a.quiz_id,
+                    card_id: a.card_id,
+                    question: card.question.clone(),
+                    answer: card.answer.clone(),
+                    given_answer: a.given_answer.clone(),
+                    correct: a.correct,
+                    hint_used: a.hint_used,
+                    elapsed_ms: a.elapsed_ms,
+                    answered_at: a.answered_at,
+                })
+            })
+            .collect();
+        answers.sort_by_key(|a| a.answered_at);
+
+        Ok(answers)
+    }
+
+    async fn get_question(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizQuestion> {
+        let state = self.state();
+        let row = quiz_session(&state, user, quiz_id)?;
+        let card = current_card(&state, &row.quiz)?;
+
+        Ok(QuizQuestion {
+            quiz_id: row.quiz.id,
+            card_id: card.id,
+            index: row.quiz.current_index,
+            card_count: state
+                .quiz_cards(&row.quiz.course_id, &row.quiz.category)
+                .len() as i64,
+            question: card.question.clone(),
+            options: remaining_options(&state, row, card),
+        })
+    }
+
+    async fn grade_answer(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        answer: &GradeAnswer,
+    ) -> KeikoResult<GradedAnswer> {
+        validate_elapsed_ms(answer.elapsed_ms)?;
+
+        let mut state = self.state();
+        let row = quiz_session(&state, user, quiz_id)?.clone();
+        let card = current_card(&state, &row.quiz)?.clone();
+
+        if card.id != answer.card_id {
+            return Err(KeikoError::Conflict(
+                "answer is not for the current question".to_owned(),
+            ));
+        }
+
+        let correct = card.answer == answer.given_answer;
+        let hinted = state
+            .hints
+            .iter()
+            .any(|h| h.quiz_id == *quiz_id && h.card_id == card.id);
+        let card_count = state
+            .quiz_cards(&row.quiz.course_id, &row.quiz.category)
+            .len() as i64;
+
+        state.answers.push(QuizAnswer {
+            id: Uuid::new_v4(),
+            quiz_id: *quiz_id,
+            card_id: card.id,
+            given_answer: answer.given_answer.clone(),
+            correct,
+            hint_used: hinted,
+            elapsed_ms: answer.elapsed_ms,
+            answered_at: chrono::Utc::now(),
+        });
+
+        let quiz = quiz_mut(&mut state, quiz_id)?;
+        let completed = i64::from(quiz.current_index) + 1 >= card_count;
+        quiz.current_index += 1;
+        quiz.correct_count += i32::from(correct);
+        quiz.hint_used = false;
+        quiz.is_completed = completed;
+        quiz.completed_at = completed.then(chrono::Utc::now);
+        METRICS.count_completion(quiz);
+
+        Ok(GradedAnswer {
+            correct,
+            correct_answer: card.answer,
+            quiz: quiz.clone(),
+        })
+    }
+
+    async fn use_hint(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        hint: &RequestHint,
+    ) -> KeikoResult<RevealedHint> {
+        let mut state = self.state();
+        let row = quiz_session(&state, user, quiz_id)?.clone();
+
+        let card = current_card(&state, &row.quiz)?.clone();
+
+        let used = state.hints.iter().filter(|h| h.quiz_id == *quiz_id).count() as i32;
+        let taken = state
+            .hints
+            .iter()
+            .find(|h| h.quiz_id == *quiz_id && h.card_id == card.id && h.kind == hint.kind);
+
+        if let Some(usage) = taken {
+            return Ok(RevealedHint {
+                usage: usage.clone(),
+                hints_remaining: row.hint_budget - used,
+            });
+        }
+
+        if used >= row.hint_budget {
+            return Err(KeikoError::Conflict("hint budget exhausted".to_owned()));
+        }
+        let options = remaining_options(&state, &row, &card);
+        let revealed = reveal(hint.kind, &card, &options, row.seed)?;
+
+        let usage = HintUsage {
+            id: Uuid::new_v4(),
+            quiz_id: *quiz_id,
+            card_id: card.id,
+            kind: hint.kind,
+            revealed,
+            used_at: chrono::Utc::now(),
+        };
+        let hints_remaining = row.hint_budget - used - 1;
+
+        state.hints.push(usage.clone());
+        quiz_mut(&mut state, quiz_id)?.hint_used = true;
+
+        Ok(RevealedHint {
+            usage,
+            hints_remaining,
+        })
+    }
+
+    async fn get_hints(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<HintUsage>> {
+        let state = self.state();
+        state.visible_quiz(user, quiz_id)?;
+
+        let mut hints: Vec<HintUsage> = state
+            .hints
+            .iter()
+            .filter(|h| h.quiz_id == *quiz_id)
+            .cloned()
+            .collect();
+        hints.sort_by_key(|h| h.used_at);
+
+        Ok(hints)
+    }
+}
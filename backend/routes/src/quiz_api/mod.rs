@@ -1,21 +1,33 @@
+pub mod distractor;
+pub mod hint;
+mod memory;
 mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::auth_api::User;
+use crate::list::{ListQuery, Page};
 use crate::KeikoResult;
+use hint::{HintUsage, RequestHint, RevealedHint};
 
-#[derive(
-    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
-)]
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, PartialOrd, Default)]
 pub struct QuizView {
     pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub course_id: Uuid,
     pub course_code: String,
     pub category: String,
     pub current_index: i32,
     pub is_completed: bool,
     pub hint_used: bool,
+    pub hint_budget: i32,
+    pub hints_used: i64,
+    /// Correct answers, each reduced by the quiz's hint penalty per hint taken on it.
+    pub score: f64,
     pub started_at: chrono::DateTime<chrono::Utc>,
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
     pub correct_count: i32,
@@ -28,6 +40,13 @@ pub struct QuizView {
 )]
 pub struct Quiz {
     pub id: Uuid,
+    /// `None` for quizzes started before user accounts until the first user registers and is
+    /// given them; no one else can see them.
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    /// Not read on updates, which leave the course of a quiz as it is.
+    #[serde(default)]
+    pub course_id: Uuid,
     pub course_code: String,
     pub category: String,
     pub current_index: i32,
@@ -38,67 +57,109 @@ pub struct Quiz {
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(
-    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
-)]
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, PartialOrd, Default)]
 pub struct CreateQuiz {
     pub course_code: String,
     pub category: String,
+    /// Wrong answers offered alongside each question, 3 if omitted.
+    #[serde(default)]
+    pub distractor_count: Option<i32>,
+    /// Hints available over the whole quiz, 3 if omitted.
+    #[serde(default)]
+    pub hint_budget: Option<i32>,
+    /// Fraction of a point deducted from a correct answer per hint taken, 0.5 if omitted.
+    #[serde(default)]
+    pub hint_penalty: Option<f64>,
 }
 
 #[derive(
     Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
 )]
-pub struct QuizCompletion {
+pub struct QuizAnswer {
     pub id: Uuid,
-    pub is_completed: bool,
+    pub quiz_id: Uuid,
+    pub card_id: Uuid,
+    pub given_answer: String,
+    pub correct: bool,
+    pub hint_used: bool,
+    pub elapsed_ms: i32,
+    pub answered_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// A recorded answer alongside the card it was given for.
 #[derive(
     Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
 )]
-pub struct QuizIndex {
-    pub current_index: i32,
+pub struct QuizAnswerView {
+    pub id: Uuid,
+    pub quiz_id: Uuid,
+    pub card_id: Uuid,
+    pub question: String,
+    pub answer: String,
+    pub given_answer: String,
+    pub correct: bool,
+    pub hint_used: bool,
+    pub elapsed_ms: i32,
+    pub answered_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(
-    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
-)]
-pub struct QuizCorrectCount {
-    pub correct_count: i32,
+/// The current question of a quiz session, without its answer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct QuizQuestion {
+    pub quiz_id: Uuid,
+    pub card_id: Uuid,
+    pub index: i32,
+    pub card_count: i64,
+    pub question: String,
+    pub options: Vec<String>,
 }
 
 #[derive(
     Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
 )]
-pub struct RenameQuiz {
-    pub old: String,
-    pub new: String,
+pub struct GradeAnswer {
+    /// The card being answered, guarding against a stale or repeated submission.
+    pub card_id: Uuid,
+    pub given_answer: String,
+    pub elapsed_ms: i32,
 }
 
-#[derive(
-    Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default,
-)]
-pub struct QuizHint {
-    pub hint_used: bool,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct GradedAnswer {
+    pub correct: bool,
+    pub correct_answer: String,
+    pub quiz: Quiz,
 }
 
 #[async_trait]
 pub trait QuizAPI: Send + Sync + 'static {
-    async fn get_quizzes(&self) -> KeikoResult<Vec<QuizView>>;
-    async fn get_quiz(&self, quiz_id: &Uuid) -> KeikoResult<QuizView>;
-    async fn get_ongoing_quizzes(&self) -> KeikoResult<Vec<QuizView>>;
-    async fn get_completed_quizzes(&self) -> KeikoResult<Vec<QuizView>>;
-    async fn create_quiz(&self, quiz: &CreateQuiz) -> KeikoResult<Quiz>;
-    async fn update_quiz(&self, quiz: &Quiz) -> KeikoResult<Quiz>;
-    async fn delete_quiz(&self, quiz_id: &Uuid) -> KeikoResult<Uuid>;
-    async fn set_quiz_completion(&self, quiz_completion: &QuizCompletion) -> KeikoResult<Quiz>;
-    async fn set_current_index(&self, quiz_id: &Uuid, quiz_index: &QuizIndex) -> KeikoResult<Quiz>;
-    async fn set_correct_count(
+    async fn get_quizzes(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<QuizView>>;
+    async fn get_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizView>;
+    /// Adds the category to the course if it has none by that name. Fails with a conflict while
+    /// the category waits on a quiz on the one it unlocks after.
+    async fn create_quiz(&self, user: &User, quiz: &CreateQuiz) -> KeikoResult<Quiz>;
+    /// Deletes the attempt with its answers and hints. The cards of its category stay.
+    async fn delete_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Uuid>;
+    /// Starts a fresh attempt at the quiz's category with the same settings, leaving the old
+    /// one and its score as they are.
+    async fn retake_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz>;
+    /// Every attempt at the quiz's category the user can see, the quiz included, oldest first.
+    async fn get_attempts(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizView>>;
+    async fn get_answers(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizAnswerView>>;
+    async fn get_question(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizQuestion>;
+    async fn grade_answer(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        answer: &GradeAnswer,
+    ) -> KeikoResult<GradedAnswer>;
+    /// Reveals a hint of the requested kind for the current card. Asking again for a kind
+    /// already taken on this card returns the same hint without spending another.
+    async fn use_hint(
         &self,
+        user: &User,
         quiz_id: &Uuid,
-        quiz_count: &QuizCorrectCount,
-    ) -> KeikoResult<Quiz>;
-    async fn set_hint_used(&self, quiz_id: &Uuid, quiz_hint: &QuizHint) -> KeikoResult<Quiz>;
-    async fn rename_quiz(&self, course_code: &str, quiz_rename: &RenameQuiz) -> KeikoResult<()>;
+        hint: &RequestHint,
+    ) -> KeikoResult<RevealedHint>;
+    async fn get_hints(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<HintUsage>>;
 }
@@ -1,157 +1,537 @@
+use tracing::instrument;
 use uuid::Uuid;
 
-use crate::{KeikoDatabase, KeikoResult};
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
 use async_trait::async_trait;
 
+use super::distractor::{answer_options, card_seed, Candidate, DEFAULT_DISTRACTOR_COUNT};
+use super::hint::{
+    reveal, HintKind, HintUsage, RequestHint, RevealedHint, DEFAULT_HINT_BUDGET,
+    DEFAULT_HINT_PENALTY,
+};
 use super::{
-    CreateQuiz, Quiz, QuizAPI, QuizCompletion, QuizCorrectCount, QuizHint, QuizIndex, QuizView,
-    RenameQuiz,
+    CreateQuiz, GradeAnswer, GradedAnswer, Quiz, QuizAPI, QuizAnswerView, QuizQuestion, QuizView,
+};
+use crate::access;
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::category_api::locked;
+use crate::list::{Filter, ListQuery, Listing, Page, SortField};
+use crate::metrics::METRICS;
+
+pub(super) const QUIZZES: Listing = Listing {
+    name: "quizzes",
+    from: "quizzes_view",
+    visible_to: "user_id = $?",
+    sort_fields: &[
+        SortField {
+            column: "started_at",
+            sql_type: "timestamptz",
+        },
+        SortField {
+            column: "course_code",
+            sql_type: "text",
+        },
+        SortField {
+            column: "category",
+            sql_type: "text",
+        },
+        SortField {
+            column: "progress",
+            sql_type: "integer",
+        },
+    ],
+    filters: &[
+        Filter {
+            name: "course_code",
+            condition: "course_code = $?",
+        },
+        Filter {
+            name: "category",
+            condition: "category = $?",
+        },
+        Filter {
+            name: "created_after",
+            condition: "started_at > $?",
+        },
+        Filter {
+            name: "is_completed",
+            condition: "is_completed = $?",
+        },
+    ],
 };
 
+pub(super) fn validate_elapsed_ms(elapsed_ms: i32) -> KeikoResult<()> {
+    if elapsed_ms < 0 {
+        return Err(KeikoError::Validation(
+            "elapsed_ms must not be negative".to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Columns of `quizzes` that drive a running quiz session.
+#[derive(sqlx::FromRow)]
+struct QuizSession {
+    id: Uuid,
+    course_id: Uuid,
+    category: String,
+    current_index: i32,
+    is_completed: bool,
+    seed: i64,
+    distractor_count: i32,
+    hint_budget: i32,
+}
+
+async fn quiz_session(
+    executor: impl sqlx::PgExecutor<'_>,
+    quiz_id: &Uuid,
+    for_update: bool,
+) -> KeikoResult<QuizSession> {
+    let query = if for_update {
+        "SELECT * FROM quizzes WHERE id = $1 FOR UPDATE"
+    } else {
+        "SELECT * FROM quizzes WHERE id = $1"
+    };
+
+    let quiz = sqlx::query_as::<_, QuizSession>(query)
+        .bind(quiz_id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("quiz"))?;
+
+    if quiz.is_completed {
+        return Err(KeikoError::Conflict("quiz is already completed".to_owned()));
+    }
+
+    Ok(quiz)
+}
+
+/// Answer options for `card`, before any eliminated by hints.
+async fn card_options(
+    executor: impl sqlx::PgExecutor<'_>,
+    quiz: &QuizSession,
+    card: &Card,
+) -> KeikoResult<Vec<String>> {
+    let candidates = sqlx::query_as::<_, Candidate>(
+        r#"
+        SELECT DISTINCT answer, category = $2 AS same_category
+        FROM cards
+        WHERE course_id = $1 AND answer <> $3 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(quiz.course_id)
+    .bind(&quiz.category)
+    .bind(&card.answer)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(answer_options(
+        &card.answer,
+        candidates,
+        quiz.distractor_count as usize,
+        card_seed(quiz.seed, &card.id),
+    ))
+}
+
+async fn eliminated_options(
+    executor: impl sqlx::PgExecutor<'_>,
+    quiz_id: &Uuid,
+    card_id: &Uuid,
+) -> KeikoResult<Vec<String>> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT revealed FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2 AND kind = $3",
+    )
+    .bind(quiz_id)
+    .bind(card_id)
+    .bind(HintKind::EliminateOption)
+    .fetch_all(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// The card at `current_index`, in the same order as GET /v1/cards/quiz/{quiz_id}.
+async fn current_card(
+    executor: impl sqlx::PgExecutor<'_>,
+    course_id: &Uuid,
+    category: &str,
+    current_index: i32,
+) -> KeikoResult<Card> {
+    sqlx::query_as::<_, Card>(
+        r#"
+        SELECT * FROM cards
+        WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+        ORDER BY created_at, id
+        OFFSET $3
+        LIMIT 1
+        "#,
+    )
+    .bind(course_id)
+    .bind(category)
+    .bind(i64::from(current_index))
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::Conflict("quiz has no remaining questions".to_owned()))
+}
+
 #[async_trait]
 impl QuizAPI for KeikoDatabase {
     /// GET /v1/quiz
-    async fn get_quizzes(&self) -> KeikoResult<Vec<QuizView>> {
-        sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_quizzes(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<QuizView>> {
+        QUIZZES.fetch(&self.pool, user, query).await
     }
 
     /// GET /v1/quiz/id/{quiz_id}
-    async fn get_quiz(&self, quiz_id: &Uuid) -> KeikoResult<QuizView> {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizView> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
         sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view WHERE id = $1")
             .bind(quiz_id)
-            .fetch_one(&self.pool)
+            .fetch_optional(&self.pool)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(KeikoError::from)?
+            .ok_or_else(|| KeikoError::not_found("quiz"))
     }
 
-    /// GET /v1/quiz/ongoing
-    async fn get_ongoing_quizzes(&self) -> KeikoResult<Vec<QuizView>> {
-        sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view WHERE is_completed IS false")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
-    }
+    /// POST /v1/quiz
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_quiz(&self, user: &User, quiz: &CreateQuiz) -> KeikoResult<Quiz> {
+        let mut tx = self.pool.begin().await?;
+        let course_id = access::course(&mut *tx, user, &quiz.course_code).await?;
 
-    /// GET /v1/quiz/completed
-    async fn get_completed_quizzes(&self) -> KeikoResult<Vec<QuizView>> {
-        sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view WHERE is_completed IS true")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
-    }
+        sqlx::query_scalar::<_, ()>("SELECT ensure_category($1, $2)")
+            .bind(course_id)
+            .bind(&quiz.category)
+            .fetch_one(&mut *tx)
+            .await?;
+        let lock = sqlx::query_scalar::<_, Option<String>>("SELECT category_lock($1, $2, $3)")
+            .bind(course_id)
+            .bind(&quiz.category)
+            .bind(user.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if let Some(unlock_after) = lock {
+            return Err(locked(&quiz.category, &unlock_after));
+        }
 
-    /// POST /v1/quiz
-    async fn create_quiz(&self, quiz: &CreateQuiz) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>(
+        let quiz = sqlx::query_as::<_, Quiz>(
             r#"
-            INSERT INTO quizzes (course_code, category)
-            VALUES ($1, $2)
+            INSERT INTO quizzes (
+                course_id, course_code, category, distractor_count, hint_budget, hint_penalty,
+                user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING *
             "#,
         )
+        .bind(course_id)
         .bind(&quiz.course_code)
         .bind(&quiz.category)
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(quiz.distractor_count.unwrap_or(DEFAULT_DISTRACTOR_COUNT))
+        .bind(quiz.hint_budget.unwrap_or(DEFAULT_HINT_BUDGET))
+        .bind(quiz.hint_penalty.unwrap_or(DEFAULT_HINT_PENALTY))
+        .bind(user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        METRICS.quizzes_started.inc();
+
+        Ok(quiz)
     }
 
-    /// PUT /v1/quiz
-    async fn update_quiz(&self, quiz: &Quiz) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>(
+    /// DELETE /v1/quiz/id/{quiz_id}
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Uuid> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_scalar::<_, Option<Uuid>>("SELECT delete_quiz($1)")
+            .bind(quiz_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(KeikoError::from)?
+            .ok_or_else(|| KeikoError::not_found("quiz"))
+    }
+
+    /// POST /v1/quiz/id/{quiz_id}/retake
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn retake_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        let retake = sqlx::query_as::<_, CreateQuiz>(
             r#"
-            UPDATE quizzes
-            SET current_index = $2, correct_count = $3, is_completed = $4, completed_at = $5
+            SELECT course_code, category, distractor_count, hint_budget, hint_penalty
+            FROM quizzes
             WHERE id = $1
-            RETURNING *
             "#,
         )
-        .bind(quiz.id)
-        .bind(quiz.current_index)
-        .bind(quiz.correct_count)
-        .bind(quiz.is_completed)
-        .bind(if quiz.is_completed {
-            Some(chrono::Utc::now())
-        } else {
-            None
-        })
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| e.to_string())
+        .bind(quiz_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("quiz"))?;
+
+        self.create_quiz(user, &retake).await
     }
 
-    /// DELETE /v1/quiz/id/{quiz_id}
-    async fn delete_quiz(&self, quiz_id: &Uuid) -> KeikoResult<Uuid> {
-        sqlx::query_scalar::<_, Uuid>("SELECT delete_quiz($1)")
-            .bind(quiz_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    /// GET /v1/quiz/id/{quiz_id}/attempts
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_attempts(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizView>> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, QuizView>(
+            r#"
+            SELECT v.*
+            FROM quizzes_view v
+            JOIN quizzes q ON q.course_id = v.course_id AND q.category = v.category
+            WHERE q.id = $1 AND v.user_id = $2
+            ORDER BY v.started_at, v.id
+            "#,
+        )
+        .bind(quiz_id)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
     }
 
-    /// PATCH /v1/quiz
-    async fn set_quiz_completion(&self, quiz_completion: &QuizCompletion) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>(
-            "UPDATE quizzes SET is_completed = $2, completed_at = $3 WHERE id = $1 RETURNING *",
+    /// GET /v1/quiz/id/{quiz_id}/answers
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_answers(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizAnswerView>> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, QuizAnswerView>(
+            r#"
+            SELECT a.*, c.question, c.answer
+            FROM quiz_answers a
+            JOIN cards c ON c.id = a.card_id
+            WHERE a.quiz_id = $1
+            ORDER BY a.answered_at
+            "#,
         )
-        .bind(quiz_completion.id)
-        .bind(quiz_completion.is_completed)
-        .bind(if quiz_completion.is_completed {
-            Some(chrono::Utc::now())
-        } else {
-            None
-        })
-        .bind(chrono::Utc::now())
-        .fetch_one(&self.pool)
+        .bind(quiz_id)
+        .fetch_all(&self.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(KeikoError::from)
     }
+    /// GET /v1/quiz/id/{quiz_id}/question
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_question(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizQuestion> {
+        access::quiz(&self.pool, user, quiz_id).await?;
 
-    /// PATCH /v1/quiz/id/{quiz_id}/index
-    async fn set_current_index(&self, quiz_id: &Uuid, quiz_index: &QuizIndex) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>("UPDATE quizzes SET current_index = $2 WHERE id = $1 RETURNING *")
-            .bind(quiz_id)
-            .bind(quiz_index.current_index)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+        let quiz = quiz_session(&self.pool, quiz_id, false).await?;
+        let card = current_card(
+            &self.pool,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        let eliminated = eliminated_options(&self.pool, &quiz.id, &card.id).await?;
+        let mut options = card_options(&self.pool, &quiz, &card).await?;
+        options.retain(|o| !eliminated.contains(o));
+
+        let card_count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM cards
+            WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(quiz.course_id)
+        .bind(&quiz.category)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(QuizQuestion {
+            quiz_id: quiz.id,
+            card_id: card.id,
+            index: quiz.current_index,
+            card_count,
+            question: card.question,
+            options,
+        })
     }
 
-    /// PATCH /v1/quiz/id/{quiz_id}/correct
-    async fn set_correct_count(
+    /// POST /v1/quiz/id/{quiz_id}/grade
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn grade_answer(
         &self,
+        user: &User,
         quiz_id: &Uuid,
-        quiz_count: &QuizCorrectCount,
-    ) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>("UPDATE quizzes SET correct_count = $2 WHERE id = $1 RETURNING *")
-            .bind(quiz_id)
-            .bind(quiz_count.correct_count)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+        answer: &GradeAnswer,
+    ) -> KeikoResult<GradedAnswer> {
+        validate_elapsed_ms(answer.elapsed_ms)?;
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let quiz = quiz_session(&mut *tx, quiz_id, true).await?;
+        let card = current_card(
+            &mut *tx,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        if card.id != answer.card_id {
+            return Err(KeikoError::Conflict(
+                "answer is not for the current question".to_owned(),
+            ));
+        }
+
+        let correct = card.answer == answer.given_answer;
+
+        sqlx::query(
+            r#"
+            INSERT INTO quiz_answers (quiz_id, card_id, given_answer, correct, hint_used, elapsed_ms)
+            VALUES (
+                $1, $2, $3, $4,
+                EXISTS (SELECT 1 FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2),
+                $5
+            )
+            "#,
+        )
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(&answer.given_answer)
+        .bind(correct)
+        .bind(answer.elapsed_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        let card_count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM cards
+            WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(quiz.course_id)
+        .bind(&quiz.category)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let quiz = sqlx::query_as::<_, Quiz>(
+            r#"
+            UPDATE quizzes
+            SET current_index = current_index + 1,
+                correct_count = correct_count + $2,
+                hint_used = false,
+                is_completed = $3,
+                completed_at = CASE WHEN $3 THEN now() END
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(quiz.id)
+        .bind(i32::from(correct))
+        .bind(i64::from(quiz.current_index) + 1 >= card_count)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        METRICS.count_completion(&quiz);
+
+        Ok(GradedAnswer {
+            correct,
+            correct_answer: card.answer,
+            quiz,
+        })
     }
+    /// POST /v1/quiz/id/{quiz_id}/hints
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn use_hint(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        hint: &RequestHint,
+    ) -> KeikoResult<RevealedHint> {
+        access::quiz(&self.pool, user, quiz_id).await?;
 
-    /// PATCH /v1/quiz/id/{quiz_id}/hint
-    async fn set_hint_used(&self, quiz_id: &Uuid, quiz_hint: &QuizHint) -> KeikoResult<Quiz> {
-        sqlx::query_as::<_, Quiz>("UPDATE quizzes SET hint_used = $2 WHERE id = $1 RETURNING *")
-            .bind(quiz_id)
-            .bind(quiz_hint.hint_used)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+        let mut tx = self.pool.begin().await?;
+
+        let quiz = quiz_session(&mut *tx, quiz_id, true).await?;
+        let card = current_card(
+            &mut *tx,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        let used =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM quiz_hints WHERE quiz_id = $1")
+                .bind(quiz.id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let taken = sqlx::query_as::<_, HintUsage>(
+            "SELECT * FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2 AND kind = $3",
+        )
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(hint.kind)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(usage) = taken {
+            return Ok(RevealedHint {
+                usage,
+                hints_remaining: quiz.hint_budget - used as i32,
+            });
+        }
+
+        if used >= i64::from(quiz.hint_budget) {
+            return Err(KeikoError::Conflict("hint budget exhausted".to_owned()));
+        }
+
+        let mut options = card_options(&mut *tx, &quiz, &card).await?;
+        let eliminated = eliminated_options(&mut *tx, &quiz.id, &card.id).await?;
+        options.retain(|o| !eliminated.contains(o));
+
+        let revealed = reveal(hint.kind, &card, &options, quiz.seed)?;
+
+        let usage = sqlx::query_as::<_, HintUsage>(
+            r#"
+            INSERT INTO quiz_hints (quiz_id, card_id, kind, revealed)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(hint.kind)
+        .bind(revealed)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE quizzes SET hint_used = true WHERE id = $1")
+            .bind(quiz.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(RevealedHint {
+            usage,
+            hints_remaining: quiz.hint_budget - used as i32 - 1,
+        })
     }
 
-    /// POST /v1/quiz/rename/{course_code}
-    async fn rename_quiz(&self, course_code: &str, quiz_rename: &RenameQuiz) -> KeikoResult<()> {
-        sqlx::query_scalar::<_, ()>("SELECT update_category($1, $2, $3)")
-            .bind(course_code)
-            .bind(&quiz_rename.old)
-            .bind(&quiz_rename.new)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| e.to_string())
+    /// GET /v1/quiz/id/{quiz_id}/hints
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_hints(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<HintUsage>> {
+        access::quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, HintUsage>(
+            "SELECT * FROM quiz_hints WHERE quiz_id = $1 ORDER BY used_at",
+        )
+        .bind(quiz_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
     }
 }
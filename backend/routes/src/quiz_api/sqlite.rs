@@ -0,0 +1,467 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::distractor::{answer_options, card_seed, Candidate, DEFAULT_DISTRACTOR_COUNT};
+use super::hint::{
+    reveal, HintKind, HintUsage, RequestHint, RevealedHint, DEFAULT_HINT_BUDGET,
+    DEFAULT_HINT_PENALTY,
+};
+use super::schema::{validate_elapsed_ms, QUIZZES};
+use super::{
+    CreateQuiz, GradeAnswer, GradedAnswer, Quiz, QuizAPI, QuizAnswerView, QuizQuestion, QuizView,
+};
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::category_api::locked;
+use crate::list::{ListQuery, Page};
+use crate::metrics::METRICS;
+use crate::sqlite::{self, visible_course, visible_quiz, KeikoSqlite};
+use crate::{KeikoError, KeikoResult};
+
+/// Columns of `quizzes` that drive a running quiz session.
+#[derive(sqlx::FromRow)]
+struct QuizSession {
+    id: Uuid,
+    course_id: Uuid,
+    category: String,
+    current_index: i32,
+    is_completed: bool,
+    seed: i64,
+    distractor_count: i32,
+    hint_budget: i32,
+}
+
+async fn quiz_session(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    quiz_id: &Uuid,
+) -> KeikoResult<QuizSession> {
+    let quiz = sqlx::query_as::<_, QuizSession>("SELECT * FROM quizzes WHERE id = $1")
+        .bind(quiz_id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("quiz"))?;
+
+    if quiz.is_completed {
+        return Err(KeikoError::Conflict("quiz is already completed".to_owned()));
+    }
+
+    Ok(quiz)
+}
+
+/// Answer options for `card`, before any eliminated by hints.
+async fn card_options(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    quiz: &QuizSession,
+    card: &Card,
+) -> KeikoResult<Vec<String>> {
+    let candidates = sqlx::query_as::<_, Candidate>(
+        r#"
+        SELECT DISTINCT answer, category = $2 AS same_category
+        FROM cards
+        WHERE course_id = $1 AND answer <> $3 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(quiz.course_id)
+    .bind(&quiz.category)
+    .bind(&card.answer)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(answer_options(
+        &card.answer,
+        candidates,
+        quiz.distractor_count as usize,
+        card_seed(quiz.seed, &card.id),
+    ))
+}
+
+async fn eliminated_options(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    quiz_id: &Uuid,
+    card_id: &Uuid,
+) -> KeikoResult<Vec<String>> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT revealed FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2 AND kind = $3",
+    )
+    .bind(quiz_id)
+    .bind(card_id)
+    .bind(HintKind::EliminateOption)
+    .fetch_all(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// The card at `current_index`, in the same order as GET /v1/cards/quiz/{quiz_id}.
+async fn current_card(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    course_id: &Uuid,
+    category: &str,
+    current_index: i32,
+) -> KeikoResult<Card> {
+    sqlx::query_as::<_, Card>(
+        r#"
+        SELECT * FROM cards
+        WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+        ORDER BY created_at, id
+        LIMIT 1 OFFSET $3
+        "#,
+    )
+    .bind(course_id)
+    .bind(category)
+    .bind(i64::from(current_index))
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::Conflict("quiz has no remaining questions".to_owned()))
+}
+
+async fn card_count(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    course_id: &Uuid,
+    category: &str,
+) -> KeikoResult<i64> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM cards
+        WHERE course_id = $1 AND category = $2 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(course_id)
+    .bind(category)
+    .fetch_one(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+#[async_trait]
+impl QuizAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_quizzes(&self, user: &User, query: &ListQuery) -> KeikoResult<Page<QuizView>> {
+        let quizzes =
+            sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view WHERE user_id = $1")
+                .bind(user.id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        QUIZZES.page(quizzes, query)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizView> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, QuizView>("SELECT * FROM quizzes_view WHERE id = $1")
+            .bind(quiz_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(KeikoError::from)?
+            .ok_or_else(|| KeikoError::not_found("quiz"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn create_quiz(&self, user: &User, quiz: &CreateQuiz) -> KeikoResult<Quiz> {
+        let mut tx = self.pool.begin().await?;
+        let course_id = visible_course(&mut *tx, user, &quiz.course_code).await?;
+
+        sqlite::ensure_category(&mut *tx, &course_id, &quiz.category).await?;
+        if let Some(unlock_after) =
+            sqlite::category_lock(&mut *tx, user, &course_id, &quiz.category).await?
+        {
+            return Err(locked(&quiz.category, &unlock_after));
+        }
+
+        let quiz = sqlx::query_as::<_, Quiz>(
+            r#"
+            INSERT INTO quizzes (
+                id, course_id, course_code, category, distractor_count, hint_budget,
+                hint_penalty, user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(course_id)
+        .bind(&quiz.course_code)
+        .bind(&quiz.category)
+        .bind(quiz.distractor_count.unwrap_or(DEFAULT_DISTRACTOR_COUNT))
+        .bind(quiz.hint_budget.unwrap_or(DEFAULT_HINT_BUDGET))
+        .bind(quiz.hint_penalty.unwrap_or(DEFAULT_HINT_PENALTY))
+        .bind(user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        METRICS.quizzes_started.inc();
+
+        Ok(quiz)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn delete_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Uuid> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        sqlite::delete_quiz(&self.pool, quiz_id)
+            .await?
+            .ok_or_else(|| KeikoError::not_found("quiz"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn retake_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        let retake = sqlx::query_as::<_, CreateQuiz>(
+            r#"
+            SELECT course_code, category, distractor_count, hint_budget, hint_penalty
+            FROM quizzes
+            WHERE id = $1
+            "#,
+        )
+        .bind(quiz_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| KeikoError::not_found("quiz"))?;
+
+        self.create_quiz(user, &retake).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_attempts(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizView>> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, QuizView>(
+            r#"
+            SELECT v.*
+            FROM quizzes_view v
+            JOIN quizzes q ON q.course_id = v.course_id AND q.category = v.category
+            WHERE q.id = $1 AND v.user_id = $2
+            ORDER BY v.started_at, v.id
+            "#,
+        )
+        .bind(quiz_id)
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_answers(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<QuizAnswerView>> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, QuizAnswerView>(
+            r#"
+            SELECT a.*, c.question, c.answer
+            FROM quiz_answers a
+            JOIN cards c ON c.id = a.card_id
+            WHERE a.quiz_id = $1
+            ORDER BY a.answered_at
+            "#,
+        )
+        .bind(quiz_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_question(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<QuizQuestion> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        let quiz = quiz_session(&self.pool, quiz_id).await?;
+        let card = current_card(
+            &self.pool,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        let eliminated = eliminated_options(&self.pool, &quiz.id, &card.id).await?;
+        let mut options = card_options(&self.pool, &quiz, &card).await?;
+        options.retain(|o| !eliminated.contains(o));
+
+        Ok(QuizQuestion {
+            quiz_id: quiz.id,
+            card_id: card.id,
+            index: quiz.current_index,
+            card_count: card_count(&self.pool, &quiz.course_id, &quiz.category).await?,
+            question: card.question,
+            options,
+        })
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn grade_answer(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        answer: &GradeAnswer,
+    ) -> KeikoResult<GradedAnswer> {
+        validate_elapsed_ms(answer.elapsed_ms)?;
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let quiz = quiz_session(&mut *tx, quiz_id).await?;
+        let card = current_card(
+            &mut *tx,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        if card.id != answer.card_id {
+            return Err(KeikoError::Conflict(
+                "answer is not for the current question".to_owned(),
+            ));
+        }
+
+        let correct = card.answer == answer.given_answer;
+
+        sqlx::query(
+            r#"
+            INSERT INTO quiz_answers (
+                id, quiz_id, card_id, given_answer, correct, hint_used, elapsed_ms
+            )
+            VALUES (
+                $6, $1, $2, $3, $4,
+                EXISTS (SELECT 1 FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2),
+                $5
+            )
+            "#,
+        )
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(&answer.given_answer)
+        .bind(correct)
+        .bind(answer.elapsed_ms)
+        .bind(Uuid::new_v4())
+        .execute(&mut *tx)
+        .await?;
+
+        let card_count = card_count(&mut *tx, &quiz.course_id, &quiz.category).await?;
+        let is_completed = i64::from(quiz.current_index) + 1 >= card_count;
+
+        let quiz = sqlx::query_as::<_, Quiz>(
+            r#"
+            UPDATE quizzes
+            SET current_index = current_index + 1,
+                correct_count = correct_count + $2,
+                hint_used = FALSE,
+                is_completed = $3,
+                completed_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(quiz.id)
+        .bind(i32::from(correct))
+        .bind(is_completed)
+        .bind(is_completed.then(chrono::Utc::now))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        METRICS.count_completion(&quiz);
+
+        Ok(GradedAnswer {
+            correct,
+            correct_answer: card.answer,
+            quiz,
+        })
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn use_hint(
+        &self,
+        user: &User,
+        quiz_id: &Uuid,
+        hint: &RequestHint,
+    ) -> KeikoResult<RevealedHint> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let quiz = quiz_session(&mut *tx, quiz_id).await?;
+        let card = current_card(
+            &mut *tx,
+            &quiz.course_id,
+            &quiz.category,
+            quiz.current_index,
+        )
+        .await?;
+
+        let used =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM quiz_hints WHERE quiz_id = $1")
+                .bind(quiz.id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        let taken = sqlx::query_as::<_, HintUsage>(
+            "SELECT * FROM quiz_hints WHERE quiz_id = $1 AND card_id = $2 AND kind = $3",
+        )
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(hint.kind)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(usage) = taken {
+            return Ok(RevealedHint {
+                usage,
+                hints_remaining: quiz.hint_budget - used as i32,
+            });
+        }
+
+        if used >= i64::from(quiz.hint_budget) {
+            return Err(KeikoError::Conflict("hint budget exhausted".to_owned()));
+        }
+
+        let mut options = card_options(&mut *tx, &quiz, &card).await?;
+        let eliminated = eliminated_options(&mut *tx, &quiz.id, &card.id).await?;
+        options.retain(|o| !eliminated.contains(o));
+
+        let revealed = reveal(hint.kind, &card, &options, quiz.seed)?;
+
+        let usage = sqlx::query_as::<_, HintUsage>(
+            r#"
+            INSERT INTO quiz_hints (id, quiz_id, card_id, kind, revealed)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(quiz.id)
+        .bind(card.id)
+        .bind(hint.kind)
+        .bind(revealed)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE quizzes SET hint_used = TRUE WHERE id = $1")
+            .bind(quiz.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(RevealedHint {
+            usage,
+            hints_remaining: quiz.hint_budget - used as i32 - 1,
+        })
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_hints(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Vec<HintUsage>> {
+        visible_quiz(&self.pool, user, quiz_id).await?;
+
+        sqlx::query_as::<_, HintUsage>(
+            "SELECT * FROM quiz_hints WHERE quiz_id = $1 ORDER BY used_at",
+        )
+        .bind(quiz_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+}
@@ -0,0 +1,84 @@
+//! Gives every request an id, taken from its `X-Request-Id` header or generated, and runs it
+//! inside a `request` span so everything it logs can be told apart from other requests.
+
+use ntex::http::header::{HeaderName, HeaderValue};
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{DefaultError, WebRequest, WebResponse};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest id accepted from a client, longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Uses the client's id if it is short and plain enough to log as is, else generates one.
+fn request_id(req: &WebRequest<DefaultError>) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Middleware that assigns request ids, echoes them in the `X-Request-Id` response header and
+/// logs each request once it is answered, with its status and how long it took.
+pub struct RequestId;
+
+impl<Svc> Middleware<Svc> for RequestId {
+    type Service = RequestIdMiddleware<Svc>;
+
+    fn create(&self, service: Svc) -> Self::Service {
+        RequestIdMiddleware { service }
+    }
+}
+
+pub struct RequestIdMiddleware<Svc> {
+    service: Svc,
+}
+
+impl<Svc> Service<WebRequest<DefaultError>> for RequestIdMiddleware<Svc>
+where
+    Svc: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = Svc::Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let id = request_id(&req);
+        let span = tracing::info_span!(
+            "request",
+            request_id = %id,
+            method = %req.method(),
+            path = %req.path(),
+            status = tracing::field::Empty,
+        );
+
+        let mut res = ctx
+            .call(&self.service, req)
+            .instrument(span.clone())
+            .await?;
+
+        span.record("status", res.status().as_u16());
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            res.headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,39 @@
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
+
+use crate::auth_api::User;
+use crate::extract::{Json, Query};
+use crate::member_api::{self, MemberAPI};
+use crate::review_api::{CreateReview, DueQuery, ReviewAPI};
+use crate::KeikoResult;
+
+pub fn service<S: ReviewAPI + MemberAPI>(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/v1/reviews")
+            .route("", web::post().to(record_review::<S>))
+            .route("/due", web::get().to(get_due_cards::<S>)),
+    );
+}
+
+/// POST /v1/reviews
+async fn record_review<S: ReviewAPI>(
+    review: Json<CreateReview>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let state = stack.record_review(&user, &review).await?;
+    Ok(HttpResponse::Ok().json(&state))
+}
+
+/// GET /v1/reviews/due
+///
+/// Answers and hints are blank in courses the user can only view.
+async fn get_due_cards<S: ReviewAPI + MemberAPI>(
+    query: Query<DueQuery>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    query.validate()?;
+    let mut cards = stack.get_due_cards(&user, &query).await?;
+    member_api::hide_answers(&*stack, &user, &mut cards).await?;
+    Ok(HttpResponse::Ok().json(&cards))
+}
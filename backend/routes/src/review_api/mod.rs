@@ -0,0 +1,139 @@
+pub mod scheduler;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::list::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::member_api::Answered;
+use crate::{KeikoError, KeikoResult};
+
+/// How well a card was recalled, on the four point scale shared by SM-2 and FSRS.
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[repr(i16)]
+pub enum Grade {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ReviewAlgorithm {
+    #[default]
+    Sm2,
+    Fsrs,
+}
+
+/// A user's scheduling state of a single card, created on their first review of it.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Default)]
+pub struct ReviewState {
+    pub card_id: Uuid,
+    pub algorithm: ReviewAlgorithm,
+    /// SM-2 ease factor.
+    pub ease: f64,
+    /// FSRS memory stability, in days.
+    pub stability: f64,
+    /// FSRS difficulty, between 1 and 10.
+    pub difficulty: f64,
+    pub interval_days: i32,
+    pub repetitions: i32,
+    pub lapses: i32,
+    pub due_at: chrono::DateTime<chrono::Utc>,
+    pub last_reviewed_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreateReview {
+    pub card_id: Uuid,
+    pub grade: Grade,
+    /// Defaults to the algorithm the card was last scheduled with, or SM-2.
+    pub algorithm: Option<ReviewAlgorithm>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DueQuery {
+    pub course_code: Option<String>,
+    pub category: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl DueQuery {
+    pub fn validate(&self) -> KeikoResult<()> {
+        if self
+            .limit
+            .is_some_and(|l| !(1..=MAX_PAGE_SIZE).contains(&l))
+        {
+            return Err(KeikoError::Validation(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_SIZE
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+}
+
+/// A card that is due, together with its scheduling state. New cards have no state yet.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct DueCard {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub card: Card,
+    pub due_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub interval_days: Option<i32>,
+    pub repetitions: Option<i32>,
+    pub lapses: Option<i32>,
+}
+
+impl Answered for DueCard {
+    fn course_code(&self) -> &str {
+        &self.card.course_code
+    }
+
+    fn hide_answer(&mut self) {
+        self.card.hide_answer();
+    }
+}
+
+#[async_trait]
+pub trait ReviewAPI: Send + Sync + 'static {
+    async fn record_review(&self, user: &User, review: &CreateReview) -> KeikoResult<ReviewState>;
+    async fn get_due_cards(&self, user: &User, query: &DueQuery) -> KeikoResult<Vec<DueCard>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_query_limit_is_bounded() {
+        let query = |limit| DueQuery {
+            limit,
+            ..Default::default()
+        };
+        assert_eq!(query(None).limit(), DEFAULT_PAGE_SIZE);
+        assert!(query(Some(1)).validate().is_ok());
+        assert!(query(Some(MAX_PAGE_SIZE)).validate().is_ok());
+        assert!(query(Some(0)).validate().is_err());
+        assert!(query(Some(-5)).validate().is_err());
+        assert!(query(Some(MAX_PAGE_SIZE + 1)).validate().is_err());
+    }
+}
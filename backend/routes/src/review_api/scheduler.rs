@@ -0,0 +1,344 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::{Grade, ReviewAlgorithm, ReviewState};
+
+/// A spaced repetition algorithm. Given the previous state of a card (if any) and a grade,
+/// produces the next state.
+pub trait Scheduler: Send + Sync {
+    fn schedule(
+        &self,
+        card_id: Uuid,
+        previous: Option<&ReviewState>,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) -> ReviewState;
+}
+
+impl ReviewAlgorithm {
+    pub fn scheduler(self) -> &'static dyn Scheduler {
+        match self {
+            Self::Sm2 => &Sm2,
+            Self::Fsrs => &Fsrs,
+        }
+    }
+}
+
+/// Longest interval either algorithm schedules, a hundred years, so a card reviewed Easy over
+/// and over never falls due past what a date can hold.
+const MAXIMUM_INTERVAL: f64 = 36_500.0;
+
+/// Days since the card was last reviewed.
+pub(crate) fn elapsed_days(previous: Option<&ReviewState>, now: DateTime<Utc>) -> f64 {
+    previous
+        .map(|p| (now - p.last_reviewed_at).num_seconds().max(0) as f64 / 86_400.0)
+        .unwrap_or(0.0)
+}
+
+fn next_state(
+    card_id: Uuid,
+    previous: Option<&ReviewState>,
+    algorithm: ReviewAlgorithm,
+    now: DateTime<Utc>,
+) -> ReviewState {
+    let previous = previous.cloned().unwrap_or(ReviewState {
+        ease: Sm2::INITIAL_EASE,
+        ..Default::default()
+    });
+
+    ReviewState {
+        card_id,
+        algorithm,
+        last_reviewed_at: now,
+        ..previous
+    }
+}
+
+/// SuperMemo 2, with grades mapped onto its 0-5 quality scale.
+pub struct Sm2;
+
+impl Sm2 {
+    pub const INITIAL_EASE: f64 = 2.5;
+    const MINIMUM_EASE: f64 = 1.3;
+
+    fn quality(grade: Grade) -> f64 {
+        match grade {
+            Grade::Again => 1.0,
+            Grade::Hard => 3.0,
+            Grade::Good => 4.0,
+            Grade::Easy => 5.0,
+        }
+    }
+}
+
+impl Scheduler for Sm2 {
+    fn schedule(
+        &self,
+        card_id: Uuid,
+        previous: Option<&ReviewState>,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) -> ReviewState {
+        let mut state = next_state(card_id, previous, ReviewAlgorithm::Sm2, now);
+        let q = Self::quality(grade);
+
+        if grade == Grade::Again {
+            state.repetitions = 0;
+            state.lapses += 1;
+            state.interval_days = 1;
+        } else {
+            state.interval_days = match state.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (f64::from(state.interval_days) * state.ease)
+                    .round()
+                    .clamp(1.0, MAXIMUM_INTERVAL) as i32,
+            };
+            state.repetitions += 1;
+        }
+
+        state.ease =
+            (state.ease + (0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02))).max(Self::MINIMUM_EASE);
+        state.due_at = now + Duration::days(state.interval_days.into());
+
+        state
+    }
+}
+
+/// FSRS v4.5 with its published default weights and 90% desired retention.
+pub struct Fsrs;
+
+impl Fsrs {
+    const W: [f64; 17] = [
+        0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461,
+        2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+    ];
+    const DECAY: f64 = -0.5;
+    const FACTOR: f64 = 19.0 / 81.0;
+    const RETENTION: f64 = 0.9;
+
+    fn g(grade: Grade) -> f64 {
+        f64::from(grade as i16)
+    }
+
+    fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + Self::FACTOR * elapsed_days / stability).powf(Self::DECAY)
+    }
+
+    fn initial_difficulty(grade: Grade) -> f64 {
+        (Self::W[4] - (Self::g(grade) - 3.0) * Self::W[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(difficulty: f64, grade: Grade) -> f64 {
+        let next = difficulty - Self::W[6] * (Self::g(grade) - 3.0);
+        let reverted =
+            Self::W[7] * Self::initial_difficulty(Grade::Easy) + (1.0 - Self::W[7]) * next;
+
+        reverted.clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(difficulty: f64, stability: f64, r: f64, grade: Grade) -> f64 {
+        let hard_penalty = if grade == Grade::Hard {
+            Self::W[15]
+        } else {
+            1.0
+        };
+        let easy_bonus = if grade == Grade::Easy {
+            Self::W[16]
+        } else {
+            1.0
+        };
+
+        stability
+            * (Self::W[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-Self::W[9])
+                * ((Self::W[10] * (1.0 - r)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn forget_stability(difficulty: f64, stability: f64, r: f64) -> f64 {
+        Self::W[11]
+            * difficulty.powf(-Self::W[12])
+            * ((stability + 1.0).powf(Self::W[13]) - 1.0)
+            * (Self::W[14] * (1.0 - r)).exp()
+    }
+
+    fn interval(stability: f64) -> i32 {
+        let days = stability / Self::FACTOR * (Self::RETENTION.powf(1.0 / Self::DECAY) - 1.0);
+
+        days.round().clamp(1.0, MAXIMUM_INTERVAL) as i32
+    }
+}
+
+impl Scheduler for Fsrs {
+    fn schedule(
+        &self,
+        card_id: Uuid,
+        previous: Option<&ReviewState>,
+        grade: Grade,
+        now: DateTime<Utc>,
+    ) -> ReviewState {
+        let elapsed = elapsed_days(previous, now);
+        let mut state = next_state(card_id, previous, ReviewAlgorithm::Fsrs, now);
+
+        if state.stability <= 0.0 {
+            state.stability = Self::W[grade as usize - 1];
+            state.difficulty = Self::initial_difficulty(grade);
+        } else {
+            let r = Self::retrievability(elapsed, state.stability);
+
+            state.stability = if grade == Grade::Again {
+                Self::forget_stability(state.difficulty, state.stability, r)
+            } else {
+                Self::recall_stability(state.difficulty, state.stability, r, grade)
+            };
+            state.difficulty = Self::next_difficulty(state.difficulty, grade);
+        }
+
+        if grade == Grade::Again {
+            state.repetitions = 0;
+            state.lapses += 1;
+        } else {
+            state.repetitions += 1;
+        }
+
+        state.interval_days = Self::interval(state.stability);
+        state.due_at = now + Duration::days(state.interval_days.into());
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(days: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::days(days)
+    }
+
+    /// Reviews a new card with each grade in turn, a day after it fell due.
+    fn review(algorithm: ReviewAlgorithm, grades: &[Grade]) -> ReviewState {
+        let card_id = Uuid::nil();
+        let mut state: Option<ReviewState> = None;
+        for grade in grades {
+            let now = state
+                .as_ref()
+                .map_or(at(0), |s| s.due_at + Duration::days(1));
+            state = Some(
+                algorithm
+                    .scheduler()
+                    .schedule(card_id, state.as_ref(), *grade, now),
+            );
+        }
+        state.unwrap()
+    }
+
+    #[test]
+    fn sm2_follows_one_six_then_ease() {
+        let first = review(ReviewAlgorithm::Sm2, &[Grade::Good]);
+        assert_eq!(first.interval_days, 1);
+        assert_eq!(first.repetitions, 1);
+        assert_eq!(first.due_at, at(1));
+
+        let second = review(ReviewAlgorithm::Sm2, &[Grade::Good, Grade::Good]);
+        assert_eq!(second.interval_days, 6);
+
+        let third = review(
+            ReviewAlgorithm::Sm2,
+            &[Grade::Good, Grade::Good, Grade::Good],
+        );
+        assert_eq!(third.interval_days, (6.0 * second.ease).round() as i32);
+        assert_eq!(third.repetitions, 3);
+    }
+
+    #[test]
+    fn sm2_ease_moves_with_grade() {
+        assert_eq!(review(ReviewAlgorithm::Sm2, &[Grade::Good]).ease, 2.5);
+        assert!(review(ReviewAlgorithm::Sm2, &[Grade::Easy]).ease > 2.5);
+        assert!(review(ReviewAlgorithm::Sm2, &[Grade::Hard]).ease < 2.5);
+    }
+
+    #[test]
+    fn sm2_lapse_resets_repetitions_and_keeps_minimum_ease() {
+        let state = review(
+            ReviewAlgorithm::Sm2,
+            &[Grade::Good, Grade::Good, Grade::Again],
+        );
+        assert_eq!(state.repetitions, 0);
+        assert_eq!(state.lapses, 1);
+        assert_eq!(state.interval_days, 1);
+
+        let state = review(ReviewAlgorithm::Sm2, &[Grade::Again; 10]);
+        assert_eq!(state.ease, Sm2::MINIMUM_EASE);
+        assert_eq!(state.lapses, 10);
+    }
+
+    #[test]
+    fn fsrs_starts_from_default_weights() {
+        for (i, grade) in [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy]
+            .into_iter()
+            .enumerate()
+        {
+            let state = review(ReviewAlgorithm::Fsrs, &[grade]);
+            assert_eq!(state.stability, Fsrs::W[i]);
+            assert_eq!(state.difficulty, Fsrs::initial_difficulty(grade));
+            assert_eq!(state.algorithm, ReviewAlgorithm::Fsrs);
+        }
+    }
+
+    #[test]
+    fn fsrs_grows_on_recall_and_shrinks_on_lapse() {
+        let good = review(ReviewAlgorithm::Fsrs, &[Grade::Good, Grade::Good]);
+        let better = review(
+            ReviewAlgorithm::Fsrs,
+            &[Grade::Good, Grade::Good, Grade::Good],
+        );
+        assert!(better.stability > good.stability);
+        assert!(better.interval_days > good.interval_days);
+
+        let lapsed = review(
+            ReviewAlgorithm::Fsrs,
+            &[Grade::Good, Grade::Good, Grade::Again],
+        );
+        assert!(lapsed.stability < good.stability);
+        assert_eq!(lapsed.repetitions, 0);
+        assert_eq!(lapsed.lapses, 1);
+        assert!(lapsed.interval_days >= 1);
+    }
+
+    #[test]
+    fn fsrs_difficulty_stays_in_range() {
+        let hard = review(ReviewAlgorithm::Fsrs, &[Grade::Again; 20]);
+        assert!(hard.difficulty <= 10.0);
+        let easy = review(ReviewAlgorithm::Fsrs, &[Grade::Easy; 20]);
+        assert!(easy.difficulty >= 1.0);
+        assert!(easy.interval_days <= MAXIMUM_INTERVAL as i32);
+    }
+
+    #[test]
+    fn schedules_are_deterministic() {
+        for algorithm in [ReviewAlgorithm::Sm2, ReviewAlgorithm::Fsrs] {
+            let grades = [Grade::Good, Grade::Hard, Grade::Again, Grade::Easy];
+            assert_eq!(review(algorithm, &grades), review(algorithm, &grades));
+        }
+    }
+
+    #[test]
+    fn elapsed_days_counts_from_last_review() {
+        assert_eq!(elapsed_days(None, at(3)), 0.0);
+        let state = review(ReviewAlgorithm::Sm2, &[Grade::Good]);
+        assert_eq!(elapsed_days(Some(&state), at(3)), 3.0);
+        assert_eq!(elapsed_days(Some(&state), at(-1)), 0.0);
+    }
+
+    #[test]
+    fn sm2_interval_is_bounded() {
+        let easy = review(ReviewAlgorithm::Sm2, &[Grade::Easy; 40]);
+        assert_eq!(easy.interval_days, MAXIMUM_INTERVAL as i32);
+        assert_eq!(easy.repetitions, 40);
+    }
+}
@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+
+use super::scheduler::elapsed_days;
+use super::{CreateReview, DueCard, DueQuery, ReviewAPI, ReviewState};
+use crate::access;
+use crate::auth_api::User;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+#[async_trait]
+impl ReviewAPI for KeikoDatabase {
+    /// POST /v1/reviews
+    async fn record_review(&self, user: &User, review: &CreateReview) -> KeikoResult<ReviewState> {
+        let mut tx = self.pool.begin().await?;
+
+        access::card(&mut *tx, user, &review.card_id).await?;
+
+        let previous = sqlx::query_as::<_, ReviewState>(
+            "SELECT * FROM card_reviews WHERE card_id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(review.card_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let now = chrono::Utc::now();
+        let algorithm = review
+            .algorithm
+            .or(previous.as_ref().map(|p| p.algorithm))
+            .unwrap_or_default();
+        let state =
+            algorithm
+                .scheduler()
+                .schedule(review.card_id, previous.as_ref(), review.grade, now);
+
+        let state = sqlx::query_as::<_, ReviewState>(
+            r#"
+            INSERT INTO card_reviews (
+                card_id, algorithm, ease, stability, difficulty, interval_days,
+                repetitions, lapses, due_at, last_reviewed_at, user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (user_id, card_id) DO UPDATE
+            SET algorithm = $2, ease = $3, stability = $4, difficulty = $5, interval_days = $6,
+                repetitions = $7, lapses = $8, due_at = $9, last_reviewed_at = $10
+            RETURNING *
+            "#,
+        )
+        .bind(state.card_id)
+        .bind(state.algorithm)
+        .bind(state.ease)
+        .bind(state.stability)
+        .bind(state.difficulty)
+        .bind(state.interval_days)
+        .bind(state.repetitions)
+        .bind(state.lapses)
+        .bind(state.due_at)
+        .bind(state.last_reviewed_at)
+        .bind(user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (
+                card_id, algorithm, grade, elapsed_days, interval_days, reviewed_at, user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(review.card_id)
+        .bind(algorithm)
+        .bind(review.grade)
+        .bind(elapsed_days(previous.as_ref(), now))
+        .bind(state.interval_days)
+        .bind(now)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(state)
+    }
+
+    /// GET /v1/reviews/due
+    async fn get_due_cards(&self, user: &User, query: &DueQuery) -> KeikoResult<Vec<DueCard>> {
+        sqlx::query_as::<_, DueCard>(
+            r#"
+            SELECT c.*, r.due_at, r.interval_days, r.repetitions, r.lapses
+            FROM cards c
+            JOIN courses co ON co.id = c.course_id
+            LEFT JOIN card_reviews r ON r.card_id = c.id AND r.user_id = $4
+            WHERE (r.due_at IS NULL OR r.due_at <= now())
+            AND c.deleted_at IS NULL
+            AND course_role(co.id, $4) IS NOT NULL
+            AND ($1::text IS NULL OR c.course_code = $1)
+            AND ($2::text IS NULL OR c.category = $2)
+            ORDER BY r.due_at NULLS LAST, c.created_at
+            LIMIT $3
+            "#,
+        )
+        .bind(&query.course_code)
+        .bind(&query.category)
+        .bind(query.limit())
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+}
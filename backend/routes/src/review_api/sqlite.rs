@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::scheduler::elapsed_days;
+use super::{CreateReview, DueCard, DueQuery, ReviewAPI, ReviewState};
+use crate::auth_api::User;
+use crate::sqlite::{visible_card, KeikoSqlite};
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl ReviewAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn record_review(&self, user: &User, review: &CreateReview) -> KeikoResult<ReviewState> {
+        let mut tx = self.pool.begin().await?;
+
+        visible_card(&mut *tx, user, &review.card_id).await?;
+
+        let previous = sqlx::query_as::<_, ReviewState>(
+            "SELECT * FROM card_reviews WHERE card_id = $1 AND user_id = $2",
+        )
+        .bind(review.card_id)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let now = chrono::Utc::now();
+        let algorithm = review
+            .algorithm
+            .or(previous.as_ref().map(|p| p.algorithm))
+            .unwrap_or_default();
+        let state =
+            algorithm
+                .scheduler()
+                .schedule(review.card_id, previous.as_ref(), review.grade, now);
+
+        let state = sqlx::query_as::<_, ReviewState>(
+            r#"
+            INSERT INTO card_reviews (
+                card_id, algorithm, ease, stability, difficulty, interval_days,
+                repetitions, lapses, due_at, last_reviewed_at, user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (user_id, card_id) DO UPDATE
+            SET algorithm = $2, ease = $3, stability = $4, difficulty = $5, interval_days = $6,
+                repetitions = $7, lapses = $8, due_at = $9, last_reviewed_at = $10
+            RETURNING *
+            "#,
+        )
+        .bind(state.card_id)
+        .bind(state.algorithm)
+        .bind(state.ease)
+        .bind(state.stability)
+        .bind(state.difficulty)
+        .bind(state.interval_days)
+        .bind(state.repetitions)
+        .bind(state.lapses)
+        .bind(state.due_at)
+        .bind(state.last_reviewed_at)
+        .bind(user.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (
+                id, card_id, algorithm, grade, elapsed_days, interval_days, reviewed_at, user_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(review.card_id)
+        .bind(algorithm)
+        .bind(review.grade)
+        .bind(elapsed_days(previous.as_ref(), now))
+        .bind(state.interval_days)
+        .bind(now)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(state)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_due_cards(&self, user: &User, query: &DueQuery) -> KeikoResult<Vec<DueCard>> {
+        sqlx::query_as::<_, DueCard>(
+            r#"
+            SELECT c.*, r.due_at, r.interval_days, r.repetitions, r.lapses
+            FROM cards c
+            LEFT JOIN card_reviews r ON r.card_id = c.id AND r.user_id = $4
+            WHERE (r.due_at IS NULL OR r.due_at <= $5)
+            AND c.deleted_at IS NULL
+            AND c.course_id IN (SELECT course_id FROM course_roles WHERE user_id = $4)
+            AND ($1 IS NULL OR c.course_code = $1)
+            AND ($2 IS NULL OR c.category = $2)
+            ORDER BY r.due_at IS NULL, r.due_at, c.created_at
+            LIMIT $3
+            "#,
+        )
+        .bind(&query.course_code)
+        .bind(&query.category)
+        .bind(query.limit())
+        .bind(user.id)
+        .bind(chrono::Utc::now())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+}
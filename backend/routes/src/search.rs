@@ -0,0 +1,25 @@
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
+
+use crate::auth_api::User;
+use crate::extract::Query;
+use crate::member_api::{self, MemberAPI};
+use crate::search_api::{SearchAPI, SearchQuery};
+use crate::KeikoResult;
+
+pub fn service<S: SearchAPI + MemberAPI>(cfg: &mut ServiceConfig) {
+    cfg.service(web::scope("/v1/search").route("", web::get().to(search::<S>)));
+}
+
+/// GET /v1/search
+///
+/// Card snippets are blank in courses the user can only view.
+async fn search<S: SearchAPI + MemberAPI>(
+    query: Query<SearchQuery>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    query.validate()?;
+    let mut hits = stack.search(&user, &query).await?;
+    member_api::hide_answers(&*stack, &user, &mut hits).await?;
+    Ok(HttpResponse::Ok().json(&hits))
+}
@@ -0,0 +1,105 @@
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::member_api::Answered;
+use crate::{KeikoError, KeikoResult};
+
+pub const DEFAULT_SEARCH_LIMIT: i64 = 50;
+pub const MAX_SEARCH_LIMIT: i64 = 200;
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum SearchKind {
+    Card,
+    Course,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchQuery {
+    /// Search terms, in web search syntax: `"exact phrase"`, `or`, and `-excluded`.
+    pub q: String,
+    pub course_code: Option<String>,
+    /// Only cards have a category, so setting it leaves courses out of the results.
+    pub category: Option<String>,
+    pub limit: Option<i64>,
+}
+
+impl SearchQuery {
+    pub fn validate(&self) -> KeikoResult<()> {
+        if self
+            .limit
+            .is_some_and(|l| !(1..=MAX_SEARCH_LIMIT).contains(&l))
+        {
+            return Err(KeikoError::Validation(format!(
+                "limit must be between 1 and {}",
+                MAX_SEARCH_LIMIT
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT)
+    }
+}
+
+/// A matching card or course. `title` and `snippet` are HTML: the text is escaped and matched
+/// terms are wrapped in `<mark>`, so they can be rendered as they are.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: Uuid,
+    pub course_code: String,
+    pub category: Option<String>,
+    /// The card question or the course name.
+    pub title: String,
+    /// The card answer or the course description.
+    pub snippet: String,
+    pub rank: f32,
+}
+
+impl Answered for SearchHit {
+    fn course_code(&self) -> &str {
+        &self.course_code
+    }
+
+    fn hide_answer(&mut self) {
+        if self.kind == SearchKind::Card {
+            self.snippet.clear();
+        }
+    }
+}
+
+#[async_trait]
+pub trait SearchAPI: Send + Sync + 'static {
+    async fn search(&self, user: &User, query: &SearchQuery) -> KeikoResult<Vec<SearchHit>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_query_limit_is_bounded() {
+        let query = |limit| SearchQuery {
+            limit,
+            ..Default::default()
+        };
+        assert_eq!(query(None).limit(), DEFAULT_SEARCH_LIMIT);
+        assert!(query(Some(1)).validate().is_ok());
+        assert!(query(Some(MAX_SEARCH_LIMIT)).validate().is_ok());
+        assert!(query(Some(0)).validate().is_err());
+        assert!(query(Some(MAX_SEARCH_LIMIT + 1)).validate().is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+
+use super::{SearchAPI, SearchHit, SearchQuery};
+use crate::auth_api::User;
+use crate::{KeikoDatabase, KeikoError, KeikoResult};
+
+#[async_trait]
+impl SearchAPI for KeikoDatabase {
+    /// GET /v1/search
+    async fn search(&self, user: &User, query: &SearchQuery) -> KeikoResult<Vec<SearchHit>> {
+        if query.q.trim().is_empty() {
+            return Err(KeikoError::Validation("q must not be empty".to_owned()));
+        }
+
+        sqlx::query_as::<_, SearchHit>(
+            r#"
+            WITH q AS (SELECT websearch_to_tsquery('english', $1) AS query)
+            SELECT * FROM (
+                SELECT
+                    'card' AS kind,
+                    c.id,
+                    c.course_code,
+                    c.category,
+                    ts_headline('english', html_escape(c.question), q.query, $5) AS title,
+                    ts_headline('english', html_escape(c.answer), q.query, $5) AS snippet,
+                    ts_rank_cd(c.search, q.query) AS rank
+                FROM cards c
+                JOIN courses co ON co.id = c.course_id, q
+                WHERE c.search @@ q.query AND c.deleted_at IS NULL
+                AND course_role(co.id, $6) IS NOT NULL
+                AND ($2::text IS NULL OR c.course_code = $2)
+                AND ($3::text IS NULL OR c.category = $3)
+                UNION ALL
+                SELECT
+                    'course' AS kind,
+                    co.id,
+                    co.course_code,
+                    NULL AS category,
+                    ts_headline('english', html_escape(co.name), q.query, $5) AS title,
+                    ts_headline('english', html_escape(co.description), q.query, $5) AS snippet,
+                    ts_rank_cd(co.search, q.query) AS rank
+                FROM courses co, q
+                WHERE co.search @@ q.query
+                AND course_role(co.id, $6) IS NOT NULL
+                AND ($2::text IS NULL OR co.course_code = $2)
+                AND $3::text IS NULL
+            ) hits
+            ORDER BY rank DESC, title
+            LIMIT $4
+            "#,
+        )
+        .bind(query.q.trim())
+        .bind(&query.course_code)
+        .bind(&query.category)
+        .bind(query.limit())
+        .bind("StartSel=<mark>, StopSel=</mark>, MaxFragments=2, HighlightAll=false")
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+}
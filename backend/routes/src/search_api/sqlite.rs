@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use super::{SearchAPI, SearchHit, SearchQuery};
+use crate::auth_api::User;
+use crate::sqlite::KeikoSqlite;
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl SearchAPI for KeikoSqlite {
+    /// Search ranks and highlights with Postgres full text search, which SQLite has no
+    /// counterpart of.
+    async fn search(&self, _: &User, _: &SearchQuery) -> KeikoResult<Vec<SearchHit>> {
+        Err(KeikoError::Unsupported(
+            "search needs the Postgres backend".to_owned(),
+        ))
+    }
+}
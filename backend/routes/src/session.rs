@@ -0,0 +1,106 @@
+//! Resolves the session or personal access token of each request to a `User`.
+
+use std::rc::Rc;
+
+use ntex::http::header::{HeaderMap, AUTHORIZATION};
+use ntex::http::Payload;
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{DefaultError, FromRequest, HttpRequest, WebRequest, WebResponse};
+
+use crate::auth_api::{AuthAPI, User, API_TOKEN_PREFIX};
+use crate::KeikoError;
+
+/// Extracts the token from an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// Middleware that attaches the `User` owning the request's session or personal access token,
+/// if any. Handlers that take a `User` argument reject requests without one. Requests beyond
+/// the scope of a personal access token are rejected here.
+pub struct Authentication<S> {
+    stack: Rc<S>,
+}
+
+impl<S> Authentication<S> {
+    pub fn new(stack: S) -> Self {
+        Self {
+            stack: Rc::new(stack),
+        }
+    }
+}
+
+impl<S, Svc> Middleware<Svc> for Authentication<S> {
+    type Service = AuthenticationMiddleware<S, Svc>;
+
+    fn create(&self, service: Svc) -> Self::Service {
+        AuthenticationMiddleware {
+            service,
+            stack: self.stack.clone(),
+        }
+    }
+}
+
+pub struct AuthenticationMiddleware<S, Svc> {
+    service: Svc,
+    stack: Rc<S>,
+}
+
+impl<S, Svc> Service<WebRequest<DefaultError>> for AuthenticationMiddleware<S, Svc>
+where
+    S: AuthAPI,
+    Svc: Service<WebRequest<DefaultError>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = Svc::Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<DefaultError>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Some(token) = bearer_token(req.headers()) {
+            let user = if token.starts_with(API_TOKEN_PREFIX) {
+                match self.stack.authenticate_api_token(token).await {
+                    Ok(Some((_, scope))) if !scope.allows(req.method(), req.path()) => {
+                        return Ok(req.error_response(KeikoError::Forbidden(
+                            "the token's scope does not allow this request".to_owned(),
+                        )));
+                    }
+                    Ok(found) => found.map(|(user, _)| user),
+                    Err(e) => return Ok(req.error_response(e)),
+                }
+            } else {
+                match self.stack.authenticate(token).await {
+                    Ok(found) => found,
+                    Err(e) => return Ok(req.error_response(e)),
+                }
+            };
+
+            if let Some(user) = user {
+                req.extensions_mut().insert(user);
+            }
+        }
+
+        ctx.call(&self.service, req).await
+    }
+}
+
+impl<Err> FromRequest<Err> for User {
+    type Error = KeikoError;
+
+    async fn from_request(req: &HttpRequest, _: &mut Payload) -> Result<Self, Self::Error> {
+        req.extensions().get::<User>().cloned().ok_or_else(|| {
+            KeikoError::Unauthorized("a valid session or API token is required".to_owned())
+        })
+    }
+}
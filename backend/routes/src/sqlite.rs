@@ -0,0 +1,340 @@
+//! `KeikoSqlite`, and the parts of the Postgres schema SQLite has no place for: the
+//! `delete_quiz()`, `delete_course()`, `ensure_category()` and `category_lock()` functions,
+//! and the checks in `access`.
+
+use std::str::FromStr;
+
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Connection, SqliteConnection, SqliteExecutor, SqlitePool};
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::{KeikoError, KeikoResult};
+
+/// Schema of SQLite databases, embedded from `routes/migrations-sqlite`.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations-sqlite");
+
+/// A backend that keeps everything in a single SQLite file, for installs without a Postgres
+/// server. It serves everything but search, which needs Postgres.
+/// Lists are read whole and paged with `Listing::page`, as a single file holds little enough.
+#[derive(Clone)]
+pub struct KeikoSqlite {
+    pub(crate) pool: SqlitePool,
+}
+
+impl KeikoSqlite {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Opens the database at `url`, such as `sqlite://keiko.db`, creating the file if it does
+    /// not exist yet, and brings its schema up to date.
+    pub async fn connect(url: &str, pool_options: SqlitePoolOptions) -> KeikoResult<Self> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal);
+
+        // Migrations that rebuild a table drop the old one, which foreign keys would cascade
+        // into the rows referring to it, so they run on a connection without them.
+        let mut conn = SqliteConnection::connect_with(&options.clone().foreign_keys(false)).await?;
+        MIGRATOR.run(&mut conn).await?;
+        let violations = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&mut conn)
+            .await?;
+        conn.close().await?;
+
+        if !violations.is_empty() {
+            return Err(KeikoError::Database(format!(
+                "{} rows break foreign keys after migrating",
+                violations.len()
+            )));
+        }
+
+        let pool = pool_options
+            .connect_with(options.foreign_keys(true))
+            .await?;
+
+        Ok(Self::new(pool))
+    }
+}
+
+/// The id of the course, failing with not found unless `user` is a member of it.
+pub(crate) async fn visible_course<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user: &User,
+    course_code: &str,
+) -> KeikoResult<Uuid> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM courses
+        WHERE course_code = $1 AND id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+        "#,
+    )
+    .bind(course_code)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| KeikoError::not_found("course"))
+}
+
+/// Fails with not found unless `user` is a member of the card's course.
+pub(crate) async fn visible_card<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user: &User,
+    card_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT c.id
+        FROM cards c
+        JOIN courses co ON co.id = c.course_id
+        WHERE c.id = $1 AND c.deleted_at IS NULL AND co.deleted_at IS NULL
+        AND co.id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+        "#,
+    )
+    .bind(card_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| KeikoError::not_found("card"))
+}
+
+/// Fails with not found unless `user` owns the quiz.
+pub(crate) async fn visible_quiz<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user: &User,
+    quiz_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT q.id
+        FROM quizzes q
+        JOIN courses co ON co.id = q.course_id
+        WHERE q.id = $1 AND q.user_id = $2
+        AND q.deleted_at IS NULL AND co.deleted_at IS NULL
+        "#,
+    )
+    .bind(quiz_id)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| KeikoError::not_found("quiz"))
+}
+
+/// `delete_quiz()`: moves the quiz to the trash. The cards of its category stay.
+pub(crate) async fn delete_quiz(pool: &SqlitePool, quiz_id: &Uuid) -> KeikoResult<Option<Uuid>> {
+    sqlx::query_scalar::<_, Uuid>(
+        "UPDATE quizzes SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL RETURNING id",
+    )
+    .bind(quiz_id)
+    .bind(chrono::Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// `delete_course()`: moves the course to the trash, which hides its quizzes and cards with it.
+pub(crate) async fn delete_course(
+    pool: &SqlitePool,
+    course_id: &Uuid,
+) -> KeikoResult<Option<Uuid>> {
+    sqlx::query_scalar::<_, Uuid>(
+        "UPDATE courses SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL RETURNING id",
+    )
+    .bind(course_id)
+    .bind(chrono::Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(KeikoError::from)
+}
+
+/// `ensure_category()`: adds a category to the end of a course unless it already has one by
+/// that name.
+pub(crate) async fn ensure_category<'e>(
+    executor: impl SqliteExecutor<'e>,
+    course_id: &Uuid,
+    name: &str,
+) -> KeikoResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO categories (id, course_id, name, position)
+        SELECT $1, $2, $3, COALESCE(MAX(position) + 1, 0)
+        FROM categories
+        WHERE course_id = $2
+        ON CONFLICT (course_id, name) DO NOTHING
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(course_id)
+    .bind(name)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// `add_card_revision()`: records what the card says now as its next revision, by `user`.
+pub(crate) async fn add_card_revision<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user: &User,
+    card_id: &Uuid,
+) -> KeikoResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO card_revisions (card_id, revision, question, answer, category, hint, user_id)
+        SELECT
+            c.id,
+            COALESCE((SELECT MAX(r.revision) FROM card_revisions r WHERE r.card_id = c.id), 0) + 1,
+            c.question,
+            c.answer,
+            c.category,
+            c.hint,
+            $2
+        FROM cards c
+        WHERE c.id = $1
+        "#,
+    )
+    .bind(card_id)
+    .bind(user.id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// `category_lock()`: name of the category `user` must still complete a quiz on before
+/// starting one on the given category, `None` if it is unlocked.
+pub(crate) async fn category_lock<'e>(
+    executor: impl SqliteExecutor<'e>,
+    user: &User,
+    course_id: &Uuid,
+    name: &str,
+) -> KeikoResult<Option<String>> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT r.name
+        FROM categories c
+        JOIN categories r ON r.id = c.unlock_after
+        WHERE c.course_id = $1 AND c.name = $2
+        AND NOT EXISTS (
+            SELECT 1
+            FROM quizzes q
+            WHERE q.course_id = r.course_id AND q.category = r.name AND q.is_completed
+            AND q.deleted_at IS NULL AND q.user_id = $3
+        )
+        "#,
+    )
+    .bind(course_id)
+    .bind(name)
+    .bind(user.id)
+    .fetch_optional(executor)
+    .await
+    .map_err(KeikoError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::migrate::Migrate;
+    use sqlx::Row;
+
+    use super::*;
+    use crate::migrate::assert_paired;
+
+    /// Tables, their columns, foreign keys and indexes, and views, as SQLite reports them.
+    /// Compared rather than the `CREATE` statements, which keep whatever spacing they were
+    /// written with.
+    async fn schema(conn: &mut SqliteConnection) -> Vec<String> {
+        let objects = sqlx::query(
+            r#"
+            SELECT type, name FROM sqlite_master
+            WHERE name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations'
+            ORDER BY type, name
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap();
+
+        let mut schema = Vec::new();
+        for object in objects {
+            let kind: String = object.get("type");
+            let name: String = object.get("name");
+            schema.push(format!("{kind} {name}"));
+            if kind != "table" {
+                continue;
+            }
+
+            let details = sqlx::query(
+                r#"
+                SELECT 'column ' || name || ' ' || type || ' ' || "notnull" || ' '
+                    || coalesce(dflt_value, '') || ' ' || pk
+                FROM pragma_table_info($1)
+                UNION ALL
+                SELECT 'foreign key ' || "from" || ' ' || "table" || '.' || coalesce("to", '')
+                    || ' ' || on_update || ' ' || on_delete
+                FROM pragma_foreign_key_list($1)
+                UNION ALL
+                SELECT 'index ' || name || ' ' || "unique" FROM pragma_index_list($1)
+                "#,
+            )
+            .bind(&name)
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+            schema.extend(
+                details
+                    .iter()
+                    .map(|d| format!("{name}: {}", d.get::<String, _>(0))),
+            );
+        }
+        schema.sort();
+
+        schema
+    }
+
+    #[test]
+    fn migrations_come_in_numbered_pairs() {
+        assert_paired(&MIGRATOR);
+    }
+
+    #[ntex::test]
+    async fn every_migration_is_undone_by_its_down() {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(false);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        conn.ensure_migrations_table().await.unwrap();
+
+        for up in MIGRATOR
+            .iter()
+            .filter(|m| m.migration_type.is_up_migration())
+        {
+            let down = MIGRATOR
+                .iter()
+                .find(|m| m.version == up.version && m.migration_type.is_down_migration())
+                .unwrap();
+
+            let before = schema(&mut conn).await;
+            conn.apply(up).await.unwrap();
+            let after = schema(&mut conn).await;
+            conn.revert(down).await.unwrap();
+            assert_eq!(schema(&mut conn).await, before, "{} down", up.description);
+            conn.apply(up).await.unwrap();
+            assert_eq!(
+                schema(&mut conn).await,
+                after,
+                "{} up again",
+                up.description
+            );
+        }
+
+        let violations = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert!(violations.is_empty());
+    }
+}
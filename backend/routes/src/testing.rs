@@ -0,0 +1,151 @@
+//! The routes served over `InMemoryKeiko`, for handler tests that go through the same
+//! middleware and extractors as a real request.
+
+use std::fmt::Debug;
+
+use ntex::http::{header, Method, Request, StatusCode};
+use ntex::service::{Pipeline, Service};
+use ntex::web::{self, test, App, WebResponse};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::session::Authentication;
+use crate::{auth, card, category, course, health, import, quiz, trash, InMemoryKeiko};
+
+pub(crate) struct TestApp<Svc> {
+    app: Pipeline<Svc>,
+    pub stack: InMemoryKeiko,
+}
+
+/// Mounts the routes `InMemoryKeiko` implements the way `main` mounts them.
+pub(crate) async fn app(
+) -> TestApp<impl Service<Request, Response = WebResponse, Error = impl Debug>> {
+    let stack = InMemoryKeiko::new();
+    let app = test::init_service(
+        App::new().state(stack.clone()).service(
+            web::scope("/api")
+                .wrap(Authentication::new(stack.clone()))
+                .state(stack.clone())
+                .configure(health::service::<InMemoryKeiko>)
+                .configure(auth::service::<InMemoryKeiko>)
+                .configure(card::service::<InMemoryKeiko>)
+                .configure(category::service::<InMemoryKeiko>)
+                .configure(course::service::<InMemoryKeiko>)
+                .configure(import::service::<InMemoryKeiko>)
+                .configure(quiz::service::<InMemoryKeiko>)
+                .configure(trash::service::<InMemoryKeiko>),
+        ),
+    )
+    .await;
+
+    TestApp { app, stack }
+}
+
+impl<Svc, E> TestApp<Svc>
+where
+    Svc: Service<Request, Response = WebResponse, Error = E>,
+    E: Debug,
+{
+    /// Sends a request to `/api{path}`, with `body` as JSON unless it is null, and returns the
+    /// status and the JSON body, null when the body is empty.
+    pub async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+        body: impl Serialize,
+    ) -> (StatusCode, Value) {
+        let mut request = test::TestRequest::default()
+            .method(method)
+            .uri(&format!("/api{path}"));
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let body = serde_json::to_value(body).unwrap();
+        if !body.is_null() {
+            request = request.set_json(&body);
+        }
+
+        self.respond(request.to_request()).await
+    }
+
+    /// Sends a raw body, for the imports.
+    pub async fn upload(&self, path: &str, token: &str, body: &'static str) -> (StatusCode, Value) {
+        let request = test::TestRequest::post()
+            .uri(&format!("/api{path}"))
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .set_payload(body)
+            .to_request();
+
+        self.respond(request).await
+    }
+
+    async fn respond(&self, request: Request) -> (StatusCode, Value) {
+        let response = test::call_service(&self.app, request).await;
+        let status = response.status();
+        let body = test::read_body(response).await;
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&body).unwrap()
+        };
+
+        (status, body)
+    }
+
+    pub async fn get(&self, path: &str, token: &str) -> (StatusCode, Value) {
+        self.send(Method::GET, path, Some(token), ()).await
+    }
+
+    pub async fn post(&self, path: &str, token: &str, body: impl Serialize) -> (StatusCode, Value) {
+        self.send(Method::POST, path, Some(token), body).await
+    }
+
+    pub async fn put(&self, path: &str, token: &str, body: impl Serialize) -> (StatusCode, Value) {
+        self.send(Method::PUT, path, Some(token), body).await
+    }
+
+    pub async fn delete(&self, path: &str, token: &str) -> (StatusCode, Value) {
+        self.send(Method::DELETE, path, Some(token), ()).await
+    }
+
+    /// Registers `username` and returns a session token for them.
+    pub async fn login(&self, username: &str) -> String {
+        let credentials = json!({ "username": username, "password": "correct horse" });
+        let (status, _) = self
+            .send(Method::POST, "/v1/auth/register", None, &credentials)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, session) = self
+            .send(Method::POST, "/v1/auth/login", None, &credentials)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        session["token"].as_str().unwrap().to_owned()
+    }
+
+    /// Creates a course and returns it.
+    pub async fn course(&self, token: &str, course_code: &str) -> Value {
+        let course = json!({
+            "name": format!("Course {course_code}"),
+            "course_code": course_code,
+            "description": "",
+        });
+        let (status, course) = self.post("/v1/courses", token, course).await;
+        assert_eq!(status, StatusCode::OK, "{course}");
+        course
+    }
+
+    /// Creates a card and returns it.
+    pub async fn card(&self, token: &str, course_code: &str, category: &str, n: u32) -> Value {
+        let card = json!({
+            "question": format!("Question {n}"),
+            "answer": format!("Answer {n}"),
+            "course_code": course_code,
+            "category": category,
+        });
+        let (status, card) = self.post("/v1/cards", token, card).await;
+        assert_eq!(status, StatusCode::OK, "{card}");
+        card
+    }
+}
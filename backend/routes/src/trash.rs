@@ -0,0 +1,147 @@
+use ntex::web::{self, types::State, HttpResponse, ServiceConfig};
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::extract::Path;
+use crate::member_api::{self, CourseRef, MemberAPI, Role};
+use crate::trash_api::TrashAPI;
+use crate::KeikoResult;
+
+pub fn service<S: TrashAPI + MemberAPI>(cfg: &mut ServiceConfig) {
+    cfg.service(
+        web::scope("/v1/trash")
+            .route("", web::get().to(get_trash::<S>))
+            .route(
+                "/courses/{course_id}/restore",
+                web::post().to(restore_course::<S>),
+            )
+            .route(
+                "/cards/{card_id}/restore",
+                web::post().to(restore_card::<S>),
+            )
+            .route(
+                "/quizzes/{quiz_id}/restore",
+                web::post().to(restore_quiz::<S>),
+            ),
+    );
+}
+
+/// GET /v1/trash
+async fn get_trash<S: TrashAPI>(user: User, stack: State<S>) -> KeikoResult<HttpResponse> {
+    let trash = stack.get_trash(&user).await?;
+    Ok(HttpResponse::Ok().json(&trash))
+}
+
+/// POST /v1/trash/courses/{course_id}/restore
+///
+/// Only the owner can restore a course. A trashed course has no members to check the role of,
+/// so `restore_course` checks ownership itself.
+async fn restore_course<S: TrashAPI>(
+    course_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let course = stack.restore_course(&user, &course_id).await?;
+    Ok(HttpResponse::Ok().json(&course))
+}
+
+/// POST /v1/trash/cards/{card_id}/restore
+async fn restore_card<S: TrashAPI + MemberAPI>(
+    card_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    member_api::require(&*stack, &user, CourseRef::Card(&card_id), Role::Editor).await?;
+    let card = stack.restore_card(&user, &card_id).await?;
+    Ok(HttpResponse::Ok().json(&card))
+}
+
+/// POST /v1/trash/quizzes/{quiz_id}/restore
+async fn restore_quiz<S: TrashAPI>(
+    quiz_id: Path<Uuid>,
+    user: User,
+    stack: State<S>,
+) -> KeikoResult<HttpResponse> {
+    let quiz = stack.restore_quiz(&user, &quiz_id).await?;
+    Ok(HttpResponse::Ok().json(&quiz))
+}
+
+#[cfg(test)]
+mod tests {
+    use ntex::http::StatusCode;
+
+    use crate::testing;
+    use crate::trash_api::TrashAPI;
+
+    #[ntex::test]
+    async fn deleted_cards_can_be_restored() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+        let card_id = card["id"].as_str().unwrap();
+        let path = format!("/v1/cards/id/{card_id}");
+
+        let (status, _) = app.delete(&path, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.get(&path, &alice).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, trash) = app.get("/v1/trash", &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(trash[0]["kind"], "card");
+        assert_eq!(trash[0]["id"], card["id"]);
+
+        let restore = format!("/v1/trash/cards/{card_id}/restore");
+        let (status, restored) = app.post(&restore, &alice, ()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(restored["question"], card["question"]);
+        let (status, _) = app.get(&path, &alice).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[ntex::test]
+    async fn deleted_courses_take_their_cards_along() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bobby").await;
+        let course = app.course(&alice, "C1").await;
+        let course_id = course["id"].as_str().unwrap();
+        app.card(&alice, "C1", "Basics", 1).await;
+
+        let (status, _) = app
+            .delete(&format!("/v1/courses/id/{course_id}"), &alice)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, cards) = app.get("/v1/cards", &alice).await;
+        assert!(cards.as_array().unwrap().is_empty());
+
+        let restore = format!("/v1/trash/courses/{course_id}/restore");
+        let (status, _) = app.post(&restore, &bob, ()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app.post(&restore, &alice, ()).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, cards) = app.get("/v1/cards", &alice).await;
+        assert_eq!(cards.as_array().unwrap().len(), 1);
+    }
+
+    #[ntex::test]
+    async fn purging_deletes_what_was_trashed_before() {
+        let app = testing::app().await;
+        let alice = app.login("alice").await;
+        app.course(&alice, "C1").await;
+        let card = app.card(&alice, "C1", "Basics", 1).await;
+        let card_id = card["id"].as_str().unwrap();
+        app.delete(&format!("/v1/cards/id/{card_id}"), &alice).await;
+
+        let before = chrono::Utc::now() - chrono::Duration::days(1);
+        assert_eq!(app.stack.purge(before).await.unwrap().cards, 0);
+        let (_, trash) = app.get("/v1/trash", &alice).await;
+        assert_eq!(trash.as_array().unwrap().len(), 1);
+
+        assert_eq!(app.stack.purge(chrono::Utc::now()).await.unwrap().cards, 1);
+        let restore = format!("/v1/trash/cards/{card_id}/restore");
+        let (status, _) = app.post(&restore, &alice, ()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{PurgeSummary, TrashAPI, TrashItem, TrashKind};
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::course_api::Course;
+use crate::member_api::Role;
+use crate::memory::{quiz_is_visible, InMemoryKeiko};
+use crate::quiz_api::Quiz;
+use crate::{KeikoError, KeikoResult};
+
+fn owns(course: &Course, user: &User) -> bool {
+    course.user_id == Some(user.id)
+}
+
+#[async_trait]
+impl TrashAPI for InMemoryKeiko {
+    async fn get_trash(&self, user: &User) -> KeikoResult<Vec<TrashItem>> {
+        let state = self.state();
+
+        let courses = state
+            .trashed_courses
+            .iter()
+            .filter(|t| owns(&t.row, user))
+            .map(|t| TrashItem {
+                kind: TrashKind::Course,
+                id: t.row.id,
+                course_id: t.row.id,
+                course_code: t.row.course_code.clone(),
+                title: t.row.name.clone(),
+                deleted_at: t.deleted_at,
+            });
+        let cards = state
+            .trashed_cards
+            .iter()
+            .filter(|t| {
+                state
+                    .course(&t.row.course_id)
+                    .and_then(|c| state.course_role(c, user))
+                    .is_some_and(|role| role >= Role::Editor)
+            })
+            .map(|t| TrashItem {
+                kind: TrashKind::Card,
+                id: t.row.id,
+                course_id: t.row.course_id,
+                course_code: t.row.course_code.clone(),
+                title: t.row.question.clone(),
+                deleted_at: t.deleted_at,
+            });
+        let quizzes = state
+            .trashed_quizzes
+            .iter()
+            .filter(|t| {
+                quiz_is_visible(&t.row.quiz, user) && state.course(&t.row.quiz.course_id).is_some()
+            })
+            .map(|t| TrashItem {
+                kind: TrashKind::Quiz,
+                id: t.row.quiz.id,
+                course_id: t.row.quiz.course_id,
+                course_code: t.row.quiz.course_code.clone(),
+                title: t.row.quiz.category.clone(),
+                deleted_at: t.deleted_at,
+            });
+
+        let mut trash: Vec<TrashItem> = courses.chain(cards).chain(quizzes).collect();
+        trash.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then(a.id.cmp(&b.id)));
+
+        Ok(trash)
+    }
+
+    async fn restore_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Course> {
+        let mut state = self.state();
+        let index = state
+            .trashed_courses
+            .iter()
+            .position(|t| t.row.id == *course_id && owns(&t.row, user))
+            .ok_or_else(|| KeikoError::not_found("course"))?;
+
+        let course = state.trashed_courses.remove(index).row;
+        state.courses.push(course.clone());
+
+        Ok(course)
+    }
+
+    async fn restore_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card> {
+        let mut state = self.state();
+        let index = state
+            .trashed_cards
+            .iter()
+            .position(|t| t.row.id == *card_id && state.card_is_visible(user, &t.row))
+            .ok_or_else(|| KeikoError::not_found("card"))?;
+
+        let card = state.trashed_cards.remove(index).row;
+        state.cards.push(card.clone());
+
+        Ok(card)
+    }
+
+    async fn restore_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz> {
+        let mut state = self.state();
+        let index = state
+            .trashed_quizzes
+            .iter()
+            .position(|t| {
+                t.row.quiz.id == *quiz_id
+                    && quiz_is_visible(&t.row.quiz, user)
+                    && state.course(&t.row.quiz.course_id).is_some()
+            })
+            .ok_or_else(|| KeikoError::not_found("quiz"))?;
+
+        let row = state.trashed_quizzes.remove(index).row;
+        let quiz = row.quiz.clone();
+        state.quizzes.push(row);
+
+        Ok(quiz)
+    }
+
+    async fn purge(&self, before: chrono::DateTime<chrono::Utc>) -> KeikoResult<PurgeSummary> {
+        let mut state = self.state();
+
+        let cards: Vec<Uuid> = state
+            .trashed_cards
+            .iter()
+            .filter(|t| t.deleted_at < before)
+            .map(|t| t.row.id)
+            .collect();
+        state.delete_cards(|c| cards.contains(&c.id));
+
+        let quizzes: Vec<Uuid> = state
+            .trashed_quizzes
+            .iter()
+            .filter(|t| t.deleted_at < before)
+            .map(|t| t.row.quiz.id)
+            .collect();
+        state.delete_quizzes(|q| quizzes.contains(&q.id));
+
+        let courses: Vec<Uuid> = state
+            .trashed_courses
+            .iter()
+            .filter(|t| t.deleted_at < before)
+            .map(|t| t.row.id)
+            .collect();
+        for course_id in &courses {
+            state.delete_course(course_id);
+        }
+
+        Ok(PurgeSummary {
+            courses: courses.len() as u64,
+            cards: cards.len() as u64,
+            quizzes: quizzes.len() as u64,
+        })
+    }
+}
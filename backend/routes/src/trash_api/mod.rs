@@ -0,0 +1,61 @@
+mod memory;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::course_api::Course;
+use crate::quiz_api::Quiz;
+use crate::KeikoResult;
+
+#[derive(
+    Serialize, Deserialize, sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum TrashKind {
+    Course,
+    Card,
+    Quiz,
+}
+
+/// A deleted course, card or quiz that can still be restored.
+#[derive(Serialize, Deserialize, FromRow, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: Uuid,
+    pub course_id: Uuid,
+    pub course_code: String,
+    /// Name of a course, question of a card, or category of a quiz.
+    pub title: String,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// How many trashed rows a purge deleted for good. Cards and quizzes a purged course takes with
+/// it are not counted unless they were trashed themselves.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PurgeSummary {
+    pub courses: u64,
+    pub cards: u64,
+    pub quizzes: u64,
+}
+
+#[async_trait]
+pub trait TrashAPI: Send + Sync + 'static {
+    /// What the user could restore: the courses they own, cards of courses they can edit and
+    /// their own quizzes, most recently deleted first. Cards and quizzes of a trashed course
+    /// come back with it, so they are not listed apart.
+    async fn get_trash(&self, user: &User) -> KeikoResult<Vec<TrashItem>>;
+    /// Takes a course the user owns out of the trash, along with its cards and quizzes.
+    async fn restore_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Course>;
+    async fn restore_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card>;
+    async fn restore_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz>;
+    /// Deletes for good everything put in the trash before `before`.
+    async fn purge(&self, before: chrono::DateTime<chrono::Utc>) -> KeikoResult<PurgeSummary>;
+}
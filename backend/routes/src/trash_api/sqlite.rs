@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use uuid::Uuid;
+
+use super::{PurgeSummary, TrashAPI, TrashItem};
+use crate::auth_api::User;
+use crate::card_api::Card;
+use crate::course_api::Course;
+use crate::quiz_api::Quiz;
+use crate::sqlite::KeikoSqlite;
+use crate::{KeikoError, KeikoResult};
+
+#[async_trait]
+impl TrashAPI for KeikoSqlite {
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn get_trash(&self, user: &User) -> KeikoResult<Vec<TrashItem>> {
+        sqlx::query_as::<_, TrashItem>(
+            r#"
+            SELECT 'course' AS kind, id, id AS course_id, course_code, name AS title, deleted_at
+            FROM courses
+            WHERE deleted_at IS NOT NULL AND user_id = $1
+            UNION ALL
+            SELECT 'card', id, course_id, course_code, question, deleted_at
+            FROM cards
+            WHERE deleted_at IS NOT NULL
+            AND course_id IN (
+                SELECT course_id FROM course_roles
+                WHERE user_id = $1 AND role IN ('owner', 'editor')
+            )
+            UNION ALL
+            SELECT 'quiz', q.id, q.course_id, q.course_code, q.category, q.deleted_at
+            FROM quizzes q
+            JOIN courses co ON co.id = q.course_id
+            WHERE q.deleted_at IS NOT NULL AND co.deleted_at IS NULL
+            AND q.user_id = $1
+            ORDER BY deleted_at DESC, id
+            "#,
+        )
+        .bind(user.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(KeikoError::from)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn restore_course(&self, user: &User, course_id: &Uuid) -> KeikoResult<Course> {
+        sqlx::query_as::<_, Course>(
+            r#"
+            UPDATE courses SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL AND user_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(course_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("course"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn restore_card(&self, user: &User, card_id: &Uuid) -> KeikoResult<Card> {
+        sqlx::query_as::<_, Card>(
+            r#"
+            UPDATE cards SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            AND course_id IN (SELECT course_id FROM course_roles WHERE user_id = $2)
+            RETURNING *
+            "#,
+        )
+        .bind(card_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("card"))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(user = %user.id))]
+    async fn restore_quiz(&self, user: &User, quiz_id: &Uuid) -> KeikoResult<Quiz> {
+        sqlx::query_as::<_, Quiz>(
+            r#"
+            UPDATE quizzes SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL AND user_id = $2
+            AND course_id IN (SELECT id FROM courses WHERE deleted_at IS NULL)
+            RETURNING *
+            "#,
+        )
+        .bind(quiz_id)
+        .bind(user.id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(KeikoError::from)?
+        .ok_or_else(|| KeikoError::not_found("quiz"))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn purge(&self, before: chrono::DateTime<chrono::Utc>) -> KeikoResult<PurgeSummary> {
+        let mut tx = self.pool.begin().await?;
+
+        // Cards and quizzes go first, so they are counted even when their course goes too.
+        let cards = sqlx::query("DELETE FROM cards WHERE deleted_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        let quizzes = sqlx::query("DELETE FROM quizzes WHERE deleted_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        let courses = sqlx::query("DELETE FROM courses WHERE deleted_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(PurgeSummary {
+            courses,
+            cards,
+            quizzes,
+        })
+    }
+}
@@ -14,9 +14,9 @@ import {
   AlertDialogTitle,
   AlertDialogTrigger,
 } from "@/components/ui/alert-dialog";
-import { CourseData, QuizData, getAllCourses, getAllQuizzes, setQuizCompletion } from "@/lib/api";
+import { CourseData, QuizData, getAllCourses, getAllQuizzes, logout, retakeQuiz } from "@/lib/api";
 import { useMutation, useQueryClient, useSuspenseQuery } from "@tanstack/react-query";
-import { Edit, FileChartLine, FolderClock, Home, Pen, Search, BookOpen } from "lucide-react";
+import { Edit, FileChartLine, FolderClock, Home, LogOut, Pen, Search, BookOpen } from "lucide-react";
 import Link from "next/link";
 import { useState, useMemo, useCallback, CSSProperties, MouseEvent } from "react";
 import { CourseDetails, CourseList, DashboardSidebar, QuizDetails, QuizList } from "./components";
@@ -73,13 +73,23 @@ export default function Dashboard() {
     queryFn: getAllQuizzes,
   });
 
-  const markCompletionMutation = useMutation({
-    mutationFn: (props: { quiz_id: string; is_completed: boolean }) =>
-      setQuizCompletion(props.quiz_id, props.is_completed),
-    onSuccess: () => {
+  const retakeMutation = useMutation({
+    mutationFn: (quiz_id: string) => retakeQuiz(quiz_id),
+    onSuccess: (quiz) => {
       queryClient.invalidateQueries({
         queryKey: ["quizzes"],
       });
+      setActiveView("ongoing");
+      setSelectedQuiz(quiz.id);
+    },
+    onError: (e) => toast({ description: e.message }),
+  }).mutate;
+
+  const logoutMutation = useMutation({
+    mutationFn: logout,
+    onSuccess: () => {
+      queryClient.clear();
+      window.location.assign("/login");
     },
     onError: (e) => toast({ description: e.message }),
   }).mutate;
@@ -150,6 +160,14 @@ export default function Dashboard() {
                 <Home className="w-6 h-6" />
               </Button>
             </Link>
+
+            <Button
+              className="p-2 rounded-full bg-inherit shadow-none hover:bg-red-50 text-red-500 transition-colors"
+              aria-label="Log out"
+              onClick={() => logoutMutation()}
+            >
+              <LogOut className="w-6 h-6" />
+            </Button>
           </div>
 
           {activeView === "courses" && (
@@ -225,21 +243,6 @@ export default function Dashboard() {
                     <span>Start Quiz</span>
                   </Button>
                 </Link>
-
-                <Link
-                  prefetch={selectedQuiz !== null}
-                  href={`/review/${selectedQuiz}`}
-                  style={disabledQuizStyle}
-                  onClick={disabledQuizOnClick}
-                >
-                  <Button
-                    className="bg-white text-red-500 border-red-500 border hover:border-zinc-500 hover:bg-zinc-500 hover:text-white flex items-center space-x-2"
-                    disabled={selectedQuiz === null}
-                  >
-                    <BookOpen width="20" height="20" />
-                    <span>Start Review</span>
-                  </Button>
-                </Link>
               </div>
             </div>
           )}
@@ -259,7 +262,7 @@ export default function Dashboard() {
                 </div>
               </div>
               <div className="mt-4 flex justify-between">
-                <div className="space-x-2 relative">
+                <div className="flex space-x-2 relative">
                   <AlertDialog>
                     <AlertDialogTrigger asChild>
                       <Button
@@ -267,7 +270,7 @@ export default function Dashboard() {
                         disabled={selectedQuiz === null}
                       >
                         <FolderClock className="mr-2 h-4 w-4" />
-                        Mark as On-going
+                        Retake Quiz
                       </Button>
                     </AlertDialogTrigger>
                     <AlertDialogContent>
@@ -275,11 +278,11 @@ export default function Dashboard() {
                         <div className="ping-container">
                           <div className="ping"></div>
                           <AlertDialogTitle className="font-gau-pop-magic text-red-500">
-                            CHANGE QUIZ STATUS
+                            RETAKE QUIZ
                           </AlertDialogTitle>
                         </div>
                         <AlertDialogDescription>
-                          Are you sure you want to change the status of this quiz to ongoing?
+                          This starts a fresh attempt at the quiz. The score of this one is kept.
                         </AlertDialogDescription>
                       </AlertDialogHeader>
                       <AlertDialogFooter>
@@ -288,8 +291,8 @@ export default function Dashboard() {
                         </AlertDialogCancel>
                         <AlertDialogAction
                           onClick={() => {
-                            if (quizzes.find((q) => q.id === selectedQuiz)?.is_completed) {
-                              markCompletionMutation({ quiz_id: selectedQuiz ?? "", is_completed: false });
+                            if (selectedQuiz !== null) {
+                              retakeMutation(selectedQuiz);
                             }
                           }}
                           className="bg-white text-red-500 border border-red-500 hover:border-red-500 hover:bg-red-500 hover:text-white"
@@ -299,6 +302,20 @@ export default function Dashboard() {
                       </AlertDialogFooter>
                     </AlertDialogContent>
                   </AlertDialog>
+                  <Link
+                    prefetch={selectedQuiz !== null}
+                    href={`/review/${selectedQuiz}`}
+                    style={disabledQuizStyle}
+                    onClick={disabledQuizOnClick}
+                  >
+                    <Button
+                      className="bg-white text-red-500 border-red-500 border hover:border-zinc-500 hover:bg-zinc-500 hover:text-white flex items-center space-x-2"
+                      disabled={selectedQuiz === null}
+                    >
+                      <BookOpen width="20" height="20" />
+                      <span>Start Review</span>
+                    </Button>
+                  </Link>
                 </div>
               </div>
             </div>
@@ -0,0 +1,125 @@
+"use client";
+
+import { Suspense } from "react";
+import { useMutation } from "@tanstack/react-query";
+import { useForm } from "react-hook-form";
+import { z } from "zod";
+import { zodResolver } from "@hookform/resolvers/zod";
+import { useSearchParams } from "next/navigation";
+import { useTransitionRouter } from "next-view-transitions";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Form, FormControl, FormField, FormItem, FormLabel, FormMessage } from "@/components/ui/form";
+import { useToast } from "@/hooks/use-toast";
+import { getQueryClient } from "../query-client";
+import { login, register } from "@/lib/api";
+
+const formSchema = z.object({
+  username: z
+    .string()
+    .trim()
+    .min(3, "Username must be at least 3 characters.")
+    .max(32, "Username must be at most 32 characters."),
+  password: z.string().min(8, "Password must be at least 8 characters."),
+});
+
+type Credentials = z.infer<typeof formSchema>;
+
+function Login() {
+  const router = useTransitionRouter();
+  const searchParams = useSearchParams();
+  const { toast } = useToast();
+
+  const form = useForm<Credentials>({
+    resolver: zodResolver(formSchema),
+    defaultValues: {
+      username: "",
+      password: "",
+    },
+  });
+
+  const loginMutation = useMutation({
+    mutationFn: async ({ credentials, isNew }: { credentials: Credentials; isNew: boolean }) => {
+      if (isNew) await register(credentials.username, credentials.password);
+      return login(credentials.username, credentials.password);
+    },
+    onError: (e) => toast({ description: e.message }),
+    onSuccess: () => {
+      // Anything fetched before logging in was answered for nobody.
+      getQueryClient().clear();
+      const next = searchParams.get("next");
+      router.push(next?.startsWith("/") ? next : "/dashboard?view=courses");
+    },
+  });
+
+  return (
+    <div className="flex flex-col items-center justify-center min-h-screen min-w-fit bg-gray-50">
+      <h1 className="text-7xl font-bold mb-8 font-gau-pop-magic text-red-500 animate-bounceIn">Keiko!</h1>
+      <Form {...form}>
+        <form
+          onSubmit={form.handleSubmit((credentials) => loginMutation.mutate({ credentials, isNew: false }))}
+          className="flex flex-col space-y-4 w-80 max-w-xs"
+        >
+          <FormField
+            control={form.control}
+            name="username"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel htmlFor="username" className="text-zinc-500">
+                  Username
+                </FormLabel>
+                <FormControl>
+                  <Input {...field} autoComplete="username" className="bg-white text-zinc-500 border-zinc-500" />
+                </FormControl>
+                <FormMessage>{form.formState.errors.username?.message}</FormMessage>
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={form.control}
+            name="password"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel htmlFor="password" className="text-zinc-500">
+                  Password
+                </FormLabel>
+                <FormControl>
+                  <Input
+                    {...field}
+                    type="password"
+                    autoComplete="current-password"
+                    className="bg-white text-zinc-500 border-zinc-500"
+                  />
+                </FormControl>
+                <FormMessage>{form.formState.errors.password?.message}</FormMessage>
+              </FormItem>
+            )}
+          />
+          <Button
+            type="submit"
+            disabled={loginMutation.isPending}
+            className="w-full bg-red-500 text-white hover:bg-white hover:text-red-500 border border-red-500 font-gau-pop-magic"
+          >
+            ⟡ Log in
+          </Button>
+          <Button
+            type="button"
+            disabled={loginMutation.isPending}
+            onClick={form.handleSubmit((credentials) => loginMutation.mutate({ credentials, isNew: true }))}
+            className="w-full bg-white text-red-500 hover:bg-red-500 hover:text-white border border-red-500 font-gau-pop-magic"
+          >
+            ✦ Create account
+          </Button>
+        </form>
+      </Form>
+    </div>
+  );
+}
+
+export default function LoginPage() {
+  return (
+    <Suspense>
+      <Login />
+    </Suspense>
+  );
+}
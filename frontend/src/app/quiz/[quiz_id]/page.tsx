@@ -1,5 +1,5 @@
 import { dehydrate, HydrationBoundary } from "@tanstack/react-query";
-import { getQuiz, getCardsByQuizId } from "@/lib/api";
+import { getQuiz, getQuizQuestion } from "@/lib/api";
 import Quiz from "./../quiz";
 import { getQueryClient } from "@/app/query-client";
 
@@ -17,8 +17,8 @@ export default async function QuizPage(props: QuizPageProps) {
   });
 
   queryClient.prefetchQuery({
-    queryKey: ["cards", quiz_id],
-    queryFn: () => getCardsByQuizId(quiz_id),
+    queryKey: ["question", quiz_id],
+    queryFn: () => getQuizQuestion(quiz_id),
   });
 
   return (
@@ -5,18 +5,10 @@ import { ErrorSkeleton } from "@/components/status";
 import { Button } from "@/components/ui/button";
 import { QuizCard } from "@/components/cards";
 import { useToast } from "@/hooks/use-toast";
-import {
-  CardData,
-  getCardsByQuizId,
-  getQuiz,
-  setQuizCompletion,
-  setQuizCorrectCount,
-  setQuizCurrentIndex,
-  setQuizHintUsed,
-} from "@/lib/api";
+import { getQuiz, getQuizQuestion, gradeAnswer, requestHint } from "@/lib/api";
 import { useQuery, useMutation, useQueryClient, useSuspenseQuery } from "@tanstack/react-query";
 import { Lightbulb } from "lucide-react";
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { Link, useTransitionRouter } from "next-view-transitions";
 import {
   AlertDialog,
@@ -40,12 +32,12 @@ export default function Quiz(props: QuizProps) {
   const [selectedAnswer, setSelectedAnswer] = useState("");
   const [isHintUsed, setIsHintUsed] = useState(false);
   const [answerOptions, setAnswerOptions] = useState<string[]>([]);
+  const [correctAnswer, setCorrectAnswer] = useState("");
   const [message, setMessage] = useState("");
   const [isSubmitted, setIsSubmitted] = useState(false);
   const [showCompletionDialog, setShowCompletionDialog] = useState(false);
   const [showExitDialog, setShowExitDialog] = useState(false);
-  const [currentCardIndex, setCurrentCardIndex] = useState(0);
-  const [correctCount, setCorrectCount] = useState(0);
+  const shownAt = useRef(Date.now());
   const { toast } = useToast();
 
   const { data: quiz } = useSuspenseQuery({
@@ -53,75 +45,63 @@ export default function Quiz(props: QuizProps) {
     queryFn: () => getQuiz(quiz_id),
   });
 
-  const { data: cards } = useSuspenseQuery({
-    queryKey: ["cards", quiz_id],
-    queryFn: () => getCardsByQuizId(quiz_id),
+  const { data: question } = useQuery({
+    queryKey: ["question", quiz_id],
+    queryFn: () => getQuizQuestion(quiz_id),
+    enabled: !quiz.is_completed,
   });
 
   useEffect(() => {
-    setCurrentCardIndex(quiz?.current_index ?? 0);
-    setCorrectCount(quiz?.correct_count ?? 0);
-    setIsHintUsed(quiz?.hint_used ?? false);
-    if (cards.length > 0 && quiz) generateAnswerOptions(cards, quiz.current_index);
-
-    return () => {
-      queryClient.invalidateQueries({ queryKey: [`quiz_${quiz_id}`, `cards_${quiz_id}`] });
-    };
-  }, [cards, quiz, queryClient, quiz_id]);
-
-  const setCurrentIndexMutation = useMutation({
-    mutationFn: (index: number) => setQuizCurrentIndex(quiz_id, index),
-  }).mutate;
-
-  const setCorrectCountMutation = useMutation({
-    mutationFn: (correct_count: number) => setQuizCorrectCount(quiz_id, correct_count),
-  }).mutate;
-
-  const setHintUsedMutation = useMutation({
-    mutationFn: (hint_used: boolean) => setQuizHintUsed(quiz_id, hint_used),
-  }).mutate;
-
-  const setFinishedMutation = useMutation({
-    mutationFn: (is_completed: boolean) => setQuizCompletion(quiz_id, is_completed),
-    onSuccess: () => {
-      setShowCompletionDialog(false);
+    setAnswerOptions(question?.options ?? []);
+    setIsHintUsed(false);
+    shownAt.current = Date.now();
+  }, [question]);
+
+  const gradeMutation = useMutation({
+    mutationFn: () =>
+      gradeAnswer(quiz_id, {
+        card_id: question!.card_id,
+        given_answer: selectedAnswer,
+        elapsed_ms: Date.now() - shownAt.current,
+      }),
+    onSuccess: (graded) => {
+      setMessage(graded.correct ? "Correct!" : "Incorrect answer!");
+      setCorrectAnswer(graded.correct_answer);
+      setIsSubmitted(true);
+      queryClient.setQueryData(["quiz", quiz_id], { ...quiz, ...graded.quiz });
+    },
+    onError: () => {
+      toast({ description: "Could not submit the answer, please try again.", variant: "destructive" });
     },
-  }).mutate;
+  });
 
-  function generateAnswerOptions(cards: CardData[], idx: number) {
-    const correctAnswer = cards[idx].answer;
-    let options = cards.map((card) => card.answer);
-    options = Array.from(new Set(options));
-    if (!options.includes(correctAnswer)) {
-      options.push(correctAnswer);
-    }
-    options = options.sort(() => 0.5 - Math.random());
-    setAnswerOptions(options);
-  }
+  const hintMutation = useMutation({
+    mutationFn: () => requestHint(quiz_id, "eliminate_option"),
+    onSuccess: (hint) => {
+      setAnswerOptions(answerOptions.filter((answer) => answer !== hint.revealed));
+      setIsHintUsed(true);
+      toast({
+        description: `One incorrect answer has been removed! ${hint.hints_remaining} hints left.`,
+      });
+    },
+    onError: () => {
+      toast({ description: "No hint is available for this question!", variant: "destructive" });
+    },
+  });
 
   function handleSubmit() {
-    if (selectedAnswer === cards[currentCardIndex].answer) {
-      setMessage("Correct!");
-      setCorrectCount(correctCount + 1);
-      setCorrectCountMutation(correctCount);
-    } else {
-      setMessage("Incorrect answer!");
-    }
-    setIsSubmitted(true);
+    gradeMutation.mutate();
   }
 
   function handleNext() {
-    if (currentCardIndex == cards.length - 1) {
+    if (quiz.is_completed) {
       setShowCompletionDialog(true);
     } else {
-      setCurrentCardIndex(currentCardIndex + 1);
-      setCurrentIndexMutation(currentCardIndex);
       setSelectedAnswer("");
       setMessage("");
+      setCorrectAnswer("");
       setIsSubmitted(false);
-      setIsHintUsed(false);
-      setHintUsedMutation(false);
-      generateAnswerOptions(cards, currentCardIndex + 1);
+      queryClient.invalidateQueries({ queryKey: ["question", quiz_id] });
     }
   }
 
@@ -133,33 +113,13 @@ export default function Quiz(props: QuizProps) {
       });
       return;
     }
-
-    const correctAnswer = cards[currentCardIndex].answer;
-    const incorrectAnswers = answerOptions.filter((answer) => answer !== correctAnswer);
-
-    if (incorrectAnswers.length > 0) {
-      // Randomly select one incorrect answer to remove
-      const randomIndex = Math.floor(Math.random() * incorrectAnswers.length);
-      const answerToRemove = incorrectAnswers[randomIndex];
-
-      // Create new array without the removed answer
-      const newOptions = answerOptions.filter((answer) => answer !== answerToRemove);
-      setAnswerOptions(newOptions);
-      setIsHintUsed(true);
-      setHintUsedMutation(true);
-
-      toast({
-        description: "One incorrect answer has been removed!",
-      });
-    }
+    hintMutation.mutate();
   }
 
   function handleExit() {
     setShowExitDialog(true);
   }
 
-  const currentCard = cards[currentCardIndex];
-
   return (
     <div className="min-h-screen bg-gray-50 overflow-y-auto">
       <header className="sticky top-0 z-10 bg-transparent flex justify-between items-center p-4">
@@ -176,7 +136,7 @@ export default function Quiz(props: QuizProps) {
               isHintUsed ? "bg-zinc-400" : "bg-red-500 hover:bg-zinc-500"
             } hover:text-white`}
             onClick={handleHint}
-            disabled={isHintUsed || isSubmitted}
+            disabled={isHintUsed || isSubmitted || !question}
           >
             <Lightbulb className="h-5 w-5" />
           </Button>
@@ -186,20 +146,20 @@ export default function Quiz(props: QuizProps) {
         </div>
       </header>
       <main className="container mx-auto px-4 py-8">
-        {(!cards || cards.length === 0) && (
+        {quiz.card_count === 0 && (
           <div className="text-center py-8 font-gau-pop-magic text-red-500 font-bold">
             NO CARDS AVAILABLE FOR THIS COURSE.
           </div>
         )}
 
-        {!quiz?.is_completed && (
+        {question && (
           <QuizCard
-            question={currentCard.question}
+            question={question.question}
             answerOptions={answerOptions}
             selectedAnswer={selectedAnswer}
             setSelectedAnswer={setSelectedAnswer}
             isSubmitted={isSubmitted}
-            correctAnswer={currentCard.answer}
+            correctAnswer={correctAnswer}
             message={message}
             onSubmit={handleSubmit}
             onNext={handleNext}
@@ -213,20 +173,19 @@ export default function Quiz(props: QuizProps) {
           <AlertDialogHeader>
             <AlertDialogTitle className="font-gau-pop-magic text-red-500">QUIZ COMPLETE!</AlertDialogTitle>
             <AlertDialogDescription className="text-zinc-500">
-              You&apos;ve reached the end of the quiz. Click mark as complete to exit!
+              You&apos;ve reached the end of the quiz with {quiz.correct_count} of {quiz.card_count} correct!
             </AlertDialogDescription>
           </AlertDialogHeader>
           <AlertDialogFooter>
             <AlertDialogAction
               onClick={() => {
-                setFinishedMutation(true);
-                setCurrentIndexMutation(0);
+                setShowCompletionDialog(false);
                 queryClient.invalidateQueries({ queryKey: ["quizzes"] });
-                router.push("/dashboard?view=ongoing");
+                router.push("/dashboard?view=completed");
               }}
               className="bg-red-500 text-white hover:bg-red-600"
             >
-              Mark as Complete
+              Back to Dashboard
             </AlertDialogAction>
           </AlertDialogFooter>
         </AlertDialogContent>
@@ -37,6 +37,76 @@ export interface QuizData {
   progress: number;
 }
 
+export interface QuizQuestionData {
+  quiz_id: string;
+  card_id: string;
+  index: number;
+  card_count: number;
+  question: string;
+  options: string[];
+}
+
+export interface GradedAnswerData {
+  correct: boolean;
+  correct_answer: string;
+  quiz: QuizData;
+}
+
+export type HintKind = "eliminate_option" | "first_letter" | "card_hint";
+
+export interface RevealedHintData {
+  id: string;
+  quiz_id: string;
+  card_id: string;
+  kind: HintKind;
+  revealed: string;
+  used_at: string;
+  hints_remaining: number;
+}
+
+export interface CardRevisionData {
+  card_id: string;
+  revision: number;
+  question: string;
+  answer: string;
+  category: string;
+  hint: string | null;
+  user_id: string | null;
+  username: string | null;
+  created_at: string;
+}
+
+export interface CardDiffData {
+  card_id: string;
+  from: CardRevisionData;
+  to: CardRevisionData;
+  fields: {
+    field: "question" | "answer" | "category" | "hint";
+    changes: { kind: "equal" | "delete" | "insert"; text: string }[];
+  }[];
+}
+
+export interface UserData {
+  id: string;
+  username: string;
+  created_at: string;
+}
+
+export interface SessionData {
+  token: string;
+  user: UserData;
+  expires_at: string;
+}
+
+export interface TrashData {
+  kind: "course" | "card" | "quiz";
+  id: string;
+  course_id: string;
+  course_code: string;
+  title: string;
+  deleted_at: string;
+}
+
 const ax = axios.create({
   baseURL: `http://${process.env.API_HOST || "62.146.233.89"}:${process.env.API_PORT || "1107"}/api/v1`,
   headers: {
@@ -45,6 +115,49 @@ const ax = axios.create({
   },
 });
 
+// The session token lives in the browser only, so requests made while rendering on the server
+// go out without one and are answered 401.
+const TOKEN_KEY = "keiko-token";
+
+export function getToken(): string | null {
+  return typeof window === "undefined" ? null : window.localStorage.getItem(TOKEN_KEY);
+}
+
+ax.interceptors.request.use((config) => {
+  const token = getToken();
+  if (token) config.headers.Authorization = `Bearer ${token}`;
+  return config;
+});
+
+ax.interceptors.response.use(undefined, (error) => {
+  if (axios.isAxiosError(error) && error.response?.status === 401 && typeof window !== "undefined") {
+    window.localStorage.removeItem(TOKEN_KEY);
+    if (window.location.pathname !== "/login") {
+      window.location.assign(`/login?next=${encodeURIComponent(window.location.pathname + window.location.search)}`);
+    }
+  }
+  return Promise.reject(error);
+});
+
+export async function register(username: string, password: string): Promise<UserData> {
+  return ax.post<UserData>("/auth/register", { username, password }).then((r): UserData => r.data);
+}
+
+export async function login(username: string, password: string): Promise<SessionData> {
+  return ax.post<SessionData>("/auth/login", { username, password }).then((r): SessionData => {
+    window.localStorage.setItem(TOKEN_KEY, r.data.token);
+    return r.data;
+  });
+}
+
+export async function logout(): Promise<void> {
+  return ax.post("/auth/logout").then(() => window.localStorage.removeItem(TOKEN_KEY));
+}
+
+export async function getMe(): Promise<UserData> {
+  return ax.get<UserData>("/auth/me").then((r): UserData => r.data);
+}
+
 export async function getAllCards(): Promise<CardData[]> {
   return ax.get<CardData[]>("/cards").then((r): CardData[] => r.data);
 }
@@ -65,6 +178,20 @@ export async function deleteCard(id: string): Promise<CardData> {
   return ax.delete<CardData>(`/cards/id/${id}`).then((r): CardData => r.data);
 }
 
+export async function getCardRevisions(id: string): Promise<CardRevisionData[]> {
+  return ax.get<CardRevisionData[]>(`/cards/id/${id}/revisions`).then((r): CardRevisionData[] => r.data);
+}
+
+export async function diffCardRevisions(id: string, from?: number, to?: number): Promise<CardDiffData> {
+  return ax
+    .get<CardDiffData>(`/cards/id/${id}/revisions/diff`, { params: { from, to } })
+    .then((r): CardDiffData => r.data);
+}
+
+export async function revertCard(id: string, revision: number): Promise<CardData> {
+  return ax.post<CardData>(`/cards/id/${id}/revisions/${revision}/revert`).then((r): CardData => r.data);
+}
+
 export async function getCardsByQuizId(quiz_id: string): Promise<CardData[]> {
   return ax.get<CardData[]>(`/cards/quiz/${quiz_id}`).then((r): CardData[] => r.data);
 }
@@ -115,34 +242,51 @@ export async function addQuiz(quiz: Pick<QuizData, "course_code" | "category">):
   return ax.post<QuizData>("/quiz", { ...quiz }).then((r): QuizData => r.data);
 }
 
-export async function updateQuiz(
-  quiz: Pick<QuizData, "id" | "current_index" | "correct_count" | "is_completed">,
-): Promise<QuizData> {
-  return ax.put<QuizData>("/quiz", { ...quiz }).then((r): QuizData => r.data);
-}
-
 export async function deleteQuiz(id: string): Promise<QuizData> {
   return ax.delete<QuizData>(`/quiz/id/${id}`).then((r): QuizData => r.data);
 }
 
-export async function setQuizCompletion(id: string, is_completed: boolean): Promise<QuizData> {
-  return ax.patch<QuizData>("/quiz", { id, is_completed }).then((r): QuizData => r.data);
+export async function retakeQuiz(id: string): Promise<QuizData> {
+  return ax.post<QuizData>(`/quiz/id/${id}/retake`).then((r): QuizData => r.data);
+}
+
+export async function getQuizAttempts(id: string): Promise<QuizData[]> {
+  return ax.get<QuizData[]>(`/quiz/id/${id}/attempts`).then((r): QuizData[] => r.data);
 }
 
-export async function setQuizCurrentIndex(id: string, index: number): Promise<QuizData> {
-  return ax.patch<QuizData>(`/quiz/id/${id}/index`, { current_index: index }).then((r): QuizData => r.data);
+export async function getQuizQuestion(id: string): Promise<QuizQuestionData> {
+  return ax.get<QuizQuestionData>(`/quiz/id/${id}/question`).then((r): QuizQuestionData => r.data);
 }
 
-export async function setQuizCorrectCount(id: string, count: number): Promise<QuizData> {
-  return ax.patch<QuizData>(`/quiz/id/${id}/correct`, { correct_count: count }).then((r): QuizData => r.data);
+export async function gradeAnswer(
+  id: string,
+  answer: { card_id: string; given_answer: string; elapsed_ms: number },
+): Promise<GradedAnswerData> {
+  return ax.post<GradedAnswerData>(`/quiz/id/${id}/grade`, { ...answer }).then((r): GradedAnswerData => r.data);
 }
 
-export async function setQuizHintUsed(id: string, hint_used: boolean): Promise<QuizData> {
-  return ax.patch<QuizData>(`/quiz/id/${id}/hint`, { hint_used }).then((r): QuizData => r.data);
+export async function requestHint(id: string, kind: HintKind): Promise<RevealedHintData> {
+  return ax.post<RevealedHintData>(`/quiz/id/${id}/hints`, { kind }).then((r): RevealedHintData => r.data);
 }
 
 export async function renameQuiz(course_code: string, old_name: string, new_name: string) {
-  return ax.post<void>(`/quiz/rename/${course_code}`, { old: old_name, new: new_name }).then((_) => {});
+  return ax.post<void>(`/categories/rename/${course_code}`, { old: old_name, new: new_name }).then((_) => {});
+}
+
+export async function getTrash(): Promise<TrashData[]> {
+  return ax.get<TrashData[]>("/trash").then((r): TrashData[] => r.data);
+}
+
+export async function restoreCourse(id: string): Promise<CourseData> {
+  return ax.post<CourseData>(`/trash/courses/${id}/restore`).then((r): CourseData => r.data);
+}
+
+export async function restoreCard(id: string): Promise<CardData> {
+  return ax.post<CardData>(`/trash/cards/${id}/restore`).then((r): CardData => r.data);
+}
+
+export async function restoreQuiz(id: string): Promise<QuizData> {
+  return ax.post<QuizData>(`/trash/quizzes/${id}/restore`).then((r): QuizData => r.data);
 }
 
 export function ratingFor(quiz: QuizData) {